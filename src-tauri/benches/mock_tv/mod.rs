@@ -0,0 +1,94 @@
+//! A stand-in for the TV on localhost: an SSAP socket that registers any client and answers
+//! every request, and a pointer input socket that takes button presses. Enough for
+//! `TvConnection` to connect, send commands and reconnect without a TV on the network.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Key the mock issues on registration; connecting with it skips the pairing prompt.
+pub const CLIENT_KEY: &str = "mock-client-key";
+
+pub struct MockTv {
+    /// SSAP port; set it as `TvConnection::port` and connect to 127.0.0.1 without SSL.
+    pub port: u16,
+}
+
+impl MockTv {
+    /// Start both sockets on free ports. Needs a running tokio runtime; they serve until it
+    /// shuts down.
+    pub async fn start() -> MockTv {
+        let ssap = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind SSAP socket");
+        let input = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind input socket");
+        let port = ssap.local_addr().unwrap().port();
+        let input_url = format!("ws://{}", input.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = ssap.accept().await {
+                tokio::spawn(serve_ssap(stream, input_url.clone()));
+            }
+        });
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = input.accept().await {
+                tokio::spawn(serve_input(stream));
+            }
+        });
+        MockTv { port }
+    }
+}
+
+async fn serve_ssap(stream: TcpStream, input_url: String) {
+    let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    while let Some(Ok(message)) = ws.next().await {
+        let Message::Text(text) = message else {
+            continue;
+        };
+        let Ok(frame) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+        let reply = match frame["type"].as_str() {
+            Some("register") => json!({
+                "type": "registered",
+                "id": frame["id"],
+                "payload": { "client-key": CLIENT_KEY },
+            }),
+            Some("request") => json!({
+                "type": "response",
+                "id": frame["id"],
+                "payload": reply_payload(frame["uri"].as_str().unwrap_or(""), &input_url),
+            }),
+            _ => continue,
+        };
+        if ws.send(Message::text(reply.to_string())).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Payload of a successful reply to `uri`.
+fn reply_payload(uri: &str, input_url: &str) -> Value {
+    match uri {
+        "ssap://com.webos.service.networkinput/getPointerInputSocket" => {
+            json!({ "returnValue": true, "socketPath": input_url })
+        }
+        "ssap://com.webos.service.tvpower/power/getPowerState" => {
+            json!({ "returnValue": true, "state": "Active" })
+        }
+        _ => json!({ "returnValue": true }),
+    }
+}
+
+/// Button presses need no answer; read them until the client goes away.
+async fn serve_input(stream: TcpStream) {
+    let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    while let Some(Ok(_)) = ws.next().await {}
+}
@@ -0,0 +1,125 @@
+//! Command pipeline benchmarks against the mock TV: button-press latency through the
+//! connection manager, command throughput under concurrent load, and reconnect time. Run
+//! with `cargo bench --bench pipeline` to compare connection layer changes before and after.
+
+mod mock_tv;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures_util::future::join_all;
+use lgtv_tray_remote::config::CommandQueueConfig;
+use lgtv_tray_remote::connection::ConnectionManager;
+use mock_tv::MockTv;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+
+/// Concurrent commands per throughput sample.
+const LOADS: &[usize] = &[1, 8, 64];
+
+async fn connect(manager: &ConnectionManager, port: u16) {
+    manager
+        .connect(move |tv| {
+            Box::pin(async move {
+                tv.port = Some(port);
+                tv.start_connect(
+                    "Mock TV",
+                    "127.0.0.1",
+                    Some(mock_tv::CLIENT_KEY),
+                    false,
+                    None,
+                    None,
+                )
+                .await
+            })
+        })
+        .await
+        .expect("connect to the mock TV");
+}
+
+/// A manager connected to `tv`, with queue limits that don't reject or space out the load.
+fn connected_manager(rt: &Runtime, tv: &MockTv) -> ConnectionManager {
+    let manager = ConnectionManager::spawn();
+    manager.set_limits(CommandQueueConfig {
+        max_in_flight: 1024,
+        spacing_ms: 0,
+        coalesce_volume: false,
+    });
+    rt.block_on(connect(&manager, tv.port));
+    manager
+}
+
+fn button_latency(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let tv = rt.block_on(MockTv::start());
+    let manager = connected_manager(&rt, &tv);
+
+    c.bench_function("button_press", |b| {
+        b.to_async(&rt).iter(|| async {
+            manager
+                .command(|tv| {
+                    Box::pin(async move {
+                        tv.ensure_active()?;
+                        tv.send_button("UP").await
+                    })
+                })
+                .await
+                .unwrap()
+        })
+    });
+}
+
+fn concurrent_throughput(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let tv = rt.block_on(MockTv::start());
+    let manager = connected_manager(&rt, &tv);
+
+    let mut group = c.benchmark_group("concurrent_commands");
+    for &load in LOADS {
+        group.throughput(Throughput::Elements(load as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(load), &load, |b, &load| {
+            b.to_async(&rt).iter(|| async {
+                let commands = (0..load).map(|_| {
+                    manager.command(|tv| {
+                        Box::pin(async move {
+                            tv.ensure_active()?;
+                            tv.volume_up().await
+                        })
+                    })
+                });
+                for result in join_all(commands).await {
+                    result.unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn reconnect(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let tv = rt.block_on(MockTv::start());
+    let manager = connected_manager(&rt, &tv);
+    let port = tv.port;
+
+    // Only the connect is timed; the disconnect before it is setup
+    c.bench_function("reconnect", |b| {
+        b.to_async(&rt).iter_custom(|iters| {
+            let manager = manager.clone();
+            async move {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    manager
+                        .run(|tv| Box::pin(async move { tv.disconnect().await }))
+                        .await
+                        .unwrap();
+                    let start = Instant::now();
+                    connect(&manager, port).await;
+                    total += start.elapsed();
+                }
+                total
+            }
+        })
+    });
+}
+
+criterion_group!(benches, button_latency, concurrent_throughput, reconnect);
+criterion_main!(benches);
@@ -0,0 +1,156 @@
+//! Bookkeeping around action execution: which action ids exist, where an action was
+//! triggered from, the "action-executed" event emitted once it finishes, and macro recording.
+
+use crate::buttons;
+use crate::config::{Config, MacroStep};
+use crate::error::AppError;
+use crate::tv::{EnergySavingLevel, SoundMode};
+use serde::Serialize;
+use std::time::Instant;
+
+/// Action ids with a fixed meaning (see `run_action_impl` and `run_tv_action`). Parameterized
+/// ones (`button_<name>`, `input_<id>`, `macro_<name>`, ...) are checked by `is_known`.
+pub const BUILTIN_ACTIONS: &[&str] = &[
+    "power_on",
+    "power_off",
+    "power_toggle",
+    "wake_streaming_device",
+    "volume_up",
+    "volume_down",
+    "mute",
+    "mute_toggle",
+    "unmute",
+    "up",
+    "down",
+    "left",
+    "right",
+    "enter",
+    "back",
+    "home",
+    "rewind",
+    "play",
+    "pause",
+    "stop",
+    "fast_forward",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "info",
+    "exit",
+    "menu",
+    "qmenu",
+    "guide",
+    "list",
+    "record",
+    "screen_off",
+    "screen_on",
+    "show_tv_keypad",
+    "pixel_cleaning",
+];
+
+/// Whether `run_action` knows `id`. Macros, scenes and button aliases must exist in `config`;
+/// apps and inputs depend on the TV and only need a non-empty id.
+pub fn is_known(id: &str, config: &Config) -> bool {
+    if BUILTIN_ACTIONS.contains(&id) {
+        return true;
+    }
+    let Some((kind, rest)) = id.split_once('_') else {
+        return false;
+    };
+    match kind {
+        "macro" => config.macros.contains_key(rest),
+        "scene" => config.scenes.contains_key(rest),
+        "button" => buttons::is_known(&buttons::resolve(rest, &config.button_aliases)),
+        "app" | "input" => !rest.trim().is_empty(),
+        _ => {
+            if let Some(level) = id.strip_prefix("energy_saving_") {
+                EnergySavingLevel::parse(level).is_some()
+            } else if let Some(mode) = id.strip_prefix("sound_mode_") {
+                SoundMode::parse(mode).is_some()
+            } else {
+                false
+            }
+        }
+    }
+}
+
+/// What triggered an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionSource {
+    /// The remote window (buttons, in-window keyboard shortcuts).
+    Ui,
+    /// A global hotkey, usually while the window is hidden.
+    Shortcut,
+    /// The tray icon.
+    Tray,
+    /// A step of a macro being replayed.
+    Macro,
+}
+
+impl ActionSource {
+    /// Same name as in the serialized form, e.g. "shortcut".
+    pub fn name(self) -> &'static str {
+        match self {
+            ActionSource::Ui => "ui",
+            ActionSource::Shortcut => "shortcut",
+            ActionSource::Tray => "tray",
+            ActionSource::Macro => "macro",
+        }
+    }
+}
+
+/// Payload of the "action-executed" event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionExecuted {
+    pub id: String,
+    pub source: ActionSource,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl ActionExecuted {
+    pub fn new(id: &str, source: ActionSource, result: &Result<(), AppError>) -> Self {
+        Self {
+            id: id.to_string(),
+            source,
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Collects actions while a macro is being recorded.
+pub struct MacroRecorder {
+    steps: Vec<MacroStep>,
+    last: Instant,
+}
+
+impl MacroRecorder {
+    pub fn new() -> Self {
+        Self {
+            steps: Vec::new(),
+            last: Instant::now(),
+        }
+    }
+
+    /// Append an action with the time since the previous one (or since recording started).
+    pub fn push(&mut self, action: &str) {
+        let now = Instant::now();
+        self.steps.push(MacroStep {
+            action: action.to_string(),
+            delay_ms: now.duration_since(self.last).as_millis() as u64,
+        });
+        self.last = now;
+    }
+
+    pub fn finish(self) -> Vec<MacroStep> {
+        let mut steps = self.steps;
+        // Time spent before the first action is just the user getting started
+        if let Some(first) = steps.first_mut() {
+            first.delay_ms = 0;
+        }
+        steps
+    }
+}
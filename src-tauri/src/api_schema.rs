@@ -0,0 +1,137 @@
+//! Machine-readable description of the status and command shapes, for integrations such as a
+//! Home Assistant custom component. Entity ids are stable slugs of the configured names.
+
+use crate::config::{Config, StreamingDeviceConfig};
+use serde_json::{json, Value};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// "Living Room TV" -> "living_room_tv"
+pub fn entity_slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('_') {
+            slug.push('_');
+        }
+    }
+    let slug = slug.trim_matches('_').to_string();
+    if slug.is_empty() {
+        "tv".to_string()
+    } else {
+        slug
+    }
+}
+
+fn streaming_kind(device: &StreamingDeviceConfig) -> &'static str {
+    match device {
+        StreamingDeviceConfig::Wol { .. } => "wol",
+        StreamingDeviceConfig::Adb { .. } => "adb",
+        StreamingDeviceConfig::Roku { .. } => "roku",
+    }
+}
+
+pub fn build(config: &Config) -> Value {
+    let mut tv_names: Vec<&String> = config.tvs.keys().collect();
+    tv_names.sort();
+    let tvs: Vec<Value> = tv_names
+        .into_iter()
+        .map(|name| {
+            json!({
+                "entity_id": format!("media_player.lgtv_{}", entity_slug(name)),
+                "name": name,
+                "model": config.tvs[name].model_name,
+                "active": config.active_tv.as_deref() == Some(name.as_str()),
+            })
+        })
+        .collect();
+    let active_device = config.active_streaming_device().map(|(name, _)| name);
+    let mut device_names: Vec<&String> = config.streaming_devices.devices.keys().collect();
+    device_names.sort();
+    let streaming_devices: Vec<Value> = device_names
+        .into_iter()
+        .map(|name| {
+            json!({
+                "entity_id": format!("button.lgtv_wake_{}", entity_slug(name)),
+                "name": name,
+                "wake_method": streaming_kind(&config.streaming_devices.devices[name]),
+                "active": active_device == Some(name),
+            })
+        })
+        .collect();
+
+    let mut actions: Vec<String> = config.active_action_shortcuts().keys().cloned().collect();
+    actions.extend(config.macros.keys().map(|name| format!("macro_{}", name)));
+    actions.extend(config.scenes.keys().map(|name| format!("scene_{}", name)));
+    actions.sort();
+
+    json!({
+        "version": SCHEMA_VERSION,
+        "tvs": tvs,
+        "streaming_devices": streaming_devices,
+        "commands": {
+            "run_action": { "scope": "control", "params": { "actionId": { "enum": actions } } },
+            "send_button": { "scope": "control", "params": { "button": "string" } },
+            "set_mute": { "scope": "control", "params": { "mute": "boolean" } },
+            "power_on": { "scope": "control", "params": { "connect": "boolean | null" } },
+            "power_off": { "scope": "control", "params": {} },
+            "wake_streaming_device": {
+                "scope": "control",
+                "params": { "name": "string | null" },
+            },
+            "apply_scene": { "scope": "control", "params": { "name": "string" } },
+            "launch_app_with_params": {
+                "scope": "control",
+                "params": { "appId": "string", "params": "object | null" },
+            },
+            "launch_webapp": {
+                "scope": "control",
+                "params": { "urlOrId": "string", "params": "object | null" },
+            },
+            "subscribe_event": {
+                "scope": "read",
+                "params": {
+                    "kind": { "enum": ["program_info", "channel", "picture_settings", "sound_output"] },
+                },
+            },
+            "unsubscribe_event": { "scope": "read", "params": { "kind": "string" } },
+            "get_status": { "scope": "read", "params": {}, "returns": "boolean (connected)" },
+            "get_current_channel": {
+                "scope": "read",
+                "params": {},
+                "returns": {
+                    "number": "string | null",
+                    "name": "string | null",
+                    "channel_id": "string | null",
+                    "channel_type": "string | null",
+                },
+            },
+            "get_system_info": { "scope": "read", "params": {} },
+        },
+        "events": {
+            "program-info-changed": "object (raw SSAP payload)",
+            "channel-changed": "object (raw SSAP payload)",
+            "picture-settings-changed": "object (raw SSAP payload)",
+            "sound-output-changed": "object (raw SSAP payload)",
+            "connection-lost": null,
+            "connection-restored": null,
+            "power-state-changed": "on | standby | off | unknown",
+            "tv-presence-changed": "connected | on | off",
+            "tv-unsolicited-message": "object (raw SSAP frame no request was waiting for)",
+            "power-on-progress": {
+                "stage": "waiting | connecting | connected | failed",
+                "elapsed_ms": "integer",
+                "message": "string | null",
+            },
+            "volume-changed": { "volume": "integer | null", "muted": "boolean | null" },
+            "foreground-app-changed": { "app_id": "string", "is_input": "boolean" },
+            "action-executed": {
+                "id": "string",
+                "source": "ui | shortcut | tray | macro",
+                "success": "boolean",
+                "error": "string | null",
+            },
+        },
+    })
+}
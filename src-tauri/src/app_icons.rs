@@ -0,0 +1,133 @@
+//! Icons of the TV's apps and inputs. The TV reports them as URLs on its own web server; they
+//! are downloaded once into app_icons/ next to config.json, so the window and the tray menu can
+//! show them while the TV is off.
+
+use crate::config::Config;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+/// Icons are small; anything bigger isn't one.
+const MAX_ICON_BYTES: usize = 1024 * 1024;
+
+fn dir() -> PathBuf {
+    Config::config_path().with_file_name("app_icons")
+}
+
+fn file_for(id: &str) -> PathBuf {
+    let name: String = id
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+            _ => '_',
+        })
+        .collect();
+    dir().join(name)
+}
+
+/// Cached icon for an app or input id, if one was downloaded.
+pub fn path(id: &str) -> Option<PathBuf> {
+    let file = file_for(id);
+    file.is_file().then_some(file)
+}
+
+/// Contents of the cached icon (PNG or JPEG, as the TV serves it).
+pub fn read(id: &str) -> Option<Vec<u8>> {
+    fs::read(path(id)?).ok()
+}
+
+/// Download the icons in `icons` (id, URL) that aren't cached yet. Failures are logged and
+/// retried the next time the list is fetched.
+pub async fn cache_missing(tv_ip: &str, icons: Vec<(String, String)>) {
+    let missing: Vec<_> = icons.into_iter().filter(|(id, _)| path(id).is_none()).collect();
+    if missing.is_empty() {
+        return;
+    }
+    let tv_ip = tv_ip.to_string();
+    let _ = tokio::task::spawn_blocking(move || {
+        if let Err(e) = fs::create_dir_all(dir()) {
+            log::warn!("Could not create icon cache: {}", e);
+            return;
+        }
+        for (id, url) in missing {
+            let result = download(&url, &tv_ip)
+                .and_then(|bytes| fs::write(file_for(&id), bytes).map_err(|e| e.to_string()));
+            match result {
+                Ok(()) => log::debug!("Cached icon for {}", id),
+                Err(e) => log::debug!("Could not fetch icon for {} from {}: {}", id, url, e),
+            }
+        }
+    })
+    .await;
+}
+
+/// GET `url` from the TV. Plain HTTP, or HTTPS with the TV's self-signed certificate; the TV
+/// sometimes names itself 127.0.0.1/localhost in its URLs, which is replaced by `tv_ip`.
+fn download(url: &str, tv_ip: &str) -> Result<Vec<u8>, String> {
+    let (tls, rest) = if let Some(rest) = url.strip_prefix("https://") {
+        (true, rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        (false, rest)
+    } else {
+        return Err("not an http(s) URL".to_string());
+    };
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let path = if path.is_empty() { "/" } else { path };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse::<u16>().map_err(|e| e.to_string())?),
+        None => (authority, if tls { 443 } else { 80 }),
+    };
+    let host = match host {
+        "127.0.0.1" | "localhost" | "" => tv_ip,
+        host => host,
+    };
+
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or("host did not resolve")?;
+    let stream = TcpStream::connect_timeout(&addr, TIMEOUT).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+    // HTTP/1.0 so the body comes unchunked and ends when the TV closes the connection
+    let request = format!("GET {} HTTP/1.0\r\nHost: {}\r\n\r\n", path, host);
+    let response = if tls {
+        let mut stream = crate::tv::tls_connector()?
+            .connect(host, stream)
+            .map_err(|e| e.to_string())?;
+        exchange(&mut stream, &request)?
+    } else {
+        let mut stream = stream;
+        exchange(&mut stream, &request)?
+    };
+
+    let body_start = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or("malformed response")?;
+    let status = String::from_utf8_lossy(&response[..body_start]);
+    let status = status.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(format!("TV answered {}", status));
+    }
+    let body = response[body_start + 4..].to_vec();
+    if body.is_empty() || body.len() > MAX_ICON_BYTES {
+        return Err(format!("not an icon ({} bytes)", body.len()));
+    }
+    Ok(body)
+}
+
+fn exchange(stream: &mut (impl Read + Write), request: &str) -> Result<Vec<u8>, String> {
+    stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+    let mut response = Vec::new();
+    stream
+        .by_ref()
+        .take(MAX_ICON_BYTES as u64 + 4096)
+        .read_to_end(&mut response)
+        .map_err(|e| e.to_string())?;
+    Ok(response)
+}
@@ -0,0 +1,141 @@
+//! Journal of what the app did on its own: parental schedule warnings and power-offs, macro
+//! replays. Each entry says what ran, why and how it went, so unattended behavior can be
+//! checked afterwards. Appended as one JSON object per line to automations.jsonl next to
+//! config.json; the field names are stable for scripts reading the file.
+
+use crate::config::Config;
+use crate::usage::now;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Entries kept; the file is compacted once it holds twice as many lines.
+const MAX_ENTRIES: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutomationOutcome {
+    Succeeded,
+    Failed,
+    /// Held back on purpose (e.g. power off during a software update); tried again later.
+    Deferred,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationEntry {
+    /// Unix seconds.
+    pub at: u64,
+    /// The automation that ran: "parental_schedule" or "macro".
+    pub automation: String,
+    /// What it did, e.g. "power_off", "warn" or the macro name.
+    pub action: String,
+    /// Why it ran.
+    pub reason: String,
+    pub outcome: AutomationOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+struct Journal {
+    entries: VecDeque<AutomationEntry>,
+    /// Lines in the file, including ones already dropped from `entries`.
+    file_lines: usize,
+}
+
+pub struct AutomationLog {
+    journal: Mutex<Journal>,
+}
+
+impl AutomationLog {
+    fn path() -> PathBuf {
+        Config::config_path().with_file_name("automations.jsonl")
+    }
+
+    pub fn load() -> Self {
+        let contents = fs::read_to_string(Self::path()).unwrap_or_default();
+        let file_lines = contents.lines().count();
+        let mut entries: VecDeque<AutomationEntry> = contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        while entries.len() > MAX_ENTRIES {
+            entries.pop_front();
+        }
+        Self {
+            journal: Mutex::new(Journal {
+                entries,
+                file_lines,
+            }),
+        }
+    }
+
+    /// Append an entry to the journal and its file.
+    pub fn record(
+        &self,
+        automation: &str,
+        action: &str,
+        reason: &str,
+        outcome: AutomationOutcome,
+        error: Option<String>,
+    ) {
+        let entry = AutomationEntry {
+            at: now(),
+            automation: automation.to_string(),
+            action: action.to_string(),
+            reason: reason.to_string(),
+            outcome,
+            error,
+        };
+        log::info!(
+            "Automation {} {} ({}): {:?}",
+            entry.automation,
+            entry.action,
+            entry.reason,
+            entry.outcome
+        );
+        let mut journal = self.journal.lock().unwrap();
+        journal.entries.push_back(entry.clone());
+        while journal.entries.len() > MAX_ENTRIES {
+            journal.entries.pop_front();
+        }
+        let result = if journal.file_lines >= MAX_ENTRIES * 2 {
+            journal.file_lines = journal.entries.len();
+            Self::rewrite(&journal.entries)
+        } else {
+            journal.file_lines += 1;
+            Self::append(&entry)
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to write automation journal: {}", e);
+        }
+    }
+
+    fn append(entry: &AutomationEntry) -> Result<(), String> {
+        let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::path())
+            .map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| e.to_string())
+    }
+
+    fn rewrite(entries: &VecDeque<AutomationEntry>) -> Result<(), String> {
+        let mut contents = String::new();
+        for entry in entries {
+            contents.push_str(&serde_json::to_string(entry).map_err(|e| e.to_string())?);
+            contents.push('\n');
+        }
+        fs::write(Self::path(), contents).map_err(|e| e.to_string())
+    }
+
+    /// The newest `limit` entries (all kept entries if None), oldest first.
+    pub fn entries(&self, limit: Option<usize>) -> Vec<AutomationEntry> {
+        let journal = self.journal.lock().unwrap();
+        let skip = limit.map_or(0, |limit| journal.entries.len().saturating_sub(limit));
+        journal.entries.iter().skip(skip).cloned().collect()
+    }
+}
@@ -0,0 +1,103 @@
+//! Remote button names for the pointer input socket, plus aliases so integrations can say
+//! "OK" or "CH_LIST" without knowing webOS's exact spelling. User aliases come from
+//! `button_aliases` in the config and must point at a known button.
+
+use std::collections::HashMap;
+
+/// Buttons the webOS input socket accepts (digits 0-9 are accepted too).
+pub const KNOWN_BUTTONS: &[&str] = &[
+    "UP", "DOWN", "LEFT", "RIGHT", "ENTER", "BACK", "EXIT", "HOME", "MENU", "QMENU", "INFO",
+    "DASH", "ASTERISK", "CC", "GUIDE", "LIST", "MUTE", "VOLUMEUP", "VOLUMEDOWN", "CHANNELUP",
+    "CHANNELDOWN", "PLAY", "PAUSE", "STOP", "REWIND", "FAST_FORWARD", "RECORD", "RED", "GREEN",
+    "YELLOW", "BLUE", "SCREEN_REMOTE", "MYAPPS", "PROGRAM", "TELETEXT", "TEXTOPTION", "AD", "SAP",
+    "3D_MODE", "LIVE_ZOOM", "MAGNIFIER_ZOOM", "NETFLIX", "AMAZON",
+];
+
+const BUILTIN_ALIASES: &[(&str, &str)] = &[
+    ("OK", "ENTER"),
+    ("SELECT", "ENTER"),
+    ("RETURN", "BACK"),
+    ("CH_LIST", "LIST"),
+    ("CHANNEL_LIST", "LIST"),
+    ("EPG", "GUIDE"),
+    ("SETTINGS", "MENU"),
+    ("QUICK_MENU", "QMENU"),
+    ("CH_UP", "CHANNELUP"),
+    ("CHANNEL_UP", "CHANNELUP"),
+    ("CH_DOWN", "CHANNELDOWN"),
+    ("CHANNEL_DOWN", "CHANNELDOWN"),
+    ("VOL_UP", "VOLUMEUP"),
+    ("VOLUME_UP", "VOLUMEUP"),
+    ("VOL_DOWN", "VOLUMEDOWN"),
+    ("VOLUME_DOWN", "VOLUMEDOWN"),
+    ("FF", "FAST_FORWARD"),
+    ("FASTFORWARD", "FAST_FORWARD"),
+    ("REW", "REWIND"),
+    ("REC", "RECORD"),
+    ("SUBTITLES", "CC"),
+    ("KEYPAD", "SCREEN_REMOTE"),
+];
+
+pub fn is_known(button: &str) -> bool {
+    let button = button.to_uppercase();
+    KNOWN_BUTTONS.contains(&button.as_str())
+        || (button.len() == 1 && button.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Check a user alias before saving it.
+pub fn validate_alias(alias: &str, button: &str) -> Result<(), String> {
+    if alias.trim().is_empty() {
+        return Err("Alias name is required".to_string());
+    }
+    if is_known(alias) {
+        return Err(format!("{} is already a button name", alias.to_uppercase()));
+    }
+    if !is_known(button) {
+        return Err(format!("Unknown button {}", button));
+    }
+    Ok(())
+}
+
+/// Button to send for `name`: user aliases first, then built-in ones. Names that aren't
+/// aliases pass through unchanged, so buttons missing from `KNOWN_BUTTONS` still work.
+pub fn resolve(name: &str, aliases: &HashMap<String, String>) -> String {
+    let upper = name.trim().to_uppercase();
+    if let Some(target) = aliases
+        .iter()
+        .find(|(alias, _)| alias.to_uppercase() == upper)
+        .map(|(_, target)| target)
+    {
+        if is_known(target) {
+            return target.to_uppercase();
+        }
+        log::warn!("Ignoring alias {} -> {}: unknown button", name, target);
+    }
+    BUILTIN_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == upper)
+        .map(|(_, target)| target.to_string())
+        .unwrap_or(upper)
+}
+
+/// SSAP request that does the same thing as `button`, for TVs that refuse the pointer input
+/// socket. Most navigation keys (arrows, BACK, colour keys) have no SSAP equivalent.
+pub fn ssap_fallback(button: &str) -> Option<&'static str> {
+    Some(match button.to_uppercase().as_str() {
+        "ENTER" => "ssap://com.webos.service.ime/sendEnterKey",
+        "PLAY" => "ssap://media.controls/play",
+        "PAUSE" => "ssap://media.controls/pause",
+        "STOP" => "ssap://media.controls/stop",
+        "REWIND" => "ssap://media.controls/rewind",
+        "FAST_FORWARD" => "ssap://media.controls/fastForward",
+        "VOLUMEUP" => "ssap://audio/volumeUp",
+        "VOLUMEDOWN" => "ssap://audio/volumeDown",
+        "CHANNELUP" => "ssap://tv/channelUp",
+        "CHANNELDOWN" => "ssap://tv/channelDown",
+        _ => return None,
+    })
+}
+
+/// Buttons that can't be sent at all when the input socket is refused.
+pub fn unavailable_without_input_socket() -> Vec<&'static str> {
+    KNOWN_BUTTONS.iter().copied().filter(|b| ssap_fallback(b).is_none()).collect()
+}
@@ -1,7 +1,10 @@
+use crate::tv::EnergySavingLevel;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Per-action shortcut: key combination and whether it is a global hotkey.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +22,315 @@ impl Default for ActionShortcutConfig {
     }
 }
 
+/// Feedback given when an action finishes while the window is hidden.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackKind {
+    #[default]
+    None,
+    /// Short system sound.
+    Sound,
+    /// Desktop notification with the action and error.
+    Notification,
+}
+
+/// Per-action feedback for success and failure.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct FeedbackConfig {
+    #[serde(default)]
+    pub on_success: FeedbackKind,
+    #[serde(default)]
+    pub on_failure: FeedbackKind,
+}
+
+/// What a left click on the tray icon does. Some desktops (KDE/SNI) never deliver left
+/// clicks; there the tray menu's "Open Remote" is the way in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayClickAction {
+    /// Show the remote next to the tray icon.
+    #[default]
+    OpenRemote,
+    /// Show the remote, or hide it if already open.
+    ToggleWindow,
+    /// Toggle the TV's mute.
+    Mute,
+    /// Turn the TV off if it's on, otherwise power it on.
+    PowerToggle,
+    /// Do nothing.
+    None,
+}
+
+/// An entry of the tray menu. Entries appear between "Open Remote" and "Quit" in config
+/// order; each runs as an action from the tray, so results show up like any other action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TrayMenuItem {
+    /// Any action id, e.g. "mute_toggle" or "screen_off".
+    Action {
+        id: String,
+        #[serde(default)]
+        label: Option<String>,
+    },
+    /// Switch to an input, e.g. "HDMI_1".
+    Input {
+        id: String,
+        #[serde(default)]
+        label: Option<String>,
+    },
+    /// Launch a TV app by id, e.g. "netflix".
+    App {
+        id: String,
+        #[serde(default)]
+        label: Option<String>,
+    },
+    /// Replay a recorded macro.
+    Macro {
+        name: String,
+        #[serde(default)]
+        label: Option<String>,
+    },
+    Separator,
+}
+
+impl TrayMenuItem {
+    /// Action id run when the entry is clicked; None for separators.
+    pub fn action_id(&self) -> Option<String> {
+        match self {
+            TrayMenuItem::Action { id, .. } => Some(id.clone()),
+            TrayMenuItem::Input { id, .. } => Some(format!("input_{}", id.to_lowercase())),
+            TrayMenuItem::App { id, .. } => Some(format!("app_{}", id)),
+            TrayMenuItem::Macro { name, .. } => Some(format!("macro_{}", name)),
+            TrayMenuItem::Separator => None,
+        }
+    }
+
+    /// Menu text: the configured label, or one derived from the id.
+    pub fn label(&self) -> String {
+        match self {
+            TrayMenuItem::Action { id, label } => label.clone().unwrap_or_else(|| {
+                let text = id.replace('_', " ");
+                let mut chars = text.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => text,
+                }
+            }),
+            TrayMenuItem::Input { id, label } => label
+                .clone()
+                .unwrap_or_else(|| id.to_uppercase().replace('_', " ")),
+            TrayMenuItem::App { id, label } => label.clone().unwrap_or_else(|| id.clone()),
+            TrayMenuItem::Macro { name, label } => label.clone().unwrap_or_else(|| name.clone()),
+            TrayMenuItem::Separator => String::new(),
+        }
+    }
+}
+
+/// Auto-repeat for held directional action shortcuts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ShortcutRepeat {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Hold this long before repeating starts.
+    #[serde(default = "default_repeat_delay_ms")]
+    pub delay_ms: u64,
+    /// Time between repeats while held.
+    #[serde(default = "default_repeat_interval_ms")]
+    pub interval_ms: u64,
+    /// Stop repeating after this long even if no release arrived (a release can get lost,
+    /// e.g. when focus changes mid-press).
+    #[serde(default = "default_repeat_max_hold_ms")]
+    pub max_hold_ms: u64,
+}
+
+fn default_repeat_delay_ms() -> u64 {
+    400
+}
+
+fn default_repeat_interval_ms() -> u64 {
+    120
+}
+
+fn default_repeat_max_hold_ms() -> u64 {
+    10_000
+}
+
+impl Default for ShortcutRepeat {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            delay_ms: default_repeat_delay_ms(),
+            interval_ms: default_repeat_interval_ms(),
+            max_hold_ms: default_repeat_max_hold_ms(),
+        }
+    }
+}
+
+/// Limits for commands waiting on the TV connection (button presses, volume, shortcuts).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandQueueConfig {
+    /// Commands that may be queued or running at once; further presses are rejected
+    /// instead of waiting in line until they time out.
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+    /// Minimum time between two commands sent to the TV.
+    #[serde(default)]
+    pub spacing_ms: u64,
+    /// Merge volume steps that pile up while waiting into a single setVolume.
+    #[serde(default = "default_true")]
+    pub coalesce_volume: bool,
+}
+
+fn default_max_in_flight() -> usize {
+    8
+}
+
+impl Default for CommandQueueConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: default_max_in_flight(),
+            spacing_ms: 0,
+            coalesce_volume: true,
+        }
+    }
+}
+
+/// How long to wait on the TV. Slow Wi-Fi TVs may need more than the defaults, especially for
+/// the first command after the TV has been idle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeoutConfig {
+    /// Opening the WebSocket, and registering with an already paired TV.
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+    /// Waiting for the reply to a command; the connection is treated as lost after this.
+    #[serde(default = "default_command_timeout_ms")]
+    pub command_timeout_ms: u64,
+    /// Time between keepalive pings while connected.
+    #[serde(default = "default_keepalive_interval_secs")]
+    pub keepalive_interval_secs: u64,
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_command_timeout_ms() -> u64 {
+    3000
+}
+
+fn default_keepalive_interval_secs() -> u64 {
+    25
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_ms: default_connect_timeout_ms(),
+            command_timeout_ms: default_command_timeout_ms(),
+            keepalive_interval_secs: default_keepalive_interval_secs(),
+        }
+    }
+}
+
+impl TimeoutConfig {
+    /// Keep values in a range where the connection still behaves sensibly.
+    pub fn clamped(self) -> Self {
+        Self {
+            connect_timeout_ms: self.connect_timeout_ms.clamp(1000, 60_000),
+            command_timeout_ms: self.command_timeout_ms.clamp(500, 30_000),
+            keepalive_interval_secs: self.keepalive_interval_secs.clamp(5, 300),
+        }
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_millis(self.connect_timeout_ms)
+    }
+
+    pub fn command_timeout(&self) -> Duration {
+        Duration::from_millis(self.command_timeout_ms)
+    }
+
+    pub fn keepalive_interval(&self) -> Duration {
+        Duration::from_secs(self.keepalive_interval_secs)
+    }
+}
+
+/// How Wake-on-LAN packets go out. A single packet is easily lost on Wi-Fi meshes, and some
+/// TVs ignore the first attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WolConfig {
+    /// Magic packets sent to each destination per attempt.
+    #[serde(default = "default_wol_count")]
+    pub count: u32,
+    /// Time between the packets of one attempt.
+    #[serde(default = "default_wol_interval_ms")]
+    pub interval_ms: u64,
+    /// Send the packets again after this long if the TV still doesn't answer; 0 turns the
+    /// retry off.
+    #[serde(default)]
+    pub retry_after_ms: u64,
+}
+
+fn default_wol_count() -> u32 {
+    3
+}
+
+fn default_wol_interval_ms() -> u64 {
+    200
+}
+
+impl Default for WolConfig {
+    fn default() -> Self {
+        Self {
+            count: default_wol_count(),
+            interval_ms: default_wol_interval_ms(),
+            retry_after_ms: 0,
+        }
+    }
+}
+
+impl WolConfig {
+    /// Keep values in a range that can't flood the network or stall a power on.
+    pub fn clamped(self) -> Self {
+        Self {
+            count: self.count.clamp(1, 20),
+            interval_ms: self.interval_ms.clamp(10, 2000),
+            retry_after_ms: match self.retry_after_ms {
+                0 => 0,
+                ms => ms.clamp(1000, 30_000),
+            },
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        (self.retry_after_ms > 0).then(|| Duration::from_millis(self.retry_after_ms))
+    }
+}
+
+/// One step of a recorded macro: run `action` after waiting `delay_ms` since the previous step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroStep {
+    pub action: String,
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// How a TV in Quick Start+ standby was last turned on successfully; tried first next time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerOnMethod {
+    /// `com.webos.service.tvpower/power/turnOn`
+    TurnOn,
+    /// `com.webos.service.tvpower/power/turnOnScreen`
+    TurnOnScreen,
+    /// POWER button on the input socket
+    PowerButton,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TvConfig {
     pub ip: String,
@@ -28,6 +340,110 @@ pub struct TvConfig {
     pub mac: Option<String>,
     #[serde(default)]
     pub use_ssl: bool,
+    #[serde(default)]
+    pub power_on_method: Option<PowerOnMethod>,
+    /// Global shortcut that opens the remote switched to this TV (in addition to the main toggle).
+    #[serde(default)]
+    pub toggle_shortcut: Option<String>,
+    /// Model name reported by the TV (system/getSystemInfo), refreshed on connect.
+    #[serde(default)]
+    pub model_name: Option<String>,
+    /// Unix time (seconds) of the last successful connection; lets the UI flag stale entries.
+    #[serde(default)]
+    pub last_connected: Option<u64>,
+    /// Input id (e.g. "HDMI_1") -> friendly label ("PC", "Shield", "PS5").
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub input_labels: HashMap<String, String>,
+    /// Address the last successful connection went to.
+    #[serde(default)]
+    pub last_seen_ip: Option<String>,
+    /// Fingerprint of the TV's TLS certificate, pinned on first connect and checked after.
+    #[serde(default)]
+    pub cert_fingerprint: Option<String>,
+    /// Interface name (e.g. "eth0") or local address to send Wake-on-LAN from, for machines
+    /// where the automatically chosen interface is wrong (VPN, Docker bridges).
+    #[serde(default)]
+    pub wol_interface: Option<String>,
+    /// What earlier sessions learned about this TV's services.
+    #[serde(default)]
+    pub capabilities: TvCapabilities,
+    /// Comes from the system-wide TV list: address settings are managed by the administrator
+    /// and the entry can't be removed. Recomputed on every load.
+    #[serde(default)]
+    pub system: bool,
+    /// Settings that differ for this TV; they apply while it's the active TV.
+    #[serde(default, skip_serializing_if = "TvProfile::is_empty")]
+    pub profile: TvProfile,
+}
+
+/// Per-TV overrides of global settings. A field that is absent uses the global setting.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TvProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_shortcuts: Option<HashMap<String, ActionShortcutConfig>>,
+    /// Name of the streaming device to wake for this TV; `null` means none, even if one is
+    /// active globally.
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub streaming_device: Option<Option<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wake_streaming_on_power_on: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_size: Option<WindowSize>,
+}
+
+impl TvProfile {
+    pub fn is_empty(&self) -> bool {
+        self.action_shortcuts.is_none()
+            && self.streaming_device.is_none()
+            && self.wake_streaming_on_power_on.is_none()
+            && self.window_size.is_none()
+    }
+}
+
+/// A field that is present deserializes to Some, even when it's `null`, so "absent" and
+/// "explicitly none" can mean different things.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Service that answered the network status query on a TV (see
+/// `TvConnection::get_network_status`); `Unsupported` if none did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkStatusEndpoint {
+    ConnectionManager,
+    Wifi,
+    PalmWifi,
+    Unsupported,
+}
+
+/// Results of probing a TV that later sessions reuse instead of probing again. The
+/// protocol (`use_ssl`) and the power-on method that worked are kept on `TvConfig` itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TvCapabilities {
+    #[serde(default)]
+    pub network_status: Option<NetworkStatusEndpoint>,
+}
+
+/// System-wide TV list (e.g. /etc/lgtv-remote/tvs.json) for shared machines. Read-only for
+/// users; each user pairs against these TVs and keeps their own client key.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct SystemTvs {
+    #[serde(default)]
+    tvs: HashMap<String, SystemTv>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SystemTv {
+    ip: String,
+    #[serde(default)]
+    mac: Option<String>,
+    #[serde(default)]
+    use_ssl: bool,
 }
 
 /// Optional streaming device (Android TV, Roku, etc.) to wake when it's in standby.
@@ -51,15 +467,66 @@ pub enum StreamingDeviceConfig {
     Roku { ip: String },
 }
 
+/// Streaming devices by name, and the one "Wake streaming device" and Power On wake.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreamingDevices {
+    #[serde(default)]
+    pub active: Option<String>,
+    #[serde(default)]
+    pub devices: HashMap<String, StreamingDeviceConfig>,
+}
+
+/// Name given to the streaming device when none is chosen (and to the one from layout 1).
+pub const DEFAULT_STREAMING_DEVICE: &str = "Streaming device";
+
+/// Layout version of config.json. A change that renames or restructures settings bumps it and
+/// adds a step to `MIGRATIONS`, so older files are upgraded instead of failing to load.
+pub const CONFIG_VERSION: u32 = 2;
+
+/// `MIGRATIONS[n]` upgrades the JSON of a layout `n` file to layout `n + 1`.
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[
+    // 0 -> 1: files from before versioning; same layout
+    |_| {},
+    // 1 -> 2: the single `streaming_device` became named `streaming_devices`; a TV profile's
+    // own device is kept under the TV's name
+    |map| {
+        let mut devices = Map::new();
+        let mut active = Value::Null;
+        if let Some(device) = map.remove("streaming_device").filter(|d| !d.is_null()) {
+            devices.insert(DEFAULT_STREAMING_DEVICE.to_string(), device);
+            active = Value::from(DEFAULT_STREAMING_DEVICE);
+        }
+        if let Some(Value::Object(tvs)) = map.get_mut("tvs") {
+            for (name, tv) in tvs.iter_mut() {
+                let Some(device) = tv
+                    .pointer_mut("/profile/streaming_device")
+                    .filter(|d| d.is_object())
+                else {
+                    continue;
+                };
+                devices.insert(name.clone(), device.take());
+                *device = Value::from(name.as_str());
+            }
+        }
+        let mut streaming_devices = Map::new();
+        streaming_devices.insert("active".to_string(), active);
+        streaming_devices.insert("devices".to_string(), Value::Object(devices));
+        map.insert("streaming_devices".to_string(), Value::Object(streaming_devices));
+    },
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Layout version the file was written with (0 for files from before versioning).
+    #[serde(default)]
+    pub version: u32,
     #[serde(default)]
     pub tvs: HashMap<String, TvConfig>,
     #[serde(default)]
     pub active_tv: Option<String>,
-    /// Optional streaming device to wake (e.g. Shield, Roku). When set, "Wake streaming device" is available.
+    /// Streaming devices to wake (e.g. Shield, Roku). While one is active, "Wake streaming device" is available.
     #[serde(default)]
-    pub streaming_device: Option<StreamingDeviceConfig>,
+    pub streaming_devices: StreamingDevices,
     /// If true, also send wake to streaming device when user triggers "Power On" (TV WoL).
     #[serde(default)]
     pub wake_streaming_on_power_on: bool,
@@ -72,6 +539,57 @@ pub struct Config {
     pub action_shortcuts: HashMap<String, ActionShortcutConfig>,
     #[serde(default)]
     pub window_size: Option<WindowSize>,
+    /// Merge the system-wide TV list (see `Config::system_tvs_path`) into this user's TVs.
+    #[serde(default = "default_true")]
+    pub use_system_tvs: bool,
+    /// Reconnect in the background when the connection drops, backing off while the TV is off.
+    #[serde(default)]
+    pub auto_reconnect: bool,
+    /// Language for backend messages (e.g. "de"); None means English.
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Show the TV's readable notifications (e.g. "software update available") as desktop
+    /// notifications after connecting.
+    #[serde(default)]
+    pub mirror_tv_notifications: bool,
+    #[serde(default)]
+    pub shortcut_repeat: ShortcutRepeat,
+    #[serde(default)]
+    pub command_queue: CommandQueueConfig,
+    #[serde(default)]
+    pub timeouts: TimeoutConfig,
+    #[serde(default)]
+    pub wol: WolConfig,
+    #[serde(default)]
+    pub tray_left_click: TrayClickAction,
+    /// Extra tray menu entries, in menu order.
+    #[serde(default)]
+    pub tray_menu: Vec<TrayMenuItem>,
+    /// Action ids for the window's quick bar, in the order the user arranged them.
+    #[serde(default)]
+    pub favorite_actions: Vec<String>,
+    /// Macro name -> recorded steps. Run with the `macro_<name>` action.
+    #[serde(default)]
+    pub macros: HashMap<String, Vec<MacroStep>>,
+    /// Action id -> feedback for global-shortcut actions run while the window is hidden.
+    #[serde(default)]
+    pub action_feedback: HashMap<String, FeedbackConfig>,
+    /// Hide-on-focus-loss behavior, per platform (window managers differ in how they report focus).
+    #[serde(default)]
+    pub window_behavior: WindowBehaviorConfig,
+    /// Enables power-user commands such as `send_raw_ssap` in release builds (always on in debug builds).
+    #[serde(default)]
+    pub developer_mode: bool,
+    #[serde(default)]
+    pub energy: EnergyConfig,
+    #[serde(default)]
+    pub parental: ParentalConfig,
+    /// Extra button names, e.g. "OK" -> "ENTER". Targets must be known input-socket buttons.
+    #[serde(default)]
+    pub button_aliases: HashMap<String, String>,
+    /// Scene name -> picture/sound preset.
+    #[serde(default)]
+    pub scenes: HashMap<String, Scene>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +604,81 @@ impl Default for WindowSize {
     }
 }
 
+/// How the popup reacts to losing focus.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WindowBehavior {
+    /// Hide the popup when it loses focus (e.g. user clicked outside).
+    #[serde(default = "default_true")]
+    pub hide_on_focus_loss: bool,
+    /// Wait this long before hiding; the hide is cancelled if focus returns or the window is
+    /// resized/moved in the meantime. 0 hides immediately.
+    #[serde(default)]
+    pub hide_delay_ms: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Window behavior for each platform, so one config file works everywhere.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowBehaviorConfig {
+    #[serde(default = "default_windows_behavior")]
+    pub windows: WindowBehavior,
+    #[serde(default = "default_other_behavior")]
+    pub macos: WindowBehavior,
+    #[serde(default = "default_other_behavior")]
+    pub linux: WindowBehavior,
+}
+
+/// Windows sends Focused(false) spuriously while resizing/moving an undecorated window.
+fn default_windows_behavior() -> WindowBehavior {
+    WindowBehavior {
+        hide_on_focus_loss: true,
+        hide_delay_ms: 200,
+    }
+}
+
+fn default_other_behavior() -> WindowBehavior {
+    WindowBehavior {
+        hide_on_focus_loss: true,
+        hide_delay_ms: 0,
+    }
+}
+
+impl Default for WindowBehaviorConfig {
+    fn default() -> Self {
+        Self {
+            windows: default_windows_behavior(),
+            macos: default_other_behavior(),
+            linux: default_other_behavior(),
+        }
+    }
+}
+
+impl WindowBehaviorConfig {
+    /// Behavior for the platform we're running on.
+    pub fn current(&self) -> &WindowBehavior {
+        if cfg!(target_os = "windows") {
+            &self.windows
+        } else if cfg!(target_os = "macos") {
+            &self.macos
+        } else {
+            &self.linux
+        }
+    }
+
+    pub fn current_mut(&mut self) -> &mut WindowBehavior {
+        if cfg!(target_os = "windows") {
+            &mut self.windows
+        } else if cfg!(target_os = "macos") {
+            &mut self.macos
+        } else {
+            &mut self.linux
+        }
+    }
+}
+
 fn default_action_shortcuts() -> HashMap<String, ActionShortcutConfig> {
     let mut m = HashMap::new();
     let default = |shortcut: &str, global: bool| ActionShortcutConfig {
@@ -106,6 +699,11 @@ fn default_action_shortcuts() -> HashMap<String, ActionShortcutConfig> {
     m.insert("power_off".to_string(), default("F8", false));
     m.insert("wake_streaming_device".to_string(), default("", false));
     m.insert("home".to_string(), default("Home", false));
+    for id in [
+        "red", "green", "yellow", "blue", "info", "exit", "menu", "qmenu", "guide", "list", "record",
+    ] {
+        m.insert(id.to_string(), default("", false));
+    }
     m
 }
 
@@ -113,32 +711,229 @@ fn default_shortcut() -> String {
     "Super+Shift+T".to_string()
 }
 
+/// Named picture/sound preset ("Movie Night", "Bright Room"), applied with `apply_scene` or the
+/// `scene_<name>` action. Unset fields are left as they are on the TV.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Scene {
+    /// webOS picture mode id, e.g. "cinema", "expert1", "vivid", "eco".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub picture_mode: Option<String>,
+    /// Backlight (OLED Pixel Brightness on OLED models), 0-100.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backlight: Option<u8>,
+    /// Sound output id, e.g. "tv_speaker", "external_arc", "external_optical", "bt_soundbar".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound_output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub energy_saving: Option<EnergySavingLevel>,
+}
+
+/// Approximate power draw per TV state, for `get_energy_estimate`. Check the TV's energy
+/// label or measure with a plug meter; the defaults are typical for a 55" LED TV.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyConfig {
+    #[serde(default = "default_on_watts")]
+    pub on_watts: f64,
+    #[serde(default = "default_screen_off_watts")]
+    pub screen_off_watts: f64,
+    #[serde(default = "default_standby_watts")]
+    pub standby_watts: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_per_kwh: Option<f64>,
+    /// Shown next to the cost, e.g. "EUR".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+fn default_on_watts() -> f64 {
+    100.0
+}
+
+fn default_screen_off_watts() -> f64 {
+    20.0
+}
+
+fn default_standby_watts() -> f64 {
+    0.5
+}
+
+impl Default for EnergyConfig {
+    fn default() -> Self {
+        Self {
+            on_watts: default_on_watts(),
+            screen_off_watts: default_screen_off_watts(),
+            standby_watts: default_standby_watts(),
+            price_per_kwh: None,
+            currency: None,
+        }
+    }
+}
+
+/// Hours the TV may be on. Outside them the app warns on the TV and then turns it off, and
+/// does so again if someone turns it back on with the physical remote.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParentalConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Allowed windows; the TV may be on when any of them matches.
+    #[serde(default)]
+    pub allowed: Vec<AllowedWindow>,
+    /// How long the on-screen warning shows before the TV is turned off.
+    #[serde(default = "default_parental_warning_secs")]
+    pub warning_secs: u64,
+}
+
+fn default_parental_warning_secs() -> u64 {
+    60
+}
+
+impl Default for ParentalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed: Vec::new(),
+            warning_secs: default_parental_warning_secs(),
+        }
+    }
+}
+
+/// e.g. `{ "days": ["sat", "sun"], "start": "08:00", "end": "21:00" }`. An end before the
+/// start runs past midnight; no days means every day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowedWindow {
+    #[serde(default)]
+    pub days: Vec<String>,
+    pub start: String,
+    pub end: String,
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
             tvs: HashMap::new(),
             active_tv: None,
-            streaming_device: None,
+            streaming_devices: StreamingDevices::default(),
             wake_streaming_on_power_on: false,
             global_shortcut: default_shortcut(),
             shortcut_enabled: false,
             action_shortcuts: default_action_shortcuts(),
             window_size: None,
+            use_system_tvs: true,
+            auto_reconnect: false,
+            locale: None,
+            mirror_tv_notifications: false,
+            shortcut_repeat: ShortcutRepeat::default(),
+            command_queue: CommandQueueConfig::default(),
+            timeouts: TimeoutConfig::default(),
+            wol: WolConfig::default(),
+            tray_left_click: TrayClickAction::default(),
+            tray_menu: Vec::new(),
+            favorite_actions: Vec::new(),
+            macros: HashMap::new(),
+            action_feedback: HashMap::new(),
+            window_behavior: WindowBehaviorConfig::default(),
+            developer_mode: false,
+            energy: EnergyConfig::default(),
+            parental: ParentalConfig::default(),
+            button_aliases: HashMap::new(),
+            scenes: HashMap::new(),
         }
     }
 }
 
 impl Config {
     pub fn load() -> Self {
-        let path = Self::config_path();
-        if path.exists() {
-            if let Ok(contents) = fs::read_to_string(&path) {
-                if let Ok(config) = serde_json::from_str(&contents) {
-                    return config;
+        let mut config = Self::load_user();
+        for tv in config.tvs.values_mut() {
+            tv.system = false;
+        }
+        if config.use_system_tvs {
+            config.merge_system_tvs();
+        }
+        config
+    }
+
+    /// The user's config.json, or defaults if there is none. A file that can't be read is
+    /// copied aside (config.json.invalid-<time>) so the next save doesn't destroy it.
+    pub fn load_user() -> Self {
+        match Self::read_user() {
+            Ok(config) => config.unwrap_or_default(),
+            Err(e) => {
+                let path = Self::config_path();
+                let backup = path.with_extension(format!("json.invalid-{}", unix_now()));
+                log::error!(
+                    "Could not load {} ({}); starting from defaults, the file is kept as {}",
+                    path.display(),
+                    e,
+                    backup.display()
+                );
+                if let Err(e) = fs::copy(&path, &backup) {
+                    log::error!("Could not back up {}: {}", path.display(), e);
                 }
+                Config::default()
+            }
+        }
+    }
+
+    /// Read and migrate config.json; None if it doesn't exist.
+    pub fn read_user() -> Result<Option<Self>, String> {
+        let path = Self::config_path();
+        match fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Parse a config file, first upgrading the JSON of files written by older versions.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut value: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+        let map = value.as_object_mut().ok_or("not a JSON object")?;
+        let version = map.get("version").and_then(Value::as_u64).unwrap_or(0);
+        if version > u64::from(CONFIG_VERSION) {
+            log::warn!(
+                "Config was written by a newer version (layout {}); settings this version \
+                 doesn't know are ignored",
+                version
+            );
+        }
+        for (from, migrate) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            log::info!("Migrating config from layout {} to {}", from, from + 1);
+            migrate(map);
+            map.insert("version".to_string(), Value::from(from + 1));
+        }
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+
+    /// Add TVs from the system-wide list. The administrator's address settings win over the
+    /// user's copy; pairing data (client key, etc.) stays per-user.
+    fn merge_system_tvs(&mut self) {
+        let Some(path) = Self::system_tvs_path() else {
+            return;
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return;
+        };
+        let system: SystemTvs = match serde_json::from_str(&contents) {
+            Ok(s) => s,
+            Err(e) => {
+                log::warn!("Ignoring invalid system TV list {}: {}", path.display(), e);
+                return;
+            }
+        };
+        for (name, stv) in system.tvs {
+            let tv = self.tvs.entry(name.clone()).or_default();
+            tv.ip = stv.ip;
+            if stv.mac.is_some() {
+                tv.mac = stv.mac;
+            }
+            tv.use_ssl = stv.use_ssl;
+            tv.system = true;
+            if self.active_tv.is_none() {
+                self.active_tv = Some(name);
             }
         }
-        Config::default()
     }
 
     pub fn save(&self) -> Result<(), String> {
@@ -147,8 +942,30 @@ impl Config {
             fs::create_dir_all(parent).map_err(|e| e.to_string())?;
         }
         let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
-        fs::write(&path, contents).map_err(|e| e.to_string())?;
-        Ok(())
+        // Write a temporary file and rename it over config.json, so a crash or full disk
+        // mid-write leaves the old file intact instead of a truncated one
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, contents).map_err(|e| e.to_string())?;
+        // Client keys are credentials: keep the file private on shared machines
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600))
+                .map_err(|e| e.to_string())?;
+        }
+        fs::rename(&tmp, &path).map_err(|e| e.to_string())
+    }
+
+    /// Location of the system-wide, administrator-managed TV list.
+    pub fn system_tvs_path() -> Option<PathBuf> {
+        if cfg!(target_os = "windows") {
+            std::env::var_os("ProgramData")
+                .map(|p| PathBuf::from(p).join("lgtv-remote").join("tvs.json"))
+        } else if cfg!(target_os = "macos") {
+            Some(PathBuf::from("/Library/Application Support/lgtv-remote/tvs.json"))
+        } else {
+            Some(PathBuf::from("/etc/lgtv-remote/tvs.json"))
+        }
     }
 
     pub fn config_path() -> PathBuf {
@@ -164,6 +981,77 @@ impl Config {
         }
     }
 
+    fn active_profile(&self) -> Option<&TvProfile> {
+        self.get_active_tv().map(|(_, tv)| &tv.profile)
+    }
+
+    fn active_profile_mut(&mut self) -> Option<&mut TvProfile> {
+        let name = self.get_active_tv()?.0.clone();
+        self.tvs.get_mut(&name).map(|tv| &mut tv.profile)
+    }
+
+    /// Action shortcuts in effect: the active TV's, or the global ones.
+    pub fn active_action_shortcuts(&self) -> &HashMap<String, ActionShortcutConfig> {
+        self.active_profile()
+            .and_then(|p| p.action_shortcuts.as_ref())
+            .unwrap_or(&self.action_shortcuts)
+    }
+
+    /// Streaming device in effect for the active TV, with its name.
+    pub fn active_streaming_device(&self) -> Option<(&String, &StreamingDeviceConfig)> {
+        let name = match self.active_profile().and_then(|p| p.streaming_device.as_ref()) {
+            Some(name) => name.as_ref(),
+            None => self.streaming_devices.active.as_ref(),
+        }?;
+        self.streaming_devices.devices.get_key_value(name)
+    }
+
+    pub fn active_wake_streaming_on_power_on(&self) -> bool {
+        self.active_profile()
+            .and_then(|p| p.wake_streaming_on_power_on)
+            .unwrap_or(self.wake_streaming_on_power_on)
+    }
+
+    pub fn active_window_size(&self) -> Option<&WindowSize> {
+        self.active_profile()
+            .and_then(|p| p.window_size.as_ref())
+            .or(self.window_size.as_ref())
+    }
+
+    /// Change the action shortcuts in effect: the active TV's override if it has one, else
+    /// the global ones. The setters below work the same way.
+    pub fn set_active_action_shortcuts(
+        &mut self,
+        shortcuts: HashMap<String, ActionShortcutConfig>,
+    ) {
+        match self.active_profile_mut().filter(|p| p.action_shortcuts.is_some()) {
+            Some(profile) => profile.action_shortcuts = Some(shortcuts),
+            None => self.action_shortcuts = shortcuts,
+        }
+    }
+
+    pub fn set_active_streaming_device(&mut self, name: Option<String>) {
+        match self.active_profile_mut().filter(|p| p.streaming_device.is_some()) {
+            Some(profile) => profile.streaming_device = Some(name),
+            None => self.streaming_devices.active = name,
+        }
+    }
+
+    pub fn set_active_wake_streaming_on_power_on(&mut self, enabled: bool) {
+        match self.active_profile_mut().filter(|p| p.wake_streaming_on_power_on.is_some()) {
+            Some(profile) => profile.wake_streaming_on_power_on = Some(enabled),
+            None => self.wake_streaming_on_power_on = enabled,
+        }
+    }
+
+    pub fn set_active_window_size(&mut self, size: Option<WindowSize>) {
+        match self.active_profile_mut().filter(|p| p.window_size.is_some()) {
+            // Resetting the override falls back to the global size
+            Some(profile) => profile.window_size = size,
+            None => self.window_size = size,
+        }
+    }
+
     pub fn set_tv(&mut self, name: String, config: TvConfig) {
         self.tvs.insert(name.clone(), config);
         if self.active_tv.is_none() {
@@ -177,13 +1065,117 @@ impl Config {
         }
     }
 
+    /// Drop the client key and pinned certificate so the next connect pairs from scratch.
+    /// False if there is no such TV.
+    pub fn forget_pairing(&mut self, name: &str) -> bool {
+        let Some(tv) = self.tvs.get_mut(name) else {
+            return false;
+        };
+        tv.client_key = None;
+        tv.cert_fingerprint = None;
+        true
+    }
+
     pub fn update_mac(&mut self, name: &str, mac: String) {
         if let Some(tv) = self.tvs.get_mut(name) {
             tv.mac = Some(mac);
         }
     }
 
-    pub fn set_streaming_device(&mut self, device: Option<StreamingDeviceConfig>) {
-        self.streaming_device = device;
+    /// Follow a TV whose address changed (DHCP). System TVs keep the administrator's address.
+    pub fn update_ip(&mut self, name: &str, ip: &str) -> bool {
+        match self.tvs.get_mut(name) {
+            Some(tv) if !tv.system && tv.ip != ip => {
+                tv.ip = ip.to_string();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Name of the configured TV a network announcement is for: the one with the same MAC
+    /// when the announcement carries one, else the one at `ip`.
+    pub fn find_tv_by_address(&self, mac: Option<&str>, ip: &str) -> Option<String> {
+        let hex = |mac: &str| -> String {
+            mac.chars()
+                .filter(char::is_ascii_hexdigit)
+                .map(|c| c.to_ascii_lowercase())
+                .collect()
+        };
+        let by_mac = mac.map(hex).filter(|mac| mac.len() == 12).and_then(|mac| {
+            self.tvs
+                .iter()
+                .find(|(_, tv)| tv.mac.as_deref().is_some_and(|m| hex(m) == mac))
+        });
+        by_mac
+            .or_else(|| self.tvs.iter().find(|(_, tv)| tv.ip == ip))
+            .map(|(name, _)| name.clone())
+    }
+
+    pub fn record_connection(&mut self, name: &str, ip: &str) {
+        if let Some(tv) = self.tvs.get_mut(name) {
+            tv.last_connected = Some(unix_now());
+            tv.last_seen_ip = Some(ip.to_string());
+        }
+    }
+
+    pub fn remove_tv(&mut self, name: &str) -> bool {
+        let removed = self.tvs.remove(name).is_some();
+        if self.active_tv.as_deref() == Some(name) {
+            self.active_tv = self.tvs.keys().next().cloned();
+        }
+        removed
+    }
+
+    pub fn update_model_name(&mut self, name: &str, model: String) {
+        if let Some(tv) = self.tvs.get_mut(name) {
+            tv.model_name = Some(model);
+        }
+    }
+
+    pub fn update_power_on_method(&mut self, name: &str, method: PowerOnMethod) {
+        if let Some(tv) = self.tvs.get_mut(name) {
+            tv.power_on_method = Some(method);
+        }
+    }
+
+    pub fn update_capabilities(&mut self, name: &str, capabilities: TvCapabilities) {
+        if let Some(tv) = self.tvs.get_mut(name) {
+            tv.capabilities = capabilities;
+        }
     }
+
+    /// Add, replace or (with None) remove a named streaming device. The first one added
+    /// becomes active; a removed one is no longer selected anywhere.
+    pub fn set_streaming_device(&mut self, name: String, device: Option<StreamingDeviceConfig>) {
+        match device {
+            Some(device) => {
+                self.streaming_devices.devices.insert(name.clone(), device);
+                if self.streaming_devices.active.is_none() {
+                    self.streaming_devices.active = Some(name);
+                }
+            }
+            None => {
+                self.streaming_devices.devices.remove(&name);
+                if self.streaming_devices.active.as_ref() == Some(&name) {
+                    self.streaming_devices.active = None;
+                }
+                for tv in self.tvs.values_mut() {
+                    if let Some(selected) = &mut tv.profile.streaming_device
+                        && selected.as_ref() == Some(&name)
+                    {
+                        *selected = None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
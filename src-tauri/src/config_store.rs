@@ -0,0 +1,320 @@
+//! Single owner of the in-memory config. Everything reads and writes settings through the
+//! store so there is one copy: updates are saved immediately and announced to subscribers,
+//! and nothing reloads the file from disk behind the store's back (which used to overwrite
+//! newer settings with stale data).
+//!
+//! Every update bumps a generation counter, so holders of a snapshot (the frontend) can tell
+//! theirs is stale. If config.json was changed on disk since our last save (hand edit, second
+//! instance), the two versions are merged field by field, down into nested settings and
+//! per-TV entries, before saving instead of the disk changes being silently dropped. Merged
+//! disk changes count as an update.
+//!
+//! Settings that change in bursts (the window size while resizing) go through
+//! `update_deferred`, which saves once the burst is over; `flush` writes anything still
+//! pending, e.g. on exit.
+
+use crate::config::Config;
+use crate::error::AppError;
+use serde_json::{Map, Value};
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{watch, Mutex};
+
+/// `update_deferred` saves once no change came in for this long.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+struct Inner {
+    config: Config,
+    generation: u64,
+    /// What we last wrote (or loaded), the common base for merging disk changes.
+    saved: Config,
+    saved_mtime: Option<SystemTime>,
+    /// Time of the latest change not saved yet (see `update_deferred`).
+    unsaved_since: Option<Instant>,
+}
+
+pub struct ConfigStore {
+    inner: Arc<Mutex<Inner>>,
+    changed: watch::Sender<u64>,
+}
+
+fn file_mtime() -> Option<SystemTime> {
+    fs::metadata(Config::config_path()).and_then(|m| m.modified()).ok()
+}
+
+fn to_map(config: &Config) -> Result<Map<String, Value>, String> {
+    match serde_json::to_value(config).map_err(|e| e.to_string())? {
+        Value::Object(map) => Ok(map),
+        _ => Err("Config did not serialize to an object".to_string()),
+    }
+}
+
+/// Three-way merge. Fields only `theirs` changed are taken from it; where both sides changed a
+/// field differently, `ours` wins and the field's path (e.g. "tvs.Bedroom.ip") is returned as
+/// a conflict.
+fn merge(base: &Config, ours: &Config, theirs: &Config) -> Result<(Config, Vec<String>), String> {
+    let base = to_map(base)?;
+    let mut merged = to_map(ours)?;
+    let mut conflicts = Vec::new();
+    merge_maps(&base, &mut merged, &to_map(theirs)?, "", &mut conflicts);
+    let merged = serde_json::from_value(Value::Object(merged)).map_err(|e| e.to_string())?;
+    Ok((merged, conflicts))
+}
+
+/// Merge `theirs` into `ours` key by key. Objects on both sides (nested settings, and maps such
+/// as `tvs` whose entries are matched by key) are merged recursively; anything else is
+/// replaced whole. Keys `theirs` removed are removed unless `ours` changed them.
+fn merge_maps(
+    base: &Map<String, Value>,
+    ours: &mut Map<String, Value>,
+    theirs: &Map<String, Value>,
+    path: &str,
+    conflicts: &mut Vec<String>,
+) {
+    let keys: Vec<String> = base.keys().chain(theirs.keys()).cloned().collect();
+    for key in keys {
+        let base_value = base.get(&key);
+        let their_value = theirs.get(&key);
+        if base_value == their_value {
+            continue;
+        }
+        let key_path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        let our_value = ours.get(&key);
+        if our_value == base_value {
+            match their_value {
+                Some(value) => ours.insert(key, value.clone()),
+                None => ours.remove(&key),
+            };
+        } else if our_value == their_value {
+            continue;
+        } else if let (Some(Value::Object(our_map)), Some(Value::Object(their_map))) =
+            (ours.get_mut(&key), their_value)
+        {
+            let empty = Map::new();
+            let base_map = match base_value {
+                Some(Value::Object(map)) => map,
+                _ => &empty,
+            };
+            merge_maps(base_map, our_map, their_map, &key_path, conflicts);
+        } else {
+            conflicts.push(key_path);
+        }
+    }
+}
+
+impl ConfigStore {
+    pub fn new(config: Config) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                saved: config.clone(),
+                config,
+                generation: 0,
+                saved_mtime: file_mtime(),
+                unsaved_since: None,
+            })),
+            changed: watch::Sender::new(0),
+        }
+    }
+
+    /// Snapshot of the current config.
+    pub async fn get(&self) -> Config {
+        self.inner.lock().await.config.clone()
+    }
+
+    /// Read part of the config without cloning all of it.
+    pub async fn read<R>(&self, f: impl FnOnce(&Config) -> R) -> R {
+        f(&self.inner.lock().await.config)
+    }
+
+    /// Apply `f` and, if it succeeds, save and notify subscribers. On error nothing is saved
+    /// (changes `f` made before failing stay in memory only).
+    pub async fn update<R>(
+        &self,
+        f: impl FnOnce(&mut Config) -> Result<R, AppError>,
+    ) -> Result<R, AppError> {
+        let mut inner = self.inner.lock().await;
+        let result = f(&mut inner.config)?;
+        inner.save()?;
+        inner.generation += 1;
+        let generation = inner.generation;
+        drop(inner);
+        self.changed.send_replace(generation);
+        Ok(result)
+    }
+
+    /// Apply `f` and notify subscribers now, but save only after `SAVE_DELAY` without further
+    /// changes, for settings that change many times a second.
+    pub async fn update_deferred(&self, f: impl FnOnce(&mut Config)) {
+        let mut inner = self.inner.lock().await;
+        f(&mut inner.config);
+        let save_scheduled = inner.unsaved_since.is_some();
+        inner.unsaved_since = Some(Instant::now());
+        inner.generation += 1;
+        let generation = inner.generation;
+        drop(inner);
+        self.changed.send_replace(generation);
+        if save_scheduled {
+            return;
+        }
+        let inner = self.inner.clone();
+        let changed = self.changed.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(SAVE_DELAY).await;
+                let mut inner = inner.lock().await;
+                match inner.unsaved_since {
+                    // Saved in the meantime (by `update` or `flush`)
+                    None => return,
+                    Some(changed) if changed.elapsed() < SAVE_DELAY => continue,
+                    Some(_) => {
+                        let generation = inner.generation;
+                        if let Err(e) = inner.save() {
+                            log::warn!("Could not save config: {}", e);
+                        }
+                        // Disk changes merged in while saving
+                        if inner.generation != generation {
+                            changed.send_replace(inner.generation);
+                        }
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Save changes `update_deferred` hasn't written yet.
+    pub async fn flush(&self) -> Result<(), String> {
+        let mut inner = self.inner.lock().await;
+        if inner.unsaved_since.is_some() {
+            let generation = inner.generation;
+            inner.save()?;
+            if inner.generation != generation {
+                self.changed.send_replace(inner.generation);
+            }
+        }
+        Ok(())
+    }
+
+    /// Notified with the new generation after every successful update.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changed.subscribe()
+    }
+}
+
+impl Inner {
+    fn save(&mut self) -> Result<(), String> {
+        self.merge_disk_changes();
+        self.config.save()?;
+        self.saved = self.config.clone();
+        self.saved_mtime = file_mtime();
+        self.unsaved_since = None;
+        Ok(())
+    }
+
+    /// Fold in changes made to config.json by someone else since our last save, bumping the
+    /// generation if that changed anything.
+    fn merge_disk_changes(&mut self) {
+        let mtime = file_mtime();
+        if mtime.is_none() || mtime == self.saved_mtime {
+            return;
+        }
+        // A file that doesn't parse (mid-edit, typo) would merge as all defaults
+        let disk = match Config::read_user() {
+            Ok(Some(disk)) => disk,
+            Ok(None) => return,
+            Err(e) => {
+                log::warn!("Ignoring on-disk config changes, the file doesn't load: {}", e);
+                return;
+            }
+        };
+        match merge(&self.saved, &self.config, &disk) {
+            Ok((merged, conflicts)) => {
+                if !conflicts.is_empty() {
+                    log::warn!(
+                        "config.json changed on disk; kept this instance's values for: {}",
+                        conflicts.join(", ")
+                    );
+                }
+                if to_map(&merged).ok() != to_map(&self.config).ok() {
+                    self.config = merged;
+                    self.generation += 1;
+                }
+            }
+            Err(e) => log::warn!("Could not merge on-disk config changes: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TvConfig;
+
+    fn with_tvs(tvs: &[(&str, &str)]) -> Config {
+        let mut config = Config::default();
+        for (name, ip) in tvs {
+            config.tvs.insert(
+                name.to_string(),
+                TvConfig {
+                    ip: ip.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        config
+    }
+
+    #[test]
+    fn merges_changes_to_different_tvs() {
+        let base = with_tvs(&[("Living Room", "10.0.0.2"), ("Bedroom", "10.0.0.3")]);
+        let ours = with_tvs(&[("Living Room", "10.0.0.20"), ("Bedroom", "10.0.0.3")]);
+        let theirs = with_tvs(&[("Living Room", "10.0.0.2"), ("Bedroom", "10.0.0.30")]);
+        let (merged, conflicts) = merge(&base, &ours, &theirs).unwrap();
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.tvs["Living Room"].ip, "10.0.0.20");
+        assert_eq!(merged.tvs["Bedroom"].ip, "10.0.0.30");
+    }
+
+    #[test]
+    fn merges_added_and_removed_tvs() {
+        let base = with_tvs(&[("Living Room", "10.0.0.2"), ("Bedroom", "10.0.0.3")]);
+        let ours = with_tvs(&[
+            ("Living Room", "10.0.0.2"),
+            ("Bedroom", "10.0.0.3"),
+            ("Office", "10.0.0.4"),
+        ]);
+        let theirs = with_tvs(&[("Living Room", "10.0.0.2")]);
+        let (merged, conflicts) = merge(&base, &ours, &theirs).unwrap();
+        assert!(conflicts.is_empty());
+        assert!(merged.tvs.contains_key("Office"));
+        assert!(!merged.tvs.contains_key("Bedroom"));
+    }
+
+    #[test]
+    fn merges_nested_settings_field_by_field() {
+        let base = Config::default();
+        let mut ours = base.clone();
+        ours.shortcut_repeat.delay_ms = 300;
+        let mut theirs = base.clone();
+        theirs.shortcut_repeat.interval_ms = 80;
+        let (merged, conflicts) = merge(&base, &ours, &theirs).unwrap();
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.shortcut_repeat.delay_ms, 300);
+        assert_eq!(merged.shortcut_repeat.interval_ms, 80);
+    }
+
+    #[test]
+    fn keeps_ours_on_conflict() {
+        let base = with_tvs(&[("Bedroom", "10.0.0.3")]);
+        let ours = with_tvs(&[("Bedroom", "10.0.0.4")]);
+        let theirs = with_tvs(&[("Bedroom", "10.0.0.5")]);
+        let (merged, conflicts) = merge(&base, &ours, &theirs).unwrap();
+        assert_eq!(conflicts, vec!["tvs.Bedroom.ip".to_string()]);
+        assert_eq!(merged.tvs["Bedroom"].ip, "10.0.0.4");
+    }
+}
@@ -0,0 +1,166 @@
+//! Moving settings between machines: export the config to a file and import it elsewhere,
+//! either replacing the settings there or merging the file's TVs, shortcuts and streaming
+//! devices into them. Client keys travel along by default so paired TVs don't need pairing
+//! again; the exported file is then a credential and kept private like config.json.
+
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Add the file's TVs and named settings (shortcuts, macros, scenes, ...) to the current
+    /// ones; entries with the same name are taken from the file. Other settings stay.
+    #[default]
+    Merge,
+    /// Use the file's settings instead of the current ones.
+    Replace,
+}
+
+/// What an import changed, for the UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    /// TVs that weren't configured here before.
+    pub added: Vec<String>,
+    /// TVs that were, and got the file's settings.
+    pub updated: Vec<String>,
+    /// TVs that arrived with a client key and connect without pairing.
+    pub paired: Vec<String>,
+}
+
+/// Write `config` to `path` (default: config-export-<time>.json next to config.json). Without
+/// `include_client_keys` the TVs have to be paired again after importing.
+pub fn export(
+    config: &Config,
+    path: Option<PathBuf>,
+    include_client_keys: bool,
+) -> Result<PathBuf, String> {
+    let mut config = config.clone();
+    for tv in config.tvs.values_mut() {
+        // Recomputed from the system-wide list on the machine that loads it
+        tv.system = false;
+        if !include_client_keys {
+            tv.client_key = None;
+        }
+    }
+    let path = path.unwrap_or_else(|| {
+        let name = format!(
+            "config-export-{}.json",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        Config::config_path().with_file_name(name)
+    });
+    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    if include_client_keys {
+        restrict_permissions(&path)?;
+    }
+    Ok(path)
+}
+
+/// Read a file written by `export` (or a config.json copied from another machine).
+pub fn read(path: &Path) -> Result<Config, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    Config::parse(&contents)
+        .map_err(|e| format!("{} is not a valid config file: {}", path.display(), e))
+}
+
+/// Apply `imported` to `config`. TVs from the system-wide list keep the administrator's
+/// address settings either way; only their pairing data is taken from the file.
+pub fn import(config: &mut Config, mut imported: Config, mode: ImportMode) -> ImportSummary {
+    let mut summary = ImportSummary {
+        added: Vec::new(),
+        updated: Vec::new(),
+        paired: Vec::new(),
+    };
+    for (name, tv) in imported.tvs.iter_mut() {
+        tv.system = false;
+        if config.tvs.contains_key(name) {
+            summary.updated.push(name.clone());
+        } else {
+            summary.added.push(name.clone());
+        }
+        if tv.client_key.is_some() {
+            summary.paired.push(name.clone());
+        }
+    }
+    summary.added.sort();
+    summary.updated.sort();
+    summary.paired.sort();
+
+    let current = std::mem::take(&mut config.tvs);
+    match mode {
+        ImportMode::Replace => {
+            *config = imported;
+            for (name, tv) in current.into_iter().filter(|(_, tv)| tv.system) {
+                let entry = config.tvs.entry(name).or_insert_with(|| tv.clone());
+                entry.ip = tv.ip;
+                entry.mac = tv.mac.or(entry.mac.take());
+                entry.use_ssl = tv.use_ssl;
+                entry.system = true;
+            }
+        }
+        ImportMode::Merge => {
+            config.tvs = current;
+            for (name, mut tv) in imported.tvs {
+                if let Some(existing) = config.tvs.get(&name) {
+                    // A file exported without keys shouldn't unpair a TV paired here
+                    if tv.client_key.is_none() {
+                        tv.client_key = existing.client_key.clone();
+                    }
+                    if existing.system {
+                        tv.ip = existing.ip.clone();
+                        tv.mac = existing.mac.clone().or(tv.mac);
+                        tv.use_ssl = existing.use_ssl;
+                        tv.system = true;
+                    }
+                }
+                config.tvs.insert(name, tv);
+            }
+            config.action_shortcuts.extend(imported.action_shortcuts);
+            config.macros.extend(imported.macros);
+            config.scenes.extend(imported.scenes);
+            config.button_aliases.extend(imported.button_aliases);
+            config.action_feedback.extend(imported.action_feedback);
+            config
+                .streaming_devices
+                .devices
+                .extend(imported.streaming_devices.devices);
+            if config.streaming_devices.active.is_none() {
+                config.streaming_devices.active = imported.streaming_devices.active;
+            }
+            for id in imported.favorite_actions {
+                if !config.favorite_actions.contains(&id) {
+                    config.favorite_actions.push(id);
+                }
+            }
+            if config.active_tv.is_none() {
+                config.active_tv = imported.active_tv;
+            }
+        }
+    }
+    if config
+        .active_tv
+        .as_ref()
+        .is_some_and(|name| !config.tvs.contains_key(name))
+    {
+        config.active_tv = config.tvs.keys().next().cloned();
+    }
+    summary
+}
+
+/// Client keys are credentials: keep the file private on shared machines.
+fn restrict_permissions(path: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
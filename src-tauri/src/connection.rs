@@ -0,0 +1,269 @@
+//! Connection manager: a task that owns the `TvConnection` and runs jobs sent to it over a
+//! channel, one at a time and in order. Keepalive, global shortcuts and UI commands queue
+//! here instead of contending for a lock, and every connect/disconnect transition happens on
+//! this one task.
+//!
+//! User commands (anything the user asks the TV to do or report) go through `command`, which
+//! applies `CommandQueueConfig`: a cap on commands waiting at once, a minimum spacing between
+//! jobs, and merging of queued volume steps. `run` is for the app's own jobs (connects and
+//! disconnects, keepalive, subscriptions, reading the connection's state), which must not be
+//! turned away when the cap is reached.
+//! `TimeoutConfig` is handed to the connection before each job, so changes apply right away.
+//!
+//! A job has the connection to itself until it finishes, so a job that waits on the TV holds
+//! up everything behind it. `request` sends in a job but waits for the reply outside the
+//! queue; the keepalive uses it so a slow ping doesn't delay hotkeys. `connect` and
+//! `measure_latency` split their work the same way.
+
+use crate::config::{CommandQueueConfig, TimeoutConfig};
+use crate::error::AppError;
+use crate::trace::ProtocolTrace;
+use crate::tv::{
+    CommandResult, Latency, PendingRegistration, SystemInfo, TvConnection, LATENCY_URI,
+    SOFTWARE_INFO_URI, SYSTEM_INFO_URI,
+};
+use futures_util::future::BoxFuture;
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+
+type Job = Box<dyn for<'a> FnOnce(&'a mut TvConnection) -> BoxFuture<'a, ()> + Send>;
+
+#[derive(Clone)]
+pub struct ConnectionManager {
+    jobs: mpsc::UnboundedSender<Job>,
+    limits: Arc<Mutex<CommandQueueConfig>>,
+    timeouts: Arc<Mutex<TimeoutConfig>>,
+    /// Commands submitted through `command` that haven't finished.
+    in_flight: Arc<AtomicUsize>,
+    /// Volume steps for the queued volume job, which applies them all when it runs.
+    pending_volume: Arc<Mutex<Option<i64>>>,
+    /// The connection's frame recorder.
+    pub trace: Arc<ProtocolTrace>,
+}
+
+/// Counts a command as in flight until dropped (also when the caller gives up waiting).
+struct InFlight(Arc<AtomicUsize>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ConnectionManager {
+    /// Start the manager task with a fresh, disconnected `TvConnection`.
+    pub fn spawn() -> Self {
+        let (jobs, mut queue) = mpsc::unbounded_channel::<Job>();
+        let limits = Arc::new(Mutex::new(CommandQueueConfig::default()));
+        let spacing_limits = limits.clone();
+        let timeouts = Arc::new(Mutex::new(TimeoutConfig::default()));
+        let job_timeouts = timeouts.clone();
+        let trace = Arc::new(ProtocolTrace::default());
+        let tv_trace = trace.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut tv = TvConnection::new();
+            tv.trace = tv_trace;
+            let mut last_start: Option<Instant> = None;
+            while let Some(job) = queue.recv().await {
+                let spacing = Duration::from_millis(spacing_limits.lock().unwrap().spacing_ms);
+                if let Some(last_start) = last_start {
+                    let wait = spacing.saturating_sub(last_start.elapsed());
+                    if !wait.is_zero() {
+                        tokio::time::sleep(wait).await;
+                    }
+                }
+                last_start = Some(Instant::now());
+                tv.timeouts = *job_timeouts.lock().unwrap();
+                job(&mut tv).await;
+            }
+        });
+        Self {
+            jobs,
+            limits,
+            timeouts,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            pending_volume: Arc::new(Mutex::new(None)),
+            trace,
+        }
+    }
+
+    /// Apply the configured queue limits; takes effect for the next job.
+    pub fn set_limits(&self, limits: CommandQueueConfig) {
+        *self.limits.lock().unwrap() = limits;
+    }
+
+    /// Apply the configured timeouts (clamped, they may be hand-edited); takes effect for the
+    /// next job.
+    pub fn set_timeouts(&self, timeouts: TimeoutConfig) {
+        *self.timeouts.lock().unwrap() = timeouts.clamped();
+    }
+
+    /// Run `f` with exclusive access to the connection once the jobs queued before it are
+    /// done. Dropping the returned future doesn't cancel a job that already started.
+    ///
+    /// `f` must not call back into the manager: it would wait behind itself. Fails only if
+    /// the manager task is gone.
+    pub async fn run<T, F>(&self, f: F) -> Result<T, AppError>
+    where
+        F: for<'a> FnOnce(&'a mut TvConnection) -> BoxFuture<'a, T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        let job: Job = Box::new(move |tv| {
+            Box::pin(async move {
+                let _ = reply.send(f(tv).await);
+            })
+        });
+        let stopped = || AppError::catalog("connection_manager_stopped", &[]);
+        self.jobs.send(job).map_err(|_| stopped())?;
+        result.await.map_err(|_| stopped())
+    }
+
+    /// Send an SSAP request as a short job and wait for the reply outside the queue, so jobs
+    /// queued behind it run while the TV answers. A failed reply updates the connection state
+    /// in a follow-up job, as `TvConnection::send_command` would.
+    pub async fn request(&self, uri: &'static str, payload: Option<Value>) -> Result<Value, AppError> {
+        self.timed_request(uri, payload).await.map(|(reply, _)| reply)
+    }
+
+    /// `request`, also returning how long the TV took to answer once the request was sent.
+    async fn timed_request(
+        &self,
+        uri: &'static str,
+        payload: Option<Value>,
+    ) -> Result<(Value, Duration), AppError> {
+        let (sent, pending) = self
+            .run(move |tv| {
+                Box::pin(async move {
+                    let sent = Instant::now();
+                    tv.start_request(uri, payload).await.map(|pending| (sent, pending))
+                })
+            })
+            .await??;
+        let result = pending.wait().await;
+        let elapsed = sent.elapsed();
+        if let Err(e) = &result {
+            let error = e.clone();
+            self.run(move |tv| Box::pin(async move { tv.request_failed(&error) }))
+                .await?;
+        }
+        result.map(|reply| (reply, elapsed))
+    }
+
+    /// Connect with `start`, a job that calls `TvConnection::start_connect`. The wait for the
+    /// TV to accept the handshake happens outside the queue (while pairing, until the user
+    /// answers the prompt); `finish_connect` then runs as a job of its own. A resumed session
+    /// the TV no longer accepts is retried once with a full handshake.
+    pub async fn connect<F>(&self, start: F) -> Result<CommandResult, AppError>
+    where
+        F: for<'a> FnOnce(
+                &'a mut TvConnection,
+            ) -> BoxFuture<'a, Result<PendingRegistration, AppError>>
+            + Send
+            + 'static,
+    {
+        let mut pending = self.run(start).await??;
+        let mut registered = pending.wait().await;
+        if let Err(e) = &registered
+            && pending.is_resume()
+        {
+            log::debug!("Session resume failed ({}), doing a full handshake", e);
+            pending = self
+                .run(move |tv| Box::pin(async move { tv.restart_connect(pending).await }))
+                .await??;
+            registered = pending.wait().await;
+        }
+        self.run(move |tv| Box::pin(async move { tv.finish_connect(pending, registered).await }))
+            .await?
+    }
+
+    /// `TvConnection::get_system_info`, with both requests waited for outside the queue.
+    pub async fn system_info(&self) -> Result<SystemInfo, AppError> {
+        let reply = self.request(SYSTEM_INFO_URI, None).await?;
+        let mut info = SystemInfo::from_payload(&reply["payload"]);
+        // Not available on every firmware; model info alone is still useful
+        match self.request(SOFTWARE_INFO_URI, None).await {
+            Ok(reply) => info.add_software_info(&reply["payload"]),
+            Err(e) => log::debug!("getCurrentSWInformation failed: {}", e),
+        }
+        Ok(info)
+    }
+
+    /// Time `samples` rounds of a TCP connect, an SSAP request and an input socket ping. Only
+    /// reading the connection's addresses is a job; the rounds run outside the queue, so
+    /// commands sent meanwhile aren't held up (and don't count against the TV).
+    pub async fn measure_latency(&self, samples: u32) -> Result<Latency, AppError> {
+        let queued = Instant::now();
+        let (queue_wait, probe) = self
+            .run(move |tv| Box::pin(async move { (queued.elapsed(), tv.latency_probe()) }))
+            .await?;
+        let probe = probe?;
+        let (mut network, mut ssap, mut input) = (Vec::new(), Vec::new(), Vec::new());
+        for _ in 0..samples {
+            network.push(probe.network().await?);
+            let (_, elapsed) = self.timed_request(LATENCY_URI, None).await?;
+            ssap.push(elapsed);
+            input.extend(probe.input_ping().await);
+        }
+        Ok(Latency::from_samples(samples, queue_wait, network, ssap, input))
+    }
+
+    /// Like `run`, for a command a user triggered: fails right away when `max_in_flight`
+    /// commands are already waiting, rather than queueing behind them.
+    pub async fn command<T, F>(&self, f: F) -> Result<T, AppError>
+    where
+        F: for<'a> FnOnce(&'a mut TvConnection) -> BoxFuture<'a, Result<T, AppError>>
+            + Send
+            + 'static,
+        T: Send + 'static,
+    {
+        let _in_flight = self.reserve()?;
+        self.run(f).await?
+    }
+
+    fn reserve(&self) -> Result<InFlight, AppError> {
+        let max_in_flight = self.limits.lock().unwrap().max_in_flight;
+        if self.in_flight.fetch_add(1, Ordering::SeqCst) >= max_in_flight {
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            return Err(AppError::catalog("command_queue_full", &[]));
+        }
+        Ok(InFlight(self.in_flight.clone()))
+    }
+
+    /// Change the volume by `steps`. With `coalesce_volume`, steps arriving while a volume
+    /// change is still queued are added to it, so a burst of presses becomes one setVolume.
+    pub async fn volume_step(&self, steps: i64) -> Result<CommandResult, AppError> {
+        if !self.limits.lock().unwrap().coalesce_volume {
+            return self
+                .command(move |tv| {
+                    Box::pin(async move {
+                        tv.ensure_active()?;
+                        tv.change_volume(steps).await
+                    })
+                })
+                .await;
+        }
+        let _in_flight = {
+            let mut pending = self.pending_volume.lock().unwrap();
+            if let Some(queued) = pending.as_mut() {
+                *queued += steps;
+                return Ok(CommandResult::ok_with_message("Merged with a queued volume change"));
+            }
+            let in_flight = self.reserve()?;
+            *pending = Some(steps);
+            in_flight
+        };
+        let pending = self.pending_volume.clone();
+        self.run(move |tv| {
+            Box::pin(async move {
+                let steps = pending.lock().unwrap().take().unwrap_or(0);
+                tv.ensure_active()?;
+                tv.change_volume(steps).await
+            })
+        })
+        .await?
+    }
+}
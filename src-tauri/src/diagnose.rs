@@ -0,0 +1,233 @@
+//! Step-by-step connection checks for the active TV, for the troubleshooting checklist: does
+//! the address resolve, does the host answer, which SSAP ports are open, does TLS work and
+//! match the pinned certificate, is the app paired, and is the input socket available. Each
+//! step says what to do when it fails; later steps are skipped when an earlier one rules them
+//! out.
+
+use crate::config::TvConfig;
+use crate::selftest::{CheckResult, CheckStatus};
+use crate::tv::InputCapabilities;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+const TCP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What the app's own session knows; the network checks can't see it.
+pub struct Session {
+    pub connected: bool,
+    /// Input socket state while connected.
+    pub input: Option<InputCapabilities>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionReport {
+    pub tv: String,
+    /// Address the TV's host resolved to.
+    pub address: Option<String>,
+    /// False if any check failed (warnings don't count).
+    pub ok: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+enum Port {
+    Open,
+    /// Something answered with a refusal: the host is up, the port closed.
+    Refused,
+    Silent,
+}
+
+async fn tcp(addr: SocketAddr) -> Port {
+    match tokio::time::timeout(TCP_TIMEOUT, TcpStream::connect(addr)).await {
+        Ok(Ok(_)) => Port::Open,
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => Port::Refused,
+        _ => Port::Silent,
+    }
+}
+
+pub async fn run(name: &str, tv: &TvConfig, session: Session) -> ConnectionReport {
+    let mut checks = Vec::new();
+    let skip = |checks: &mut Vec<CheckResult>, names: &[&str], why: &str| {
+        for name in names {
+            checks.push(CheckResult::new(name, CheckStatus::Skipped, why));
+        }
+    };
+
+    // 1. Address
+    let ip = match crate::tv::resolve(&tv.ip, 3000).await {
+        Some(addr) => {
+            let detail = if addr.ip().to_string() == tv.ip.trim() {
+                format!("Using {}", tv.ip)
+            } else {
+                format!("{} resolves to {}", tv.ip, addr.ip())
+            };
+            checks.push(CheckResult::new("address", CheckStatus::Pass, detail));
+            addr.ip()
+        }
+        None => {
+            checks.push(CheckResult::new(
+                "address",
+                CheckStatus::Fail,
+                format!("{} does not resolve; check the TV's IP or hostname", tv.ip),
+            ));
+            skip(
+                &mut checks,
+                &["reachable", "port_3000", "port_3001", "tls", "pairing", "input_socket"],
+                "Address does not resolve",
+            );
+            return report(name, None, checks);
+        }
+    };
+
+    // 2. Ports, and whether the host answers at all (ICMP needs privileges; a refused TCP
+    //    connection proves the host is up just as well)
+    let (plain, secure) = tokio::join!(
+        tcp(SocketAddr::new(ip, 3000)),
+        tcp(SocketAddr::new(ip, 3001))
+    );
+    let host_up = !matches!((&plain, &secure), (Port::Silent, Port::Silent))
+        || matches!(tcp(SocketAddr::new(ip, 9)).await, Port::Refused);
+    checks.push(if host_up {
+        CheckResult::new("reachable", CheckStatus::Pass, format!("{} answers", ip))
+    } else {
+        CheckResult::new(
+            "reachable",
+            CheckStatus::Fail,
+            format!(
+                "Nothing answers at {}. The TV is off (use Power On), on another network, or \
+                 its address changed",
+                ip
+            ),
+        )
+    });
+    let port_check = |name: &str, port: &Port, wanted: bool| match port {
+        Port::Open => CheckResult::new(name, CheckStatus::Pass, "Open"),
+        _ if !wanted => {
+            CheckResult::new(name, CheckStatus::Skipped, "Closed (not used with this setting)")
+        }
+        Port::Refused => CheckResult::new(
+            name,
+            CheckStatus::Fail,
+            "Closed: the TV is in standby without Quick Start+, or network control is off \
+             (Settings → General → External Devices → TV On With Mobile)",
+        ),
+        Port::Silent => CheckResult::new(name, CheckStatus::Fail, "No answer"),
+    };
+    checks.push(port_check("port_3000", &plain, !tv.use_ssl));
+    checks.push(port_check("port_3001", &secure, tv.use_ssl));
+    // Newer firmware only serves SSAP over TLS, older only in the clear
+    match (tv.use_ssl, &plain, &secure) {
+        (false, Port::Refused | Port::Silent, Port::Open) => checks.push(CheckResult::new(
+            "ssl_setting",
+            CheckStatus::Warn,
+            "Only port 3001 is open: turn on SSL for this TV",
+        )),
+        (true, Port::Open, Port::Refused | Port::Silent) => checks.push(CheckResult::new(
+            "ssl_setting",
+            CheckStatus::Warn,
+            "Only port 3000 is open: turn off SSL for this TV",
+        )),
+        _ => {}
+    }
+
+    // 3. TLS
+    if matches!(secure, Port::Open) {
+        checks.push(check_tls(SocketAddr::new(ip, 3001), tv.cert_fingerprint.clone()).await);
+    } else {
+        skip(&mut checks, &["tls"], "Port 3001 is not open");
+    }
+
+    // 4. Pairing and the session's input socket
+    checks.push(match (&tv.client_key, session.connected) {
+        (Some(_), true) => CheckResult::new("pairing", CheckStatus::Pass, "Paired and connected"),
+        (Some(_), false) => CheckResult::new(
+            "pairing",
+            CheckStatus::Pass,
+            "Paired (client key saved); not connected right now",
+        ),
+        (None, _) => CheckResult::new(
+            "pairing",
+            CheckStatus::Fail,
+            "Not paired: click Authenticate and accept the prompt on the TV",
+        ),
+    });
+    checks.push(match session.input {
+        Some(InputCapabilities { input_socket: true, .. }) => {
+            CheckResult::new("input_socket", CheckStatus::Pass, "Remote buttons available")
+        }
+        Some(InputCapabilities { reason, .. }) => CheckResult::new(
+            "input_socket",
+            CheckStatus::Warn,
+            format!(
+                "The TV refused the input socket ({}); arrow keys and Back are unavailable. \
+                 Try pairing again.",
+                reason.unwrap_or_default()
+            ),
+        ),
+        None => CheckResult::new(
+            "input_socket",
+            CheckStatus::Skipped,
+            "Needs a connection; connect and run again",
+        ),
+    });
+
+    report(name, Some(ip), checks)
+}
+
+fn report(name: &str, address: Option<IpAddr>, checks: Vec<CheckResult>) -> ConnectionReport {
+    ConnectionReport {
+        tv: name.to_string(),
+        address: address.map(|ip| ip.to_string()),
+        ok: checks.iter().all(|c| c.status != CheckStatus::Fail),
+        checks,
+    }
+}
+
+/// TLS handshake on the SSAP port (the TV's certificate is self-signed), compared with the
+/// pinned fingerprint.
+async fn check_tls(addr: SocketAddr, pinned: Option<String>) -> CheckResult {
+    const NAME: &str = "tls";
+    let handshake = tokio::task::spawn_blocking(move || -> Result<String, String> {
+        let stream = std::net::TcpStream::connect_timeout(&addr, TCP_TIMEOUT)
+            .map_err(|e| e.to_string())?;
+        stream.set_read_timeout(Some(TCP_TIMEOUT)).map_err(|e| e.to_string())?;
+        let tls = crate::tv::tls_connector()?
+            .connect(&addr.ip().to_string(), stream)
+            .map_err(|e| e.to_string())?;
+        let der = tls
+            .peer_certificate()
+            .map_err(|e| e.to_string())?
+            .ok_or("no certificate")?
+            .to_der()
+            .map_err(|e| e.to_string())?;
+        Ok(Sha256::digest(&der)
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(":"))
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result);
+    match (handshake, pinned) {
+        (Err(e), _) => {
+            CheckResult::new(NAME, CheckStatus::Fail, format!("Handshake failed: {}", e))
+        }
+        (Ok(actual), Some(pinned)) if !actual.eq_ignore_ascii_case(&pinned) => CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            "The TV's certificate changed since it was pinned. If the TV was reset or replaced, \
+             click Reset certificate pin in settings.",
+        ),
+        (Ok(_), Some(_)) => {
+            CheckResult::new(NAME, CheckStatus::Pass, "Handshake ok; certificate matches the pin")
+        }
+        (Ok(_), None) => CheckResult::new(
+            NAME,
+            CheckStatus::Pass,
+            "Handshake ok; the certificate is pinned on first connect",
+        ),
+    }
+}
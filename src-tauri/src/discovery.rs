@@ -0,0 +1,284 @@
+//! Finding webOS TVs on the local network with SSDP, so setting up a TV doesn't start with
+//! digging its IP out of the router. TVs answer an M-SEARCH for the second-screen service
+//! while they're on (and, with Quick Start+, in standby).
+//!
+//! TVs also announce themselves over mDNS (AirPlay, and webOS on newer firmware). Browsing
+//! those in the background notices a TV coming back on and follows DHCP address changes.
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+const SSDP_ADDR: &str = "239.255.255.250:1900";
+const SEARCH_TARGET: &str = "urn:lge-com:service:webos-second-screen:1";
+/// mDNS service types LG TVs announce. AirPlay is also announced by other vendors' devices,
+/// so those answers are filtered on the manufacturer.
+const MDNS_SERVICES: [&str; 2] = ["_airplay._tcp.local.", "_webos._tcp.local."];
+/// Device descriptions are small XML documents.
+const MAX_DESCRIPTION_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredTv {
+    /// Name set on the TV, e.g. "[LG] webOS TV OLED55C1".
+    pub name: String,
+    pub ip: String,
+    /// Model name from the device description, when it could be read.
+    pub model: Option<String>,
+}
+
+/// Send an M-SEARCH and collect the TVs that answer within `wait`.
+pub async fn discover(wait: Duration) -> Result<Vec<DiscoveredTv>, String> {
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .await
+        .map_err(|e| format!("Could not open discovery socket: {}", e))?;
+    let _ = socket.set_multicast_ttl_v4(2);
+    let mx = wait.as_secs().clamp(1, 5);
+    let search = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: {}\r\nST: {}\r\n\r\n",
+        SSDP_ADDR, mx, SEARCH_TARGET
+    );
+    // UDP may drop one; TVs answer both with the same location, which is deduplicated below
+    for _ in 0..2 {
+        socket
+            .send_to(search.as_bytes(), SSDP_ADDR)
+            .await
+            .map_err(|e| format!("Could not send discovery request: {}", e))?;
+    }
+
+    let deadline = Instant::now() + wait;
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+    let mut buf = [0u8; 2048];
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let Ok((len, from)) = received else {
+            break;
+        };
+        let response = String::from_utf8_lossy(&buf[..len]);
+        let Some(headers) = parse_response(&response) else {
+            continue;
+        };
+        if !seen.insert(from.ip()) {
+            continue;
+        }
+        found.push((from, headers));
+    }
+
+    let mut tvs = Vec::new();
+    for (from, headers) in found {
+        tvs.push(describe(from, &headers).await);
+    }
+    tvs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(tvs)
+}
+
+struct SsdpHeaders {
+    location: Option<String>,
+    /// LG TVs put their (URL-encoded) name in a vendor header.
+    device_name: Option<String>,
+}
+
+/// Headers of a search response for the webOS service, or None for anything else.
+fn parse_response(response: &str) -> Option<SsdpHeaders> {
+    let mut lines = response.lines();
+    if !lines.next()?.contains(" 200 ") {
+        return None;
+    }
+    let mut is_webos = false;
+    let mut headers = SsdpHeaders {
+        location: None,
+        device_name: None,
+    };
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "st" => is_webos = value == SEARCH_TARGET,
+            "location" => headers.location = Some(value.to_string()),
+            "dlnadevicename.lge.com" => headers.device_name = Some(percent_decode(value)),
+            _ => {}
+        }
+    }
+    is_webos.then_some(headers)
+}
+
+/// Name and model for a TV that answered from `from`, read from its device description where
+/// possible. A TV whose description can't be read is still listed with its IP.
+async fn describe(from: SocketAddr, headers: &SsdpHeaders) -> DiscoveredTv {
+    let ip = from.ip();
+    let description = match &headers.location {
+        Some(location) => fetch_description(location, ip).await,
+        None => None,
+    };
+    let tag = |name: &str| description.as_deref().and_then(|d| xml_tag(d, name));
+    let name = headers
+        .device_name
+        .clone()
+        .or_else(|| tag("friendlyName"))
+        .unwrap_or_else(|| format!("LG TV ({})", ip));
+    DiscoveredTv {
+        name,
+        ip: ip.to_string(),
+        model: tag("modelName").or_else(|| tag("modelNumber")),
+    }
+}
+
+/// GET the device description at `location`. Only fetched from the address the answer came
+/// from, so a response can't point discovery at another host.
+async fn fetch_description(location: &str, ip: IpAddr) -> Option<String> {
+    let rest = location.strip_prefix("http://")?;
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let (host, port) = authority.rsplit_once(':').unwrap_or((authority, "80"));
+    if host.parse::<IpAddr>().ok()? != ip {
+        return None;
+    }
+    let port: u16 = port.parse().ok()?;
+    let fetch = async {
+        let mut stream = TcpStream::connect((ip, port)).await.ok()?;
+        let request = format!("GET {} HTTP/1.0\r\nHost: {}\r\n\r\n", path, authority);
+        stream.write_all(request.as_bytes()).await.ok()?;
+        let mut response = Vec::new();
+        stream
+            .take(MAX_DESCRIPTION_BYTES)
+            .read_to_end(&mut response)
+            .await
+            .ok()?;
+        Some(String::from_utf8_lossy(&response).into_owned())
+    };
+    tokio::time::timeout(Duration::from_secs(2), fetch)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Text of the first `<name>` element; enough for UPnP device descriptions.
+fn xml_tag(xml: &str, name: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+    let value = xml[start..end].trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Payload of "tv-discovered": a configured TV announced itself on the network again.
+#[derive(Debug, Clone, Serialize)]
+pub struct TvDiscovered {
+    /// Name of the TV in the config.
+    pub name: String,
+    pub ip: String,
+    pub model: Option<String>,
+    /// The TV came back at a different address, which was saved to its config.
+    pub address_changed: bool,
+}
+
+/// Change in the TVs announced over mDNS.
+#[derive(Debug, Clone)]
+pub enum MdnsEvent {
+    Seen(MdnsTv),
+    /// The announcement with this full name was withdrawn (TV turned off or left).
+    Gone(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct MdnsTv {
+    /// Full mDNS instance name, e.g. "LG OLED55C1._airplay._tcp.local."; one per service.
+    pub fullname: String,
+    pub name: String,
+    pub ip: String,
+    /// AirPlay announces the TV's MAC as its device id.
+    pub mac: Option<String>,
+    pub model: Option<String>,
+}
+
+/// Browse the LG mDNS services until the returned receiver is dropped.
+pub fn browse_mdns() -> Result<mpsc::UnboundedReceiver<MdnsEvent>, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| format!("Could not start mDNS: {}", e))?;
+    let (tx, rx) = mpsc::unbounded_channel();
+    for service in MDNS_SERVICES {
+        let browse = daemon
+            .browse(service)
+            .map_err(|e| format!("Could not browse {}: {}", service, e))?;
+        let (tx, daemon) = (tx.clone(), daemon.clone());
+        tauri::async_runtime::spawn(async move {
+            while let Ok(event) = browse.recv_async().await {
+                let event = match event {
+                    ServiceEvent::ServiceResolved(info) => match lg_tv(service, &info) {
+                        Some(tv) => MdnsEvent::Seen(tv),
+                        None => continue,
+                    },
+                    ServiceEvent::ServiceRemoved(_, fullname) => MdnsEvent::Gone(fullname),
+                    _ => continue,
+                };
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+            let _ = daemon.stop_browse(service);
+        });
+    }
+    Ok(rx)
+}
+
+/// The TV behind a resolved announcement, or None if it isn't an LG TV.
+fn lg_tv(service: &str, info: &ServiceInfo) -> Option<MdnsTv> {
+    let property = |key: &str| {
+        info.get_property_val_str(key)
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    let is_airplay = service.starts_with("_airplay.");
+    if is_airplay
+        && !property("manufacturer").is_some_and(|m| m.to_ascii_lowercase().starts_with("lg"))
+    {
+        return None;
+    }
+    // Prefer IPv4; that's what the rest of the app was configured with
+    let addresses = info.get_addresses();
+    let ip = addresses
+        .iter()
+        .find(|ip| ip.is_ipv4())
+        .or_else(|| addresses.iter().next())?;
+    let fullname = info.get_fullname().to_string();
+    let name = fullname
+        .strip_suffix(service)
+        .map(|n| n.trim_end_matches('.'))
+        .unwrap_or(&fullname)
+        .to_string();
+    Some(MdnsTv {
+        fullname,
+        name,
+        ip: ip.to_string(),
+        mac: if is_airplay { property("deviceid") } else { None },
+        model: property("model"),
+    })
+}
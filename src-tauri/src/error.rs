@@ -0,0 +1,184 @@
+//! Error type for TV operations and the commands built on them. Each kind has a stable
+//! `code` the frontend can branch on ("needs pairing" vs "TV is off" vs "connection lost")
+//! without matching on wording; the message is already localized via the i18n catalog where
+//! one exists. Serializes as `{"code", "message", "key", "params"}`: catalog messages keep
+//! their key and params so the UI can re-localize them; other messages have a null key.
+
+use crate::i18n;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// An error's text, and the catalog key and params it was rendered from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorMessage {
+    pub text: String,
+    pub key: Option<String>,
+    pub params: BTreeMap<String, String>,
+}
+
+impl fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl From<String> for ErrorMessage {
+    fn from(text: String) -> Self {
+        ErrorMessage {
+            text,
+            key: None,
+            params: BTreeMap::new(),
+        }
+    }
+}
+
+impl From<&str> for ErrorMessage {
+    fn from(text: &str) -> Self {
+        text.to_string().into()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AppError {
+    /// No session with the TV.
+    #[error("{0}")]
+    NotConnected(ErrorMessage),
+    #[error("{0}")]
+    NoTvConfigured(ErrorMessage),
+    /// Off, in standby, or not answering on the network.
+    #[error("{0}")]
+    TvOff(ErrorMessage),
+    /// The TV didn't answer in time.
+    #[error("{0}")]
+    Timeout(ErrorMessage),
+    /// The connection dropped while talking to the TV.
+    #[error("{0}")]
+    ConnectionLost(ErrorMessage),
+    /// Another remote app registered with the TV and took over our session.
+    #[error("{0}")]
+    SessionReplaced(ErrorMessage),
+    /// The TV refused the pairing (or the PIN), or the prompt was never accepted.
+    #[error("{0}")]
+    PairingRejected(ErrorMessage),
+    /// The TV answered with an error (explained by `ssap_error`).
+    #[error("{0}")]
+    Tv(ErrorMessage),
+    /// Too many commands already waiting for the TV.
+    #[error("{0}")]
+    Busy(ErrorMessage),
+    #[error("{0}")]
+    Other(ErrorMessage),
+}
+
+impl AppError {
+    /// Error for a message from the i18n catalog; the kind follows from the key.
+    pub fn catalog(key: &str, params: &[(&str, &str)]) -> Self {
+        let message = ErrorMessage {
+            text: i18n::text(key, params),
+            key: Some(key.to_string()),
+            params: params
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        };
+        match key {
+            "not_connected" => AppError::NotConnected(message),
+            "no_tv_configured" => AppError::NoTvConfigured(message),
+            "tv_unreachable" | "tv_standby" | "host_not_found" => AppError::TvOff(message),
+            "connection_timeout" | "command_timeout" => AppError::Timeout(message),
+            "connection_closed" | "pong_timeout" | "send_failed" | "input_send_failed" => {
+                AppError::ConnectionLost(message)
+            }
+            "session_replaced" => AppError::SessionReplaced(message),
+            "registration_timeout" => AppError::PairingRejected(message),
+            "command_queue_full" => AppError::Busy(message),
+            _ => AppError::Other(message),
+        }
+    }
+
+    /// Stable identifier of the kind, sent to the frontend as `code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotConnected(_) => "not_connected",
+            AppError::NoTvConfigured(_) => "no_tv_configured",
+            AppError::TvOff(_) => "tv_off",
+            AppError::Timeout(_) => "timeout",
+            AppError::ConnectionLost(_) => "connection_lost",
+            AppError::SessionReplaced(_) => "session_replaced",
+            AppError::PairingRejected(_) => "pairing_rejected",
+            AppError::Tv(_) => "tv_error",
+            AppError::Busy(_) => "busy",
+            AppError::Other(_) => "other",
+        }
+    }
+
+    /// The same error with `context` in front of its text (e.g. which of several steps
+    /// failed). Code, key and params stay as they were.
+    pub fn context(mut self, context: &str) -> Self {
+        let message = self.message_mut();
+        message.text = format!("{}: {}", context, message.text);
+        self
+    }
+
+    pub fn message(&self) -> &ErrorMessage {
+        match self {
+            AppError::NotConnected(m)
+            | AppError::NoTvConfigured(m)
+            | AppError::TvOff(m)
+            | AppError::Timeout(m)
+            | AppError::ConnectionLost(m)
+            | AppError::SessionReplaced(m)
+            | AppError::PairingRejected(m)
+            | AppError::Tv(m)
+            | AppError::Busy(m)
+            | AppError::Other(m) => m,
+        }
+    }
+
+    fn message_mut(&mut self) -> &mut ErrorMessage {
+        match self {
+            AppError::NotConnected(m)
+            | AppError::NoTvConfigured(m)
+            | AppError::TvOff(m)
+            | AppError::Timeout(m)
+            | AppError::ConnectionLost(m)
+            | AppError::SessionReplaced(m)
+            | AppError::PairingRejected(m)
+            | AppError::Tv(m)
+            | AppError::Busy(m)
+            | AppError::Other(m) => m,
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let message = self.message();
+        let mut error = serializer.serialize_struct("AppError", 4)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &message.text)?;
+        error.serialize_field("key", &message.key)?;
+        error.serialize_field("params", &message.params)?;
+        error.end()
+    }
+}
+
+// Helpers outside tv.rs (config store, ADB, ...) still report plain strings
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message.into())
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.into())
+    }
+}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
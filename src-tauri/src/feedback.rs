@@ -0,0 +1,80 @@
+//! Audible / desktop-notification feedback for actions run while the window is hidden, so a
+//! failed global hotkey is distinguishable from a successful one.
+
+use crate::config::{FeedbackConfig, FeedbackKind};
+use crate::error::AppError;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+/// Give the configured feedback for an action's outcome.
+pub fn report(
+    app: &AppHandle,
+    config: &FeedbackConfig,
+    action_id: &str,
+    result: &Result<(), AppError>,
+) {
+    let kind = if result.is_ok() {
+        config.on_success
+    } else {
+        config.on_failure
+    };
+    match kind {
+        FeedbackKind::None => {}
+        FeedbackKind::Sound => play_sound(result.is_ok()),
+        FeedbackKind::Notification => {
+            let body = match result {
+                Ok(()) => format!("{} done", action_id),
+                Err(e) => format!("{} failed: {}", action_id, e),
+            };
+            show_notification(app, &body);
+        }
+    }
+}
+
+/// Desktop notification from the app. Best effort: failures are only logged.
+pub fn show_notification(app: &AppHandle, body: &str) {
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title("LG TV Remote")
+        .body(body)
+        .show()
+    {
+        log::warn!("Failed to show notification: {}", e);
+    }
+}
+
+/// Play a short system sound without blocking. Best effort: a missing player is only logged.
+fn play_sound(success: bool) {
+    #[cfg(target_os = "macos")]
+    let (program, args): (&str, Vec<&str>) = (
+        "afplay",
+        vec![if success {
+            "/System/Library/Sounds/Glass.aiff"
+        } else {
+            "/System/Library/Sounds/Basso.aiff"
+        }],
+    );
+    #[cfg(target_os = "windows")]
+    let (program, args): (&str, Vec<&str>) = (
+        "powershell",
+        vec![
+            "-NoProfile",
+            "-Command",
+            if success {
+                "[System.Media.SystemSounds]::Asterisk.Play()"
+            } else {
+                "[System.Media.SystemSounds]::Hand.Play()"
+            },
+        ],
+    );
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let (program, args): (&str, Vec<&str>) = (
+        "canberra-gtk-play",
+        vec!["-i", if success { "complete" } else { "dialog-error" }],
+    );
+
+    if let Err(e) = std::process::Command::new(program).args(&args).spawn() {
+        log::warn!("Failed to play feedback sound via {}: {}", program, e);
+    }
+}
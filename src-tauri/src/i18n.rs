@@ -0,0 +1,129 @@
+//! Message catalog for backend errors and statuses. Each message has a key and named params;
+//! the backend renders it in the configured `locale` (falling back to English).
+//!
+//! Errors are built with `AppError::catalog`, which also picks the error kind from the key,
+//! so the UI can react to a backend error by its `code` without matching on its wording.
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Locales with a column in `CATALOG`, in column order.
+pub const LOCALES: &[&str] = &["en", "de"];
+
+/// key -> template per locale (same order as `LOCALES`). `{name}` is replaced by the param.
+const CATALOG: &[(&str, [&str; 2])] = &[
+    ("not_connected", ["Not connected", "Nicht verbunden"]),
+    ("no_tv_configured", ["No TV configured", "Kein Fernseher eingerichtet"]),
+    (
+        "tv_unreachable",
+        [
+            "TV is off or unreachable — use Power On",
+            "Fernseher ist aus oder nicht erreichbar – Einschalten verwenden",
+        ],
+    ),
+    (
+        "tv_standby",
+        [
+            "TV is in standby (Quick Start+). Use Power On first.",
+            "Fernseher ist im Standby (Quick Start+). Zuerst einschalten.",
+        ],
+    ),
+    (
+        "host_not_found",
+        [
+            "Could not find {host} on the network — check the TV's hostname or IP",
+            "{host} wurde im Netzwerk nicht gefunden – Hostname oder IP des Fernsehers prüfen",
+        ],
+    ),
+    ("connection_timeout", ["Connection timeout", "Zeitüberschreitung beim Verbinden"]),
+    ("connection_closed", ["Connection closed", "Verbindung geschlossen"]),
+    (
+        "connection_manager_stopped",
+        [
+            "Internal error: the TV connection stopped working. Restart the app.",
+            "Interner Fehler: Die Verbindung zum Fernseher funktioniert nicht mehr. App neu starten.",
+        ],
+    ),
+    (
+        "pong_timeout",
+        [
+            "The TV stopped responding (connection lost)",
+            "Der Fernseher antwortet nicht mehr (Verbindung verloren)",
+        ],
+    ),
+    (
+        "registration_timeout",
+        [
+            "Registration timeout - check TV for pairing prompt",
+            "Zeitüberschreitung beim Koppeln – Abfrage am Fernseher prüfen",
+        ],
+    ),
+    (
+        "send_failed",
+        [
+            "Send failed (disconnected): {error}",
+            "Senden fehlgeschlagen (getrennt): {error}",
+        ],
+    ),
+    (
+        "input_send_failed",
+        [
+            "Input send failed (disconnected): {error}",
+            "Tastendruck fehlgeschlagen (getrennt): {error}",
+        ],
+    ),
+    (
+        "command_timeout",
+        [
+            "Command timeout (disconnected)",
+            "Zeitüberschreitung des Befehls (getrennt)",
+        ],
+    ),
+    (
+        "session_replaced",
+        [
+            "Another remote app took over the TV connection. Connect again to take it back (the other app will be disconnected).",
+            "Eine andere Fernbedienungs-App hat die Verbindung übernommen. Erneut verbinden, um sie zurückzuholen (die andere App wird getrennt).",
+        ],
+    ),
+    (
+        "command_queue_full",
+        [
+            "Too many commands waiting for the TV — try again",
+            "Zu viele Befehle warten auf den Fernseher – erneut versuchen",
+        ],
+    ),
+];
+
+static LOCALE: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+/// Normalize a locale like "de-DE" or "de_AT" to a supported one, if any.
+pub fn supported(locale: &str) -> Option<&'static str> {
+    let lang = locale.split(['-', '_']).next().unwrap_or("").to_lowercase();
+    LOCALES.iter().copied().find(|l| *l == lang)
+}
+
+/// Set the locale messages are rendered in (from `Config::locale`); None means English.
+pub fn set_locale(locale: Option<&str>) {
+    *LOCALE.lock().unwrap() = locale.and_then(supported).map(String::from);
+}
+
+fn template(key: &str) -> Option<&'static str> {
+    let locale = LOCALE.lock().unwrap().clone();
+    let column = LOCALES
+        .iter()
+        .position(|l| Some(*l) == locale.as_deref())
+        .unwrap_or(0);
+    CATALOG
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, templates)| templates[column])
+}
+
+/// Render `key` in the current locale. Unknown keys render as the key itself.
+pub fn text(key: &str, params: &[(&str, &str)]) -> String {
+    let mut message = template(key).unwrap_or(key).to_string();
+    for (name, value) in params {
+        message = message.replace(&format!("{{{}}}", name), value);
+    }
+    message
+}
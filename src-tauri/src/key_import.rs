@@ -0,0 +1,305 @@
+//! Client keys from other LG webOS tools, so someone moving over from a command-line tool or
+//! Home Assistant keeps their pairing instead of accepting a second key on the TV:
+//!
+//! - LGWebOSRemote (`lgtv`): `~/.lgtv/config.json`, TV name -> `{ "key", "ip", "mac" }`
+//! - lgtv2 (Node): `~/.lgtv2/keyfile-<host>`, the bare key
+//! - aiopylgtv / bscpylgtv: a JSON file mapping host -> key, or with the `aiopylgtv-sqlite`
+//!   feature their default `~/.aiopylgtv.sqlite` (SqliteDict: host -> pickled key)
+//! - Home Assistant: the `webostv` entries in `.storage/core.config_entries`, and the legacy
+//!   `webostv.conf` (host -> key)
+
+use crate::config::{Config, TvConfig};
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A client key found in another tool's files.
+#[derive(Debug, Clone)]
+pub struct FoundKey {
+    /// Address the key was issued for.
+    pub host: String,
+    /// Name the other tool gave the TV, if it has one.
+    pub name: Option<String>,
+    pub mac: Option<String>,
+    pub key: String,
+    /// File it came from, for the log.
+    pub source: PathBuf,
+}
+
+/// What an import changed, for the UI.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KeyImportSummary {
+    /// Configured TVs that got a key.
+    pub paired: Vec<String>,
+    /// TVs that weren't configured and were added with their key.
+    pub added: Vec<String>,
+    /// Configured TVs that kept their own, different key (pass `replace` to take the other one).
+    pub skipped: Vec<String>,
+}
+
+/// Files the tools write by default, relative to the home directory.
+const KNOWN_FILES: &[&str] = &[
+    ".lgtv/config.json",
+    ".aiopylgtv.sqlite",
+    ".homeassistant/.storage/core.config_entries",
+    ".homeassistant/webostv.conf",
+];
+
+/// Collect keys from `path` (a file or a directory such as a Home Assistant config dir), or
+/// from the tools' default locations when None. Unreadable files are skipped.
+pub fn scan(path: Option<&Path>) -> Vec<FoundKey> {
+    let mut found = Vec::new();
+    match path {
+        Some(path) if path.is_dir() => scan_dir(path, &mut found),
+        Some(path) => read_file(path, &mut found),
+        None => {
+            let Some(home) = dirs::home_dir() else {
+                return found;
+            };
+            for file in KNOWN_FILES {
+                let path = home.join(file);
+                if path.is_file() {
+                    read_file(&path, &mut found);
+                }
+            }
+            scan_dir(&home.join(".lgtv2"), &mut found);
+        }
+    }
+    found.retain(|k| !k.host.is_empty() && looks_like_key(&k.key));
+    found
+}
+
+/// Client keys are short alphanumeric tokens (32 hex digits in practice); anything else in a
+/// host -> string map is some other setting.
+fn looks_like_key(key: &str) -> bool {
+    key.len() >= 16 && key.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn scan_dir(dir: &Path, found: &mut Vec<FoundKey>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_file()
+            && (name.starts_with("keyfile-")
+                || name.ends_with(".sqlite")
+                || name == "config.json"
+                || name == "webostv.conf")
+        {
+            read_file(&path, found);
+        }
+    }
+    let entries = dir.join(".storage").join("core.config_entries");
+    if entries.is_file() {
+        read_file(&entries, found);
+    }
+}
+
+/// Tell the format from the contents and read the keys.
+fn read_file(path: &Path, found: &mut Vec<FoundKey>) {
+    let Ok(bytes) = fs::read(path) else {
+        return;
+    };
+    let before = found.len();
+    if bytes.starts_with(b"SQLite format 3\0") {
+        #[cfg(feature = "aiopylgtv-sqlite")]
+        if let Err(e) = read_sqlite_dict(path, found) {
+            log::debug!("Could not read keys from {}: {}", path.display(), e);
+        }
+        #[cfg(not(feature = "aiopylgtv-sqlite"))]
+        log::info!(
+            "Skipping {}: built without the aiopylgtv-sqlite feature",
+            path.display()
+        );
+    } else if let Ok(json) = serde_json::from_slice::<Value>(&bytes) {
+        read_json(&json, path, found);
+    } else if let Some(host) = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.strip_prefix("keyfile-"))
+    {
+        // lgtv2 writes the key alone, named after the host (dots kept, or as underscores)
+        found.push(FoundKey {
+            host: host.replace('_', "."),
+            name: None,
+            mac: None,
+            key: String::from_utf8_lossy(&bytes).trim().to_string(),
+            source: path.to_path_buf(),
+        });
+    }
+    if found.len() > before {
+        log::info!(
+            "Found {} client key(s) in {}",
+            found.len() - before,
+            path.display()
+        );
+    }
+}
+
+fn read_json(json: &Value, path: &Path, found: &mut Vec<FoundKey>) {
+    let source = path.to_path_buf();
+    // Home Assistant config entries
+    if let Some(entries) = json.pointer("/data/entries").and_then(Value::as_array) {
+        for entry in entries.iter().filter(|e| e["domain"] == "webostv") {
+            let data = &entry["data"];
+            let key = data["client_secret"].as_str().or(data["key"].as_str());
+            if let (Some(host), Some(key)) = (data["host"].as_str(), key) {
+                found.push(FoundKey {
+                    host: host.to_string(),
+                    name: entry["title"].as_str().map(str::to_string),
+                    mac: None,
+                    key: key.to_string(),
+                    source: source.clone(),
+                });
+            }
+        }
+        return;
+    }
+    let Some(map) = json.as_object() else {
+        return;
+    };
+    for (name, value) in map {
+        match value {
+            // host -> key (webostv.conf, aiopylgtv/bscpylgtv JSON)
+            Value::String(key) => found.push(FoundKey {
+                host: name.clone(),
+                name: None,
+                mac: None,
+                key: key.clone(),
+                source: source.clone(),
+            }),
+            // LGWebOSRemote: name -> { key, ip, mac }
+            Value::Object(tv) => {
+                let host = tv.get("ip").or(tv.get("hostname")).and_then(Value::as_str);
+                if let (Some(host), Some(key)) = (host, tv.get("key").and_then(Value::as_str)) {
+                    found.push(FoundKey {
+                        host: host.to_string(),
+                        name: Some(name.clone()),
+                        mac: tv.get("mac").and_then(Value::as_str).map(str::to_string),
+                        key: key.to_string(),
+                        source: source.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// aiopylgtv's SqliteDict: table `unnamed`, host -> pickled key string.
+#[cfg(feature = "aiopylgtv-sqlite")]
+fn read_sqlite_dict(path: &Path, found: &mut Vec<FoundKey>) -> Result<(), rusqlite::Error> {
+    let db =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut query = db.prepare("SELECT key, value FROM unnamed")?;
+    let rows = query.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+    })?;
+    for (host, value) in rows.flatten() {
+        if let Some(key) = unpickle_str(&value) {
+            found.push(FoundKey {
+                host,
+                name: None,
+                mac: None,
+                key,
+                source: path.to_path_buf(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Decode a pickled Python `str` (protocol 2+), the only value SqliteDict stores for a key.
+#[cfg(feature = "aiopylgtv-sqlite")]
+fn unpickle_str(data: &[u8]) -> Option<String> {
+    let mut data = data;
+    // PROTO <version>, then FRAME <u64 length> from protocol 4 on
+    if let [0x80, _, rest @ ..] = data {
+        data = rest;
+    }
+    if let [0x95, rest @ ..] = data {
+        data = rest.get(8..)?;
+    }
+    let (len, rest) = match data {
+        // SHORT_BINUNICODE
+        [0x8c, len, rest @ ..] => (*len as usize, rest),
+        // BINUNICODE
+        [b'X', a, b, c, d, rest @ ..] => (u32::from_le_bytes([*a, *b, *c, *d]) as usize, rest),
+        _ => return None,
+    };
+    String::from_utf8(rest.get(..len)?.to_vec()).ok()
+}
+
+/// Give configured TVs the keys found for their address; with `add_missing`, TVs that aren't
+/// configured yet are added. A TV that already has a different key keeps it unless `replace`.
+pub fn apply(
+    config: &mut Config,
+    found: Vec<FoundKey>,
+    add_missing: bool,
+    replace: bool,
+) -> KeyImportSummary {
+    let mut summary = KeyImportSummary::default();
+    for key in found {
+        let existing = config
+            .tvs
+            .iter_mut()
+            .find(|(_, tv)| tv.ip == key.host || tv.last_seen_ip.as_deref() == Some(&key.host));
+        match existing {
+            Some((name, tv)) => {
+                let name = name.clone();
+                match &tv.client_key {
+                    Some(current) if *current == key.key => continue,
+                    Some(_) if !replace => {
+                        summary.skipped.push(name);
+                        continue;
+                    }
+                    _ => {}
+                }
+                tv.client_key = Some(key.key);
+                if tv.mac.is_none() {
+                    tv.mac = key.mac;
+                }
+                log::info!(
+                    "Imported client key for {} from {}",
+                    name,
+                    key.source.display()
+                );
+                summary.paired.push(name);
+            }
+            None if add_missing => {
+                let mut name = key
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("LG TV ({})", key.host));
+                if config.tvs.contains_key(&name) {
+                    name = format!("{} ({})", name, key.host);
+                }
+                log::info!(
+                    "Added {} with client key from {}",
+                    name,
+                    key.source.display()
+                );
+                config.set_tv(
+                    name.clone(),
+                    TvConfig {
+                        ip: key.host,
+                        client_key: Some(key.key),
+                        mac: key.mac,
+                        ..Default::default()
+                    },
+                );
+                summary.added.push(name);
+            }
+            None => {}
+        }
+    }
+    summary.paired.sort();
+    summary.paired.dedup();
+    summary.added.sort();
+    summary.skipped.sort();
+    summary.skipped.dedup();
+    summary
+}
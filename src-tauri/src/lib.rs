@@ -0,0 +1,14 @@
+//! The TV connection layer: the SSAP client, the connection manager that queues work for it,
+//! and the config and error types they use. Split out of the app so benches can drive it
+//! against a mock TV; main.rs builds the tray app on top.
+
+pub mod buttons;
+pub mod config;
+pub mod connection;
+pub mod error;
+pub mod i18n;
+pub mod netif;
+pub mod ssap_error;
+pub mod tasks;
+pub mod trace;
+pub mod tv;
@@ -1,23 +1,61 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod config;
-mod tv;
+mod actions;
+mod api_schema;
+mod app_icons;
+mod automation_log;
+mod config_store;
+mod config_transfer;
+mod diagnose;
+mod discovery;
+mod feedback;
+mod key_import;
+mod neighbors;
+mod parental;
+mod popup;
+mod presence;
+mod reconnect;
+mod selftest;
+mod shortcut;
+mod sleep_watch;
+mod state_cache;
+mod subscriptions;
+mod usage;
 
-use config::{ActionShortcutConfig, Config, StreamingDeviceConfig, TvConfig, WindowSize};
+use lgtv_tray_remote::{buttons, config, connection, error, i18n, netif, tv};
+
+use actions::{ActionExecuted, ActionSource, MacroRecorder};
+use automation_log::{AutomationEntry, AutomationLog, AutomationOutcome};
+use config::{
+    ActionShortcutConfig, Config, DEFAULT_STREAMING_DEVICE, FeedbackConfig, MacroStep,
+    ParentalConfig, Scene, ShortcutRepeat, TimeoutConfig,
+    StreamingDeviceConfig, TrayClickAction, TrayMenuItem, TvCapabilities, TvConfig, TvProfile,
+    WindowBehavior, WindowSize, WolConfig,
+};
+use config_store::ConfigStore;
+use connection::ConnectionManager;
+use error::AppError;
+use popup::{FocusLoss, PendingHide};
+use subscriptions::{EventKind, EventSubscriptions};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use tauri::{
     image::Image,
-    menu::{MenuBuilder, MenuItemBuilder},
+    menu::MenuBuilder,
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     AppHandle, Emitter, Manager, PhysicalPosition, WebviewWindow,
 };
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 use tauri_plugin_updater::UpdaterExt;
-use tokio::sync::Mutex;
-use tv::{CommandResult, TvConnection};
+use tv::{
+    BluetoothDevice, CommandResult, CurrentChannel, EnergySavingLevel, ForegroundApp,
+    InputCapabilities, InputSource, Latency, PinSubmission, PowerState, SoundMode, SystemInfo,
+    TvApp, TvConnection, TvNotification, TvUpdateStatus, VolumeStatus,
+};
+use state_cache::{StateCache, TvSnapshot};
+use usage::{EnergyEstimate, EnergyPeriod, UsageChange, UsageHistory, UsageStats};
 
 #[cfg(feature = "autostart")]
 use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
@@ -27,9 +65,14 @@ use auto_launch::LinuxLaunchMode;
 // Track window visibility ourselves since is_visible() can be unreliable
 static WINDOW_VISIBLE: AtomicBool = AtomicBool::new(false);
 
-// When true, cancel the pending hide scheduled on Focused(false) (e.g. user is resizing).
-#[cfg(target_os = "windows")]
-static CANCEL_PENDING_HIDE: AtomicBool = AtomicBool::new(false);
+// Current platform's window behavior from config; read from the (sync) window event handler.
+static HIDE_ON_FOCUS_LOSS: AtomicBool = AtomicBool::new(true);
+static HIDE_DELAY_MS: AtomicU64 = AtomicU64::new(0);
+
+fn apply_window_behavior(behavior: &WindowBehavior) {
+    HIDE_ON_FOCUS_LOSS.store(behavior.hide_on_focus_loss, Ordering::SeqCst);
+    HIDE_DELAY_MS.store(behavior.hide_delay_ms, Ordering::SeqCst);
+}
 
 /// On Windows with decorations: false, the OS adds ~16×9 to inner size to get outer.
 /// We store inner size in config so set_size(saved) reproduces the same window.
@@ -50,15 +93,80 @@ fn outer_to_inner_size(outer_w: u32, outer_h: u32) -> (u32, u32) {
 }
 
 struct AppState {
-    tv: Mutex<TvConnection>,
-    config: Mutex<Config>,
+    /// Owns the TV connection; commands, keepalive and shortcuts queue jobs on it.
+    tv: ConnectionManager,
+    config: ConfigStore,
+    keepalive: std::sync::Mutex<KeepaliveState>,
+    /// Long-running operation in progress (pairing, connecting, ...), shown in the tray.
+    busy: tokio::sync::watch::Sender<Option<String>>,
+    /// Set while a macro is being recorded.
+    recorder: std::sync::Mutex<Option<MacroRecorder>>,
+    /// Background auto-reconnect schedule.
+    reconnector: reconnect::Reconnector,
+    /// Repeat tasks for held action shortcuts, by action id.
+    repeats: std::sync::Mutex<HashMap<String, tauri::async_runtime::JoinHandle<()>>>,
+    /// Optional subscriptions requested with `subscribe_event`.
+    event_subscriptions: std::sync::Mutex<EventSubscriptions>,
+    usage: UsageHistory,
+    /// Last volume/mute state pushed by the TV.
+    volume: std::sync::Mutex<Option<VolumeStatus>>,
+    /// Latest subscription-derived state, persisted to state.json (throttled).
+    tv_state: StateCache,
+    /// Journal of automations run without a user action (automations.jsonl).
+    automations: AutomationLog,
+    /// TV notifications already shown on the desktop this run, by id.
+    mirrored_notifications: std::sync::Mutex<std::collections::HashSet<String>>,
+    /// Wakes the parental enforcer for an immediate check (TV turned on, schedule changed).
+    parental_check: tokio::sync::Notify,
+    /// Where `submit_pin` sends the PIN while a PIN pairing is waiting for it.
+    pin_pairing: std::sync::Mutex<Option<tokio::sync::mpsc::Sender<PinSubmission>>>,
+    /// Hide scheduled after the window lost focus.
+    pending_hide: PendingHide,
+    /// Last result of the presence monitor for the active TV.
+    presence: std::sync::Mutex<Option<presence::Presence>>,
+}
+
+impl AppState {
+    /// Mark `operation` as in progress until the returned guard is dropped.
+    fn begin_busy(&self, operation: &str) -> BusyGuard<'_> {
+        self.busy.send_replace(Some(operation.to_string()));
+        BusyGuard { state: self }
+    }
+
+    fn record_usage(&self, tv: &str, change: UsageChange) {
+        self.usage.record(tv, change);
+    }
+
+    /// Append an action to the macro being recorded, if any.
+    fn record_action(&self, action_id: &str) {
+        if let Some(recorder) = self.recorder.lock().unwrap().as_mut() {
+            recorder.push(action_id);
+        }
+    }
+
+    /// Resolve a button name or alias to the name the TV expects.
+    async fn resolve_button(&self, name: &str) -> String {
+        self.config
+            .read(|c| buttons::resolve(name, &c.button_aliases))
+            .await
+    }
+}
+
+struct BusyGuard<'a> {
+    state: &'a AppState,
+}
+
+impl Drop for BusyGuard<'_> {
+    fn drop(&mut self) {
+        self.state.busy.send_replace(None);
+    }
 }
 
 // ============ Tauri Commands ============
 
 #[tauri::command]
-async fn get_config(state: tauri::State<'_, Arc<AppState>>) -> Result<Config, String> {
-    let config = state.config.lock().await;
+async fn get_config(state: tauri::State<'_, Arc<AppState>>) -> Result<Config, AppError> {
+    let config = state.config.get().await;
     Ok(config.clone())
 }
 
@@ -68,284 +176,2465 @@ async fn save_tv(
     name: String,
     ip: String,
     use_ssl: bool,
-) -> Result<(), String> {
-    let mut config = state.config.lock().await;
-    config.set_tv(
-        name,
-        TvConfig {
-            ip,
-            use_ssl,
-            client_key: None,
-            mac: None,
-        },
-    );
-    config.save()
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            config.set_tv(
+                name,
+                TvConfig {
+                    ip,
+                    use_ssl,
+                    ..Default::default()
+                },
+            );
+            Ok(())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn remove_tv(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            if config.tvs.get(&name).is_some_and(|tv| tv.system) {
+                return Err(format!("{} is managed system-wide and can't be removed", name).into());
+            }
+            if !config.remove_tv(&name) {
+                return Err("TV not found".into());
+            }
+            Ok(())
+        })
+        .await?;
+
+    state
+        .tv
+        .run(move |tv| {
+            Box::pin(async move {
+                if tv.name == name {
+                    tv.disconnect().await;
+                }
+            })
+        })
+        .await?;
+    Ok(())
 }
 
 #[tauri::command]
 async fn set_active_tv(
     state: tauri::State<'_, Arc<AppState>>,
     name: String,
-) -> Result<(), String> {
-    let mut config = state.config.lock().await;
-    if config.tvs.contains_key(&name) {
-        config.active_tv = Some(name);
-        config.save()
-    } else {
-        Err("TV not found".to_string())
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            if !config.tvs.contains_key(&name) {
+                return Err("TV not found".into());
+            }
+            config.active_tv = Some(name);
+            Ok(())
+        })
+        .await
+}
+
+/// The keepalive is considered stalled if it hasn't started a cycle in this long: three
+/// intervals, and never less than one slow cycle (two command timeouts + the 3s retry delay).
+fn keepalive_stall_after(timeouts: &TimeoutConfig) -> std::time::Duration {
+    let slow_cycle = timeouts.command_timeout() * 2 + std::time::Duration::from_secs(3);
+    (timeouts.keepalive_interval() * 3).max(timeouts.keepalive_interval() + slow_cycle)
+}
+
+/// Current keepalive task and its heartbeat, watched by the keepalive watchdog.
+/// `generation` lets an exiting task tell whether it has already been replaced.
+#[derive(Default)]
+struct KeepaliveState {
+    generation: u64,
+    handle: Option<tokio::task::JoinHandle<()>>,
+    last_run: Option<std::time::Instant>,
+}
+
+fn keepalive_heartbeat(state: &AppState, generation: u64) {
+    let mut ks = state.keepalive.lock().unwrap();
+    if ks.generation == generation {
+        ks.last_run = Some(std::time::Instant::now());
     }
 }
 
-/// Spawns a background task that pings the TV every 25s while connected.
-/// Never stops pinging until connection is dropped or disconnected.
-/// Emits "connection-lost" to the frontend when keepalive detects a dead connection.
-fn spawn_keepalive(state: Arc<AppState>, app: tauri::AppHandle) {
-    tauri::async_runtime::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(25));
+/// Outcome of one keepalive round.
+enum KeepaliveCycle {
+    NotConnected,
+    Alive,
+    /// The ping worked but the input socket couldn't be refreshed.
+    InputSocketFailed(AppError),
+    Lost(AppError),
+    /// Another remote app registered with the TV and took over the session.
+    Replaced(AppError),
+}
+
+/// Check the session and refresh the input socket. The session's reader task pings the TV
+/// and closes the session when it stops answering; this notices that, and keeps the input
+/// socket fresh. Each step is a short connection job and the TV's replies are awaited outside
+/// the queue, so hotkeys don't wait behind a slow TV. The heartbeat is taken when the first
+/// job runs, so a queue that never gets to it looks stalled to the watchdog.
+async fn keepalive_cycle(state: &Arc<AppState>, generation: u64) -> KeepaliveCycle {
+    let job_state = state.clone();
+    let status = state
+        .tv
+        .run(move |tv| {
+            Box::pin(async move {
+                keepalive_heartbeat(&job_state, generation);
+                (tv.connected, tv.session_replaced, tv.link_error(), tv.power_state)
+            })
+        })
+        .await;
+    let (connected, replaced, link_error, power) = match status {
+        Ok(status) => status,
+        Err(e) => return KeepaliveCycle::Lost(e),
+    };
+    // A command may already have found the session taken over since the last round
+    if replaced {
+        return KeepaliveCycle::Replaced(AppError::catalog("session_replaced", &[]));
+    }
+    if !connected {
+        return KeepaliveCycle::NotConnected;
+    }
+    if let Some(e) = link_error {
+        log::debug!("Keepalive: SSAP socket is down: {}", e);
+        let replaced = state
+            .tv
+            .run(|tv| {
+                Box::pin(async move {
+                    tv.connected = false;
+                    tv.detect_session_replaced().await
+                })
+            })
+            .await
+            .unwrap_or(false);
+        if replaced {
+            return KeepaliveCycle::Replaced(AppError::catalog("session_replaced", &[]));
+        }
+        return KeepaliveCycle::Lost(e);
+    }
+    log::debug!("Keepalive: ok");
+    // The input socket isn't usable while the panel is off; the power state subscription
+    // keeps `power_state` current
+    if power == PowerState::Standby {
+        log::debug!("Keepalive: TV in standby, skipping input socket refresh");
+        return KeepaliveCycle::Alive;
+    }
+    // Refresh input socket (d-pad, enter, back, etc.) so it doesn't go stale;
+    // the TV can close it while the main SSAP socket stays open.
+    log::debug!("Keepalive: refreshing input socket");
+    match refresh_input_socket(state).await {
+        Ok(()) => {
+            log::debug!("Keepalive: input socket refreshed");
+            KeepaliveCycle::Alive
+        }
+        Err(e) => KeepaliveCycle::InputSocketFailed(e),
+    }
+}
+
+/// Reopen the input socket, asking the TV for its path and opening it outside the connection
+/// queue; only swapping in the new socket runs as a job.
+async fn refresh_input_socket(state: &AppState) -> Result<(), AppError> {
+    let response = state.tv.request(tv::INPUT_SOCKET_URI, None).await?;
+    let (socket_path, use_ssl, pinned) = state
+        .tv
+        .run(move |tv| {
+            Box::pin(async move {
+                let path = tv.input_socket_path(&response)?;
+                Ok::<_, AppError>((path, tv.use_ssl, tv.cert_fingerprint.clone()))
+            })
+        })
+        .await??;
+    let socket = TvConnection::open_input_socket(&socket_path, use_ssl, pinned).await?;
+    state
+        .tv
+        .run(move |tv| Box::pin(async move { tv.install_input_socket(socket).await }))
+        .await?;
+    Ok(())
+}
+
+/// Spawns a background task that checks the session every keepalive interval (from
+/// `tv.timeouts`) and as soon as the session's reader task stops, replacing any previous
+/// keepalive. Runs as a session task of `tv`, so it
+/// is cancelled on disconnect. Emits "connection-lost" to the frontend when keepalive detects
+/// a dead connection, or "session-replaced" (with guidance) when another remote app took it.
+fn spawn_keepalive(state: Arc<AppState>, app: tauri::AppHandle, tv: &TvConnection) {
+    let mut ks = state.keepalive.lock().unwrap();
+    if let Some(old) = ks.handle.take() {
+        old.abort();
+    }
+    ks.generation += 1;
+    ks.last_run = Some(std::time::Instant::now());
+    let generation = ks.generation;
+    let keepalive_interval = tv.timeouts.keepalive_interval();
+    let link_down = tv.link_down();
+    let name = tv.name.clone();
+
+    let task_state = state.clone();
+    ks.handle = Some(tv.tasks.spawn(async move {
+        let state = task_state;
+        let mut interval = tokio::time::interval(keepalive_interval);
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
         loop {
-            interval.tick().await;
-            let mut tv = state.tv.lock().await;
-            if !tv.connected {
-                log::debug!("Keepalive: exiting (not connected)");
-                break;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = link_down.notified() => log::debug!("Keepalive: SSAP socket closed"),
             }
-            log::debug!("Keepalive: sending ping");
-            match tv.keepalive_ping().await {
-                Ok(()) => {
-                    log::debug!("Keepalive: ok");
-                    // Refresh input socket (d-pad, enter, back, etc.) so it doesn't go stale;
-                    // the TV can close it while the main SSAP socket stays open.
-                    log::debug!("Keepalive: refreshing input socket");
-                    match tv.refresh_input_socket().await {
-                        Ok(()) => log::debug!("Keepalive: input socket refreshed"),
-                        Err(e) => {
-                            log::warn!("Keepalive: refresh input socket failed: {} (retrying in 3s)", e);
-                            drop(tv);
-                            tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-                            let mut tv = state.tv.lock().await;
-                            if tv.connected {
-                                if let Err(e2) = tv.refresh_input_socket().await {
-                                    log::warn!("Keepalive: refresh input socket failed again: {} (will retry next cycle)", e2);
-                                } else {
-                                    log::debug!("Keepalive: input socket refreshed on retry");
-                                }
-                            }
-                        }
+            let cycle = keepalive_cycle(&state, generation).await;
+            match cycle {
+                KeepaliveCycle::NotConnected => {
+                    log::debug!("Keepalive: exiting (not connected)");
+                    break;
+                }
+                KeepaliveCycle::Alive => {}
+                KeepaliveCycle::InputSocketFailed(e) => {
+                    log::warn!("Keepalive: refresh input socket failed: {} (retrying in 3s)", e);
+                    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+                    let connected = state
+                        .tv
+                        .run(|tv| Box::pin(async move { tv.connected }))
+                        .await
+                        .unwrap_or(false);
+                    if !connected {
+                        continue;
+                    }
+                    match refresh_input_socket(&state).await {
+                        Ok(()) => log::debug!("Keepalive: input socket refreshed on retry"),
+                        Err(e2) => log::warn!("Keepalive: refresh input socket failed again: {} (will retry next cycle)", e2),
                     }
                 }
-                Err(e) => {
+                KeepaliveCycle::Lost(e) => {
                     log::warn!("Keepalive failed, connection dropped: {}", e);
+                    // Off, or only unreachable: either way nothing is known until the next connect
+                    state.record_usage(&name, UsageChange::Power(PowerState::Unknown));
                     if let Some(window) = app.get_webview_window("main") {
                         let _ = window.emit("connection-lost", ());
                     }
                     break;
                 }
+                KeepaliveCycle::Replaced(e) => {
+                    log::warn!("Keepalive: session taken over by another remote app");
+                    set_tray_status(&app, Some("Taken over by another app"));
+                    let _ = app.emit("session-replaced", e.to_string());
+                    break;
+                }
+            }
+        }
+
+        let mut ks = state.keepalive.lock().unwrap();
+        if ks.generation == generation {
+            ks.handle = None;
+            ks.last_run = None;
+        }
+    }));
+}
+
+/// Watches the keepalive heartbeat. If the keepalive task is wedged (e.g. starved behind a
+/// long connection job) it is aborted and respawned, and "keepalive-stalled" is emitted.
+fn spawn_keepalive_watchdog(state: Arc<AppState>, app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let timeouts = state.config.read(|c| c.timeouts.clamped()).await;
+            tokio::time::sleep(timeouts.keepalive_interval()).await;
+            let stall_after = keepalive_stall_after(&timeouts);
+            let stalled_for = {
+                let ks = state.keepalive.lock().unwrap();
+                // A finished handle means the session was cancelled, not that the task is stuck
+                match (&ks.handle, ks.last_run) {
+                    (Some(h), Some(last))
+                        if !h.is_finished() && last.elapsed() > stall_after =>
+                    {
+                        Some(last.elapsed())
+                    }
+                    _ => None,
+                }
+            };
+            if let Some(elapsed) = stalled_for {
+                log::warn!(
+                    "Keepalive watchdog: no heartbeat for {}s, restarting keepalive",
+                    elapsed.as_secs()
+                );
+                let _ = app.emit("keepalive-stalled", elapsed.as_secs());
+                let (job_state, job_app) = (state.clone(), app.clone());
+                let restart = state.tv.run(move |tv| {
+                    Box::pin(async move {
+                        // spawn_keepalive aborts the stalled task
+                        if tv.connected {
+                            spawn_keepalive(job_state, job_app, tv);
+                        }
+                        tv.connected
+                    })
+                });
+                match tokio::time::timeout(std::time::Duration::from_secs(5), restart).await {
+                    Ok(Ok(true)) => {}
+                    Ok(Ok(false)) => {
+                        log::debug!("Keepalive watchdog: not connected, not restarting");
+                        if let Some(old) = state.keepalive.lock().unwrap().handle.take() {
+                            old.abort();
+                        }
+                    }
+                    Ok(Err(e)) => log::error!("Keepalive watchdog: {}", e),
+                    Err(_) => log::warn!("Keepalive watchdog: TV connection busy, will retry"),
+                }
             }
         }
     });
 }
 
-#[tauri::command]
-async fn connect(
-    app: tauri::AppHandle,
-    state: tauri::State<'_, Arc<AppState>>,
-) -> Result<CommandResult, String> {
-    let config = state.config.lock().await;
-    let (name, tv_config) = config
-        .get_active_tv()
-        .ok_or("No TV configured")?;
+/// Subscribe to TV-side changes for this session and forward them to the frontend as events.
+/// Forwarding tasks are session tasks, so they end with the connection.
+async fn start_subscriptions(app: &AppHandle, tv: &mut TvConnection) {
+    set_tray_status(
+        app,
+        (tv.power_state == PowerState::Standby).then_some("TV in standby"),
+    );
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        let power = match tv.power_state {
+            PowerState::Unknown => PowerState::On,
+            power => power,
+        };
+        state.record_usage(&tv.name, UsageChange::Power(power));
+        state.tv_state.update(&tv.name, |s| s.power = Some(power));
+        state.parental_check.notify_one();
+    }
 
-    let name = name.clone();
-    let ip = tv_config.ip.clone();
-    let client_key = tv_config.client_key.clone();
-    let use_ssl = tv_config.use_ssl;
-    drop(config);
+    // Volume and mute, including changes made with the physical remote. getStatus also
+    // pushes on mute and output changes, which getVolume misses on some firmware.
+    match tv.subscribe("ssap://audio/getStatus", None).await {
+        Ok(mut rx) => {
+            let app = app.clone();
+            let name = tv.name.clone();
+            tv.tasks.spawn(async move {
+                while let Some(msg) = rx.recv().await {
+                    if let Some(status) = VolumeStatus::from_payload(&msg["payload"]) {
+                        if let Some(state) = app.try_state::<Arc<AppState>>() {
+                            if let Some(volume) = status.volume {
+                                state.record_usage(&name, UsageChange::Volume(volume));
+                            }
+                            *state.volume.lock().unwrap() = Some(status.clone());
+                            state.tv_state.update(&name, |s| s.volume = Some(status.clone()));
+                        }
+                        let _ = app.emit("volume-changed", status);
+                    }
+                }
+            });
+        }
+        Err(e) => log::warn!("Volume subscription failed: {}", e),
+    }
 
-    let mut tv = state.tv.lock().await;
-    let result = tv
-        .connect(&name, &ip, client_key.as_deref(), use_ssl)
-        .await?;
+    // App switches (streaming app vs HDMI input)
+    match tv
+        .subscribe("ssap://com.webos.applicationManager/getForegroundAppInfo", None)
+        .await
+    {
+        Ok(mut rx) => {
+            let app = app.clone();
+            let name = tv.name.clone();
+            tv.tasks.spawn(async move {
+                while let Some(msg) = rx.recv().await {
+                    if let Some(fg) = ForegroundApp::from_payload(&msg["payload"]) {
+                        if let Some(state) = app.try_state::<Arc<AppState>>() {
+                            state.tv_state.update(&name, |s| s.foreground_app = Some(fg.clone()));
+                        }
+                        let _ = app.emit("foreground-app-changed", fg);
+                    }
+                }
+            });
+        }
+        Err(e) => log::warn!("Foreground app subscription failed: {}", e),
+    }
 
-    // Save new client key if returned
-    if let Some(ref key) = result.client_key {
-        let mut config = state.config.lock().await;
-        config.update_client_key(&name, key.clone());
-        let _ = config.save();
+    // Frames nothing was waiting for (TV-initiated prompts and notices), surfaced instead of
+    // dropped so model-specific behavior shows up
+    let mut unsolicited = tv.unsolicited_messages();
+    let forward_app = app.clone();
+    tv.tasks.spawn(async move {
+        while let Some(message) = unsolicited.recv().await {
+            log::info!("Unsolicited message from the TV: {}", message);
+            let _ = forward_app.emit("tv-unsolicited-message", message);
+        }
+    });
+
+    // Standby / power-off transitions, so commands and the tray react without waiting
+    // for the next keepalive ping to fail
+    match tv
+        .subscribe("ssap://com.webos.service.tvpower/power/getPowerState", None)
+        .await
+    {
+        Ok(mut rx) => {
+            let app = app.clone();
+            let name = tv.name.clone();
+            tv.tasks.spawn(async move {
+                while let Some(msg) = rx.recv().await {
+                    if let (Some(screen), Some(state)) =
+                        (msg["payload"]["state"].as_str(), app.try_state::<Arc<AppState>>())
+                    {
+                        if screen == "Active" || screen == "Screen Off" {
+                            state.record_usage(
+                                &name,
+                                UsageChange::ScreenOff(screen == "Screen Off"),
+                            );
+                        }
+                    }
+                    let power = PowerState::from_response(&msg);
+                    if power != PowerState::Unknown {
+                        on_power_state_changed(&app, power).await;
+                    }
+                }
+            });
+        }
+        Err(e) => log::warn!("Power state subscription failed: {}", e),
     }
 
-    spawn_keepalive(state.inner().clone(), app);
-    Ok(result)
+    // Optional subscriptions the frontend asked for, carried over from the last session
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        let wanted = state.event_subscriptions.lock().unwrap().wanted();
+        for kind in wanted {
+            match subscriptions::start(app, tv, kind).await {
+                Ok((id, task)) => state
+                    .event_subscriptions
+                    .lock()
+                    .unwrap()
+                    .set_active(kind, id, task),
+                Err(e) => log::warn!("{:?} subscription failed: {}", kind, e),
+            }
+        }
+    }
+}
+
+/// Start the subscriptions and keepalive of the session a connect just finished. Fails if
+/// the session already ended.
+async fn start_session_tasks(state: &Arc<AppState>, app: &AppHandle) -> Result<(), AppError> {
+    let (job_state, job_app) = (state.clone(), app.clone());
+    state
+        .tv
+        .run(move |tv| {
+            Box::pin(async move {
+                if !tv.connected {
+                    return Err(AppError::catalog("connection_closed", &[]));
+                }
+                start_subscriptions(&job_app, tv).await;
+                spawn_keepalive(job_state, job_app, tv);
+                Ok(())
+            })
+        })
+        .await?
 }
 
+/// On/standby time and volume per day for the last 7 days, of `tv` or else the active TV.
 #[tauri::command]
-async fn authenticate(
-    app: tauri::AppHandle,
+async fn get_usage_stats(
     state: tauri::State<'_, Arc<AppState>>,
-    name: String,
-    ip: String,
-    use_ssl: bool,
-) -> Result<CommandResult, String> {
-    // First save the TV
-    {
-        let mut config = state.config.lock().await;
-        config.set_tv(
-            name.clone(),
-            TvConfig {
-                ip: ip.clone(),
-                use_ssl,
-                client_key: None,
-                mac: None,
-            },
-        );
-        config.active_tv = Some(name.clone());
-        config.save()?;
+    tv: Option<String>,
+) -> Result<UsageStats, AppError> {
+    let tv = usage_tv(&state, tv).await?;
+    Ok(state.usage.stats(&tv))
+}
+
+/// `tv`, or the active TV if not given.
+async fn usage_tv(state: &AppState, tv: Option<String>) -> Result<String, AppError> {
+    match tv {
+        Some(tv) => Ok(tv),
+        None => state
+            .config
+            .read(|c| c.active_tv.clone())
+            .await
+            .ok_or_else(|| AppError::catalog("no_tv_configured", &[])),
     }
+}
 
-    // Connect (will prompt for pairing on TV)
-    let mut tv = state.tv.lock().await;
-    let result = tv.connect(&name, &ip, None, use_ssl).await?;
+/// Last volume and mute state the TV reported (None before the first report).
+#[tauri::command]
+fn get_volume_status(state: tauri::State<'_, Arc<AppState>>) -> Option<VolumeStatus> {
+    state.volume.lock().unwrap().clone()
+}
 
-    // Save client key and try to get MAC
-    if let Some(ref key) = result.client_key {
-        let mut config = state.config.lock().await;
-        config.update_client_key(&name, key.clone());
-
-        // Try to get MAC address for Wake-on-LAN
-        // We need the MAC of the connected interface (wifi or wired)
-        match tv.get_connected_mac().await {
-            Ok(Some(mac)) => {
-                log::info!("Saved MAC address: {}", mac);
-                config.update_mac(&name, mac);
-            }
-            Ok(None) => {
-                log::warn!("Could not find MAC address in network info");
-            }
-            Err(e) => {
-                log::warn!("Failed to get MAC address: {}", e);
-            }
-        }
+/// Last known TV state from subscriptions, kept across restarts (may be stale when not
+/// connected; see `updated`).
+#[tauri::command]
+fn get_last_tv_state(state: tauri::State<'_, Arc<AppState>>) -> TvSnapshot {
+    state.tv_state.snapshot()
+}
 
-        config.save()?;
-    }
+/// Journaled automation runs, oldest first; `limit` keeps only the newest entries.
+#[tauri::command]
+fn get_automation_log(
+    state: tauri::State<'_, Arc<AppState>>,
+    limit: Option<usize>,
+) -> Vec<AutomationEntry> {
+    state.automations.entries(limit)
+}
 
-    spawn_keepalive(state.inner().clone(), app);
-    Ok(result)
+/// Approximate kWh (and cost, if a price is configured) for today, yesterday or the last week,
+/// of `tv` or else the active TV.
+#[tauri::command]
+async fn get_energy_estimate(
+    state: tauri::State<'_, Arc<AppState>>,
+    period: EnergyPeriod,
+    tv: Option<String>,
+) -> Result<EnergyEstimate, AppError> {
+    let tv = usage_tv(&state, tv).await?;
+    let energy = state.config.read(|c| c.energy.clone()).await;
+    Ok(state.usage.energy_estimate(&tv, period, &energy))
 }
 
+/// Start forwarding `kind` events (e.g. while a view that shows them is open). Calls are
+/// counted; the TV subscription stays until every subscriber has called `unsubscribe_event`.
 #[tauri::command]
-async fn disconnect(state: tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
-    let mut tv = state.tv.lock().await;
-    tv.disconnect().await;
+async fn subscribe_event(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    kind: EventKind,
+) -> Result<(), AppError> {
+    if !state.event_subscriptions.lock().unwrap().add_ref(kind) {
+        return Ok(());
+    }
+    let started = state
+        .tv
+        .run(move |tv| {
+            Box::pin(async move {
+                if !tv.connected {
+                    // Started by start_subscriptions on the next connect
+                    return Ok(None);
+                }
+                subscriptions::start(&app, tv, kind).await.map(Some)
+            })
+        })
+        .await??;
+    let Some((id, task)) = started else {
+        return Ok(());
+    };
+    let mut subs = state.event_subscriptions.lock().unwrap();
+    if subs.is_wanted(kind) {
+        subs.set_active(kind, id, task);
+    } else {
+        task.abort();
+    }
     Ok(())
 }
 
 #[tauri::command]
-async fn get_status(state: tauri::State<'_, Arc<AppState>>) -> Result<bool, String> {
-    let tv = state.tv.lock().await;
-    Ok(tv.connected)
+async fn unsubscribe_event(
+    state: tauri::State<'_, Arc<AppState>>,
+    kind: EventKind,
+) -> Result<(), AppError> {
+    let id = state.event_subscriptions.lock().unwrap().release(kind);
+    if let Some(id) = id {
+        state
+            .tv
+            .run(move |tv| {
+                Box::pin(async move {
+                    if tv.connected {
+                        tv.unsubscribe(&id).await
+                    } else {
+                        Ok(())
+                    }
+                })
+            })
+            .await??;
+    }
+    Ok(())
+}
+
+/// Apply a pushed power state. Power-off only marks the connection dead (keepalive and the
+/// next connect clean up); we can't disconnect from here since we run as a session task.
+async fn on_power_state_changed(app: &AppHandle, power: PowerState) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let name = state
+        .tv
+        .run(move |tv| {
+            Box::pin(async move {
+                if tv.power_state == power {
+                    return None;
+                }
+                tv.power_state = power;
+                if power == PowerState::Off {
+                    tv.connected = false;
+                }
+                Some(tv.name.clone())
+            })
+        })
+        .await;
+    let Ok(Some(name)) = name else {
+        return;
+    };
+    log::info!("TV power state changed: {:?}", power);
+    state.record_usage(&name, UsageChange::Power(power));
+    state.tv_state.update(&name, |s| s.power = Some(power));
+    if power == PowerState::On {
+        // Turned back on, possibly with the physical remote outside allowed hours
+        state.parental_check.notify_one();
+    }
+    set_tray_status(
+        app,
+        match power {
+            PowerState::Standby => Some("TV in standby"),
+            PowerState::Off => Some("TV off"),
+            _ => None,
+        },
+    );
+    let _ = app.emit("power-state-changed", power);
+    if power == PowerState::Off {
+        let _ = app.emit("connection-lost", ());
+    }
+}
+
+/// Log which model/firmware we're talking to and remember the model name for the TV.
+async fn record_system_info(state: &AppState, name: &str) {
+    match state.tv.system_info().await {
+        Ok(info) => {
+            log::info!(
+                "Connected to {}: model {}, firmware {} ({})",
+                name,
+                info.model_name.as_deref().unwrap_or("unknown"),
+                info.firmware_version.as_deref().unwrap_or("unknown"),
+                info.product_name.as_deref().unwrap_or("unknown")
+            );
+            if let Some(model) = info.model_name {
+                let changed = state
+                    .config
+                    .read(|config| {
+                        config
+                            .tvs
+                            .get(name)
+                            .is_some_and(|tv| tv.model_name.as_deref() != Some(model.as_str()))
+                    })
+                    .await;
+                if changed {
+                    let _ = state
+                        .config
+                        .update(|config| {
+                            config.update_model_name(name, model);
+                            Ok(())
+                        })
+                        .await;
+                }
+            }
+        }
+        Err(e) => log::debug!("Could not get system info: {}", e),
+    }
 }
 
 #[tauri::command]
-async fn send_button(
+async fn connect(
+    app: tauri::AppHandle,
     state: tauri::State<'_, Arc<AppState>>,
-    button: String,
-) -> Result<CommandResult, String> {
-    let mut tv = state.tv.lock().await;
-    if !tv.connected {
-        return Err("Not connected".to_string());
+) -> Result<CommandResult, AppError> {
+    // A manual connect means the user expects the TV to be there: retry fast again
+    state.reconnector.kick();
+    connect_impl(app, state.inner().clone()).await
+}
+
+/// Connect to the active TV and start the session's subscriptions and keepalive.
+async fn connect_impl(app: AppHandle, state: Arc<AppState>) -> Result<CommandResult, AppError> {
+    let result = connect_active_tv(app.clone(), state.clone()).await;
+    // Nothing at the saved address: the TV may have a new DHCP lease
+    if let Err(AppError::TvOff(_)) = &result
+        && relocate_active_tv(&state).await
+    {
+        return connect_active_tv(app, state).await;
+    }
+    result
+}
+
+/// Look for the active TV's MAC in the neighbor table and save the address it has now. True
+/// if it moved. Only for TVs configured by IP (hostnames resolve on every connect) with a
+/// saved MAC; system TVs keep the administrator's address.
+async fn relocate_active_tv(state: &AppState) -> bool {
+    let target = state
+        .config
+        .read(|c| {
+            let (name, tv) = c.get_active_tv()?;
+            if tv.system || tv.ip.parse::<std::net::IpAddr>().is_err() {
+                return None;
+            }
+            Some((name.clone(), tv.mac.clone()?, tv.ip.clone()))
+        })
+        .await;
+    let Some((name, mac, ip)) = target else {
+        return false;
+    };
+    let Some(found) = neighbors::find_by_mac(&mac, &ip).await.map(|ip| ip.to_string()) else {
+        return false;
+    };
+    if found == ip {
+        return false;
+    }
+    log::info!("{} ({}) moved from {} to {}", name, mac, ip, found);
+    match state.config.update(|c| Ok(c.update_ip(&name, &found))).await {
+        Ok(moved) => moved,
+        Err(e) => {
+            log::warn!("Could not save new address of {}: {}", name, e);
+            false
+        }
+    }
+}
+
+async fn connect_active_tv(
+    app: AppHandle,
+    state: Arc<AppState>,
+) -> Result<CommandResult, AppError> {
+    let config = state.config.get().await;
+    let (name, tv_config) = config
+        .get_active_tv()
+        .ok_or_else(|| AppError::catalog("no_tv_configured", &[]))?;
+
+    let name = name.clone();
+    let ip = tv_config.ip.clone();
+    let client_key = tv_config.client_key.clone();
+    let use_ssl = tv_config.use_ssl;
+    let pinned_cert = tv_config.cert_fingerprint.clone();
+    let capabilities = tv_config.capabilities.clone();
+    drop(config);
+
+    let _busy = state.begin_busy(&format!("Connecting to {}", name));
+    // Switching TVs ends what's known about the previous one
+    let previous = state
+        .tv
+        .run(|tv| Box::pin(async move { tv.name.clone() }))
+        .await?;
+    if !previous.is_empty() && previous != name {
+        state.record_usage(&previous, UsageChange::Power(PowerState::Unknown));
+    }
+    let (job_name, job_ip) = (name.clone(), ip.clone());
+    let result = state
+        .tv
+        .connect(move |tv| {
+            Box::pin(async move {
+                tv.capabilities = capabilities;
+                tv.start_connect(
+                    &job_name,
+                    &job_ip,
+                    client_key.as_deref(),
+                    use_ssl,
+                    pinned_cert.as_deref(),
+                    None,
+                )
+                .await
+            })
+        })
+        .await?;
+    let (fingerprint, seen_ip) = state
+        .tv
+        .run(|tv| Box::pin(async move { (tv.cert_fingerprint.clone(), tv.resolved_ip) }))
+        .await?;
+    let seen_ip = seen_ip.map_or_else(|| ip.clone(), |a| a.to_string());
+
+    let _ = state
+        .config
+        .update(|config| {
+            // Save new client key if returned
+            if let Some(ref key) = result.client_key {
+                config.update_client_key(&name, key.clone());
+            }
+            // Trust on first use: pin the certificate the first time we see one
+            if let Some(tv) = config.tvs.get_mut(&name) {
+                if tv.cert_fingerprint.is_none() {
+                    tv.cert_fingerprint = fingerprint;
+                }
+            }
+            config.record_connection(&name, &seen_ip);
+            Ok(())
+        })
+        .await;
+    record_system_info(&state, &name).await;
+    start_session_tasks(&state, &app).await?;
+    if state.config.read(|c| c.mirror_tv_notifications).await {
+        let state = state.clone();
+        tauri::async_runtime::spawn(async move { mirror_tv_notifications(&app, &state).await });
+    }
+    Ok(result)
+}
+
+/// Show TV notifications we haven't shown yet as desktop notifications.
+async fn mirror_tv_notifications(app: &AppHandle, state: &AppState) {
+    let notifications = state
+        .tv
+        .run(|tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.get_notifications().await
+            })
+        })
+        .await;
+    let notifications = match notifications {
+        Ok(Ok(notifications)) => notifications,
+        Ok(Err(e)) | Err(e) => {
+            log::debug!("Reading TV notifications failed: {}", e);
+            return;
+        }
+    };
+    for notification in notifications {
+        if state
+            .mirrored_notifications
+            .lock()
+            .unwrap()
+            .insert(notification.id.clone())
+        {
+            feedback::show_notification(app, &notification.message);
+        }
+    }
+}
+
+/// With `auto_reconnect` on, reconnect whenever the connection is down, following the
+/// reconnector's back-off schedule.
+fn spawn_auto_reconnect(state: Arc<AppState>, app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let enabled = state.config.read(|c| c.auto_reconnect).await;
+            // Reconnecting would kick out the app that took the session over, which would then
+            // do the same; wait for the user to connect again instead
+            let Ok((connected, replaced)) = state
+                .tv
+                .run(|tv| Box::pin(async move { (tv.connected, tv.session_replaced) }))
+                .await
+            else {
+                log::error!("Auto-reconnect stopped: the connection manager is gone");
+                return;
+            };
+            if !enabled || connected || replaced {
+                state.reconnector.record_success();
+                state.reconnector.wait(std::time::Duration::from_secs(10)).await;
+                continue;
+            }
+            // Only reconnect to a TV we've paired with
+            let paired = state
+                .config
+                .read(|c| c.get_active_tv().is_some_and(|(_, tv)| tv.client_key.is_some()))
+                .await;
+            if !paired {
+                state.reconnector.wait(std::time::Duration::from_secs(30)).await;
+                continue;
+            }
+            match connect_impl(app.clone(), state.clone()).await {
+                Ok(_) => {
+                    log::info!("Auto-reconnected");
+                    state.reconnector.record_success();
+                    let _ = app.emit("connection-restored", ());
+                }
+                Err(e) => {
+                    let delay = state.reconnector.record_failure();
+                    log::debug!("Auto-reconnect failed ({}); next try in {:?}", e, delay);
+                    state.reconnector.wait(delay).await;
+                }
+            }
+        }
+    });
+}
+
+/// Follow the TVs announced over mDNS: a configured TV whose address changed gets the new
+/// one, and a configured TV coming back onto the network emits "tv-discovered" and wakes the
+/// reconnect loop if it's the active TV.
+fn spawn_mdns_tracker(state: Arc<AppState>, app: AppHandle) {
+    let mut events = match discovery::browse_mdns() {
+        Ok(events) => events,
+        Err(e) => {
+            log::warn!("mDNS tracking unavailable: {}", e);
+            return;
+        }
+    };
+    tauri::async_runtime::spawn(async move {
+        // Announcements currently up, by mDNS full name -> configured TV name. A TV announces
+        // several services, so it's back only when the first of them appears.
+        let mut announced: HashMap<String, String> = HashMap::new();
+        while let Some(event) = events.recv().await {
+            let tv = match event {
+                discovery::MdnsEvent::Seen(tv) => tv,
+                discovery::MdnsEvent::Gone(fullname) => {
+                    announced.remove(&fullname);
+                    continue;
+                }
+            };
+            let ip = tv.ip.clone();
+            let configured = state
+                .config
+                .read(|c| {
+                    let name = c.find_tv_by_address(tv.mac.as_deref(), &ip)?;
+                    let active = c.active_tv.as_deref() == Some(name.as_str());
+                    let moved = c.tvs.get(&name).is_some_and(|t| !t.system && t.ip != ip);
+                    Some((name, active, moved))
+                })
+                .await;
+            let Some((name, active, address_changed)) = configured else {
+                continue;
+            };
+            if address_changed {
+                let update_name = name.clone();
+                match state.config.update(|c| Ok(c.update_ip(&update_name, &ip))).await {
+                    Ok(_) => log::info!("{} moved to {} (mDNS)", name, ip),
+                    Err(e) => log::warn!("Could not save new address of {}: {}", name, e),
+                }
+            }
+            let back = !announced.values().any(|n| *n == name);
+            announced.insert(tv.fullname, name.clone());
+            if back || address_changed {
+                log::debug!("{} announced at {} ({})", name, ip, tv.name);
+                let _ = app.emit(
+                    "tv-discovered",
+                    discovery::TvDiscovered {
+                        name,
+                        ip,
+                        model: tv.model,
+                        address_changed,
+                    },
+                );
+                if active {
+                    state.reconnector.kick();
+                }
+            }
+        }
+    });
+}
+
+/// While not connected, probe the active TV every `presence::INTERVAL` and emit
+/// "tv-presence-changed" ("connected", "on" or "off") when the answer changes, so the tray and
+/// window can show whether the TV is on before pairing or connecting.
+fn spawn_presence_monitor(state: Arc<AppState>, app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last: Option<(String, presence::Presence)> = None;
+        loop {
+            let target = state
+                .config
+                .read(|c| {
+                    c.get_active_tv()
+                        .map(|(name, tv)| (name.clone(), tv.ip.clone(), tv.use_ssl))
+                })
+                .await;
+            let Some((name, ip, use_ssl)) = target else {
+                last = None;
+                *state.presence.lock().unwrap() = None;
+                tokio::time::sleep(presence::INTERVAL).await;
+                continue;
+            };
+            let (connected, replaced) = state
+                .tv
+                .run(|tv| Box::pin(async move { (tv.connected, tv.session_replaced) }))
+                .await
+                .unwrap_or((false, false));
+            let presence = if connected {
+                presence::Presence::Connected
+            } else if tv::probe(&ip, use_ssl).await {
+                presence::Presence::On
+            } else {
+                presence::Presence::Off
+            };
+            let current = (name, presence);
+            if last.as_ref() != Some(&current) {
+                log::debug!("{} presence: {:?}", current.0, presence);
+                *state.presence.lock().unwrap() = Some(presence);
+                // A taken-over session keeps its own, more useful tray status
+                if !connected && !replaced {
+                    set_tray_status(&app, presence.tray_status());
+                }
+                let _ = app.emit("tv-presence-changed", presence);
+                last = Some(current);
+            }
+            tokio::time::sleep(presence::INTERVAL).await;
+        }
+    });
+}
+
+/// Close the session before the system suspends, so the TV doesn't keep it half-open and
+/// refuse the next connect. After resuming, a session that was open (or still looks open, when
+/// the suspend went unnoticed) is dropped and auto-reconnect is woken up.
+fn spawn_sleep_watch(state: Arc<AppState>, app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut events = sleep_watch::watch();
+        let mut closed_for_sleep = false;
+        while let Some(event) = events.recv().await {
+            let was_connected = state
+                .tv
+                .run(|tv| {
+                    Box::pin(async move {
+                        let connected = tv.connected;
+                        if connected {
+                            tv.disconnect().await;
+                        }
+                        connected
+                    })
+                })
+                .await
+                .unwrap_or(false);
+            match event {
+                sleep_watch::SleepEvent::Suspending(delay) => {
+                    if was_connected {
+                        log::info!("System suspending: closed the TV session");
+                    }
+                    closed_for_sleep |= was_connected;
+                    // Session closed: let the suspend go ahead
+                    drop(delay);
+                }
+                sleep_watch::SleepEvent::Resumed => {
+                    if !(closed_for_sleep || was_connected) {
+                        continue;
+                    }
+                    log::info!("System resumed: reconnecting");
+                    closed_for_sleep = false;
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.emit("connection-lost", ());
+                    }
+                    state.reconnector.kick();
+                }
+            }
+        }
+    });
+}
+
+/// Power off on behalf of an automation (parental schedule, macro), not a user press: if the
+/// TV looks busy with something turning it off would interrupt, leave it on, tell whoever is
+/// watching, and return Err so the caller can try again later. The attempt is journaled under
+/// `automation` with `reason`.
+async fn automated_power_off(
+    app: &AppHandle,
+    tv: &mut TvConnection,
+    automation: &str,
+    reason: &str,
+) -> Result<(), AppError> {
+    let journal = |outcome, error| {
+        if let Some(state) = app.try_state::<Arc<AppState>>() {
+            state.automations.record(automation, "power_off", reason, outcome, error);
+        }
+    };
+    let blockers = tv.power_off_blockers().await;
+    if !blockers.is_empty() {
+        let blocked_by = blockers.join(", ");
+        log::info!("Automated power off postponed: {}", blocked_by);
+        let _ = tv.show_toast(&format!("Not turning off: {}", blocked_by)).await;
+        let _ = app.emit("power-off-deferred", &blocked_by);
+        let error = format!("Power off postponed: {}", blocked_by);
+        journal(AutomationOutcome::Deferred, Some(error.clone()));
+        return Err(AppError::Other(error.into()));
+    }
+    let result = tv.power_off().await.map(|_| ());
+    match &result {
+        Ok(()) => journal(AutomationOutcome::Succeeded, None),
+        Err(e) => journal(AutomationOutcome::Failed, Some(e.to_string())),
+    }
+    result
+}
+
+const PARENTAL_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+const PARENTAL_REASON: &str = "outside the allowed hours";
+
+/// Outside the parental schedule's allowed hours, warn on the TV and then turn it off. Checks
+/// every minute and right away when the TV comes on. Needs a connection to act, so pair it
+/// with auto-reconnect.
+fn spawn_parental_enforcer(state: Arc<AppState>, app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(PARENTAL_CHECK_INTERVAL) => {}
+                _ = state.parental_check.notified() => {}
+            }
+            let parental = state.config.read(|c| c.parental.clone()).await;
+            if parental::is_allowed(&parental, chrono::Local::now()) {
+                continue;
+            }
+            let warning_secs = parental.warning_secs;
+            let warned = state
+                .tv
+                .run(move |tv| {
+                    Box::pin(async move {
+                        if !tv.connected || tv.power_state == PowerState::Standby {
+                            return None;
+                        }
+                        log::info!("Parental schedule: outside allowed hours, warning before power off");
+                        let warning =
+                            format!("TV time is over. Turning off in {} seconds.", warning_secs);
+                        Some(tv.show_toast(&warning).await)
+                    })
+                })
+                .await;
+            let Ok(Some(warned)) = warned else {
+                continue;
+            };
+            let (outcome, error) = match warned {
+                Ok(_) => (AutomationOutcome::Succeeded, None),
+                Err(e) => {
+                    log::warn!("Parental schedule: warning toast failed: {}", e);
+                    (AutomationOutcome::Failed, Some(e.to_string()))
+                }
+            };
+            state.automations.record("parental_schedule", "warn", PARENTAL_REASON, outcome, error);
+            tokio::time::sleep(std::time::Duration::from_secs(parental.warning_secs)).await;
+
+            // The schedule may have changed (or a window opened) during the warning
+            let parental = state.config.read(|c| c.parental.clone()).await;
+            if parental::is_allowed(&parental, chrono::Local::now()) {
+                continue;
+            }
+            let app = app.clone();
+            let _ = state
+                .tv
+                .run(move |tv| {
+                    Box::pin(async move {
+                        if !tv.connected || tv.power_state == PowerState::Standby {
+                            return;
+                        }
+                        // Tried again on the next check
+                        let result =
+                            automated_power_off(&app, tv, "parental_schedule", PARENTAL_REASON)
+                                .await;
+                        match result {
+                            Ok(()) => log::info!("Parental schedule: TV turned off"),
+                            Err(e) => log::warn!("Parental schedule: {}", e),
+                        }
+                    })
+                })
+                .await;
+        }
+    });
+}
+
+#[tauri::command]
+async fn get_parental_schedule(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<ParentalConfig, AppError> {
+    Ok(state.config.read(|c| c.parental.clone()).await)
+}
+
+#[tauri::command]
+async fn set_parental_schedule(
+    state: tauri::State<'_, Arc<AppState>>,
+    parental: ParentalConfig,
+) -> Result<(), AppError> {
+    parental::validate(&parental)?;
+    state
+        .config
+        .update(|config| {
+            config.parental = parental;
+            Ok(())
+        })
+        .await?;
+    state.parental_check.notify_one();
+    Ok(())
+}
+
+/// Look for webOS TVs on the local network (SSDP), waiting `timeout_ms` (default 3s) for
+/// answers. TVs that are fully off don't answer.
+#[tauri::command]
+async fn discover_tvs(timeout_ms: Option<u64>) -> Result<Vec<discovery::DiscoveredTv>, AppError> {
+    let wait = std::time::Duration::from_millis(timeout_ms.unwrap_or(3000).clamp(500, 10_000));
+    Ok(discovery::discover(wait).await?)
+}
+
+#[tauri::command]
+async fn authenticate(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+    ip: String,
+    use_ssl: bool,
+    pin_pairing: Option<bool>,
+) -> Result<CommandResult, AppError> {
+    // First save the TV
+    state
+        .config
+        .update(|config| {
+            config.set_tv(
+                name.clone(),
+                TvConfig {
+                    ip: ip.clone(),
+                    use_ssl,
+                    ..Default::default()
+                },
+            );
+            config.active_tv = Some(name.clone());
+            Ok(())
+        })
+        .await?;
+
+    // Connect (will prompt for pairing on TV, or show a PIN to enter with `submit_pin`)
+    let pins = if pin_pairing.unwrap_or(false) {
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        *state.pin_pairing.lock().unwrap() = Some(tx);
+        let _ = app.emit("pin-required", &name);
+        Some(rx)
+    } else {
+        None
+    };
+    let _busy = state.begin_busy(if pins.is_some() {
+        "Pairing — enter the PIN shown on the TV"
+    } else {
+        "Pairing — accept the prompt on the TV"
+    });
+    let (job_name, job_ip) = (name.clone(), ip.clone());
+    let result = state
+        .tv
+        .connect(move |tv| {
+            Box::pin(async move {
+                tv.capabilities = TvCapabilities::default();
+                tv.start_connect(&job_name, &job_ip, None, use_ssl, None, pins)
+                    .await
+            })
+        })
+        .await;
+    state.pin_pairing.lock().unwrap().take();
+    let result = result?;
+
+    // Save client key and try to get MAC
+    if let Some(ref key) = result.client_key {
+        let (mac, fingerprint, seen_ip, capabilities) = state
+            .tv
+            .run(|tv| {
+                Box::pin(async move {
+                    // Try to get MAC address for Wake-on-LAN
+                    // We need the MAC of the connected interface (wifi or wired)
+                    let mac = match tv.get_connected_mac().await {
+                        Ok(Some(mac)) => {
+                            log::info!("Saved MAC address: {}", mac);
+                            Some(mac)
+                        }
+                        Ok(None) => {
+                            log::warn!("Could not find MAC address in network info");
+                            None
+                        }
+                        Err(e) => {
+                            log::warn!("Failed to get MAC address: {}", e);
+                            None
+                        }
+                    };
+                    (mac, tv.cert_fingerprint.clone(), tv.resolved_ip, tv.capabilities.clone())
+                })
+            })
+            .await?;
+        let seen_ip = seen_ip.map_or_else(|| ip.clone(), |a| a.to_string());
+
+        state
+            .config
+            .update(|config| {
+                config.update_client_key(&name, key.clone());
+                config.update_capabilities(&name, capabilities);
+                config.record_connection(&name, &seen_ip);
+                if let Some(tv) = config.tvs.get_mut(&name) {
+                    tv.cert_fingerprint = fingerprint;
+                }
+                if let Some(mac) = mac {
+                    config.update_mac(&name, mac);
+                }
+                Ok(())
+            })
+            .await?;
+    }
+    record_system_info(&state, &name).await;
+    start_session_tasks(&state, &app).await?;
+    Ok(result)
+}
+
+/// Send the PIN shown on the TV to a PIN pairing started with `authenticate`. Errs if the
+/// TV rejects it; the pairing keeps waiting so the user can try again.
+#[tauri::command]
+async fn submit_pin(state: tauri::State<'_, Arc<AppState>>, pin: String) -> Result<(), AppError> {
+    let pin = pin.trim().to_string();
+    if pin.is_empty() || !pin.chars().all(|c| c.is_ascii_digit()) {
+        return Err("PIN must be digits".into());
+    }
+    let sender = state
+        .pin_pairing
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("No PIN pairing in progress")?;
+    let (reply, accepted) = tokio::sync::oneshot::channel();
+    sender
+        .send((pin, reply))
+        .await
+        .map_err(|_| "Pairing already finished".to_string())?;
+    accepted
+        .await
+        .map_err(|_| "Pairing ended before the TV answered".to_string())?
+}
+
+/// Time round trips to the active TV (`samples` rounds, default 3): TCP connect, an SSAP
+/// request, and a ping on the input socket, plus how long the request queued in the app.
+#[tauri::command]
+async fn measure_latency(
+    state: tauri::State<'_, Arc<AppState>>,
+    samples: Option<u32>,
+) -> Result<Latency, AppError> {
+    let samples = samples.unwrap_or(3).clamp(1, 20);
+    let latency = state.tv.measure_latency(samples).await?;
+    log::info!("Latency: {:?}", latency);
+    Ok(latency)
+}
+
+/// Record every frame exchanged with the TV (client keys redacted) until `stop_trace`.
+/// Starts from an empty buffer.
+#[tauri::command]
+fn start_trace(state: tauri::State<'_, Arc<AppState>>) {
+    log::info!("Protocol trace started");
+    state.tv.trace.start();
+}
+
+#[tauri::command]
+fn stop_trace(state: tauri::State<'_, Arc<AppState>>) {
+    state.tv.trace.stop();
+}
+
+/// Write the recorded frames to `path` (default: trace-<time>.json next to config.json) and
+/// return the file written.
+#[tauri::command]
+fn export_trace(
+    state: tauri::State<'_, Arc<AppState>>,
+    path: Option<String>,
+) -> Result<String, AppError> {
+    let path = state.tv.trace.export(path.map(std::path::PathBuf::from))?;
+    log::info!("Protocol trace written to {}", path.display());
+    Ok(path.display().to_string())
+}
+
+/// Write the settings to `path` (default: config-export-<time>.json next to config.json) for
+/// moving them to another machine, and return the file written. Client keys are included
+/// unless `include_client_keys` is false.
+#[tauri::command]
+async fn export_config(
+    state: tauri::State<'_, Arc<AppState>>,
+    path: Option<String>,
+    include_client_keys: Option<bool>,
+) -> Result<String, AppError> {
+    let config = state.config.get().await;
+    let path = config_transfer::export(
+        &config,
+        path.map(std::path::PathBuf::from),
+        include_client_keys.unwrap_or(true),
+    )?;
+    log::info!("Config exported to {}", path.display());
+    Ok(path.display().to_string())
+}
+
+/// Load settings exported on another machine. `mode` "merge" (default) adds the file's TVs,
+/// shortcuts, macros and streaming devices to the current settings; "replace" uses the file's
+/// settings instead.
+#[tauri::command]
+async fn import_config(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    path: String,
+    mode: Option<config_transfer::ImportMode>,
+) -> Result<config_transfer::ImportSummary, AppError> {
+    let imported = config_transfer::read(std::path::Path::new(&path))?;
+    let (summary, config) = state
+        .config
+        .update(|config| {
+            let summary = config_transfer::import(config, imported, mode.unwrap_or_default());
+            Ok((summary, config.clone()))
+        })
+        .await?;
+    log::info!(
+        "Config imported from {}: {} TVs added, {} updated",
+        path,
+        summary.added.len(),
+        summary.updated.len()
+    );
+    i18n::set_locale(config.locale.as_deref());
+    register_all_global_shortcuts(&app, &config)?;
+    Ok(summary)
+}
+
+/// Take client keys from other LG tools (LGWebOSRemote, lgtv2, aiopylgtv/bscpylgtv, Home
+/// Assistant) so their TVs connect without pairing again. Reads `path` (a file or directory)
+/// or the tools' default locations; `add_missing` also adds TVs that aren't configured, and
+/// `replace` overwrites a TV's own key.
+#[tauri::command]
+async fn import_pairing_keys(
+    state: tauri::State<'_, Arc<AppState>>,
+    path: Option<String>,
+    add_missing: Option<bool>,
+    replace: Option<bool>,
+) -> Result<key_import::KeyImportSummary, AppError> {
+    let found = key_import::scan(path.as_deref().map(std::path::Path::new));
+    if found.is_empty() {
+        return Err(match path {
+            Some(path) => format!("No LG TV client keys found in {}", path),
+            None => "No LG TV client keys found in the usual locations".to_string(),
+        }
+        .into());
+    }
+    state
+        .config
+        .update(|config| {
+            Ok(key_import::apply(
+                config,
+                found,
+                add_missing.unwrap_or(true),
+                replace.unwrap_or(false),
+            ))
+        })
+        .await
+}
+
+/// Latest presence monitor result for the active TV; None until the first probe.
+#[tauri::command]
+fn get_tv_presence(state: tauri::State<'_, Arc<AppState>>) -> Option<presence::Presence> {
+    *state.presence.lock().unwrap()
+}
+
+/// Power state of the active TV. Asks the TV when connected; otherwise probes its SSAP port
+/// (open → `on`, closed → `off`), which works before pairing too.
+#[tauri::command]
+async fn get_power_state(state: tauri::State<'_, Arc<AppState>>) -> Result<PowerState, AppError> {
+    let live = state
+        .tv
+        .command(|tv| {
+            Box::pin(async move {
+                if tv.connected {
+                    tv.get_power_state().await.map(Some)
+                } else {
+                    Ok(None)
+                }
+            })
+        })
+        .await?;
+    if let Some(power) = live {
+        return Ok(power);
+    }
+    let (ip, use_ssl) = {
+        let config = state.config.get().await;
+        let (_, tv_config) = config
+            .get_active_tv()
+            .ok_or_else(|| AppError::catalog("no_tv_configured", &[]))?;
+        (tv_config.ip.clone(), tv_config.use_ssl)
+    };
+    if tv::probe(&ip, use_ssl).await {
+        Ok(PowerState::On)
+    } else {
+        Ok(PowerState::Off)
+    }
+}
+
+#[tauri::command]
+async fn get_system_info(state: tauri::State<'_, Arc<AppState>>) -> Result<SystemInfo, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                if !tv.connected {
+                    return Err(AppError::catalog("not_connected", &[]));
+                }
+                tv.get_system_info().await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn get_current_channel(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<CurrentChannel, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                if !tv.connected {
+                    return Err(AppError::catalog("not_connected", &[]));
+                }
+                tv.get_current_channel().await
+            })
+        })
+        .await
+}
+
+/// Whether remote buttons work normally, or which are unavailable because the TV refused
+/// the pointer input socket.
+#[tauri::command]
+async fn get_input_capabilities(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<InputCapabilities, AppError> {
+    state
+        .tv
+        .run(move |tv| {
+            Box::pin(async move {
+                if !tv.connected {
+                    return Err(AppError::catalog("not_connected", &[]));
+                }
+                Ok(tv.input_capabilities())
+            })
+        })
+        .await?
+}
+
+#[tauri::command]
+async fn check_tv_update(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<TvUpdateStatus, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.check_update().await
+            })
+        })
+        .await
+}
+
+/// Notifications the TV has pending, as far as SSAP lets us read them.
+#[tauri::command]
+async fn get_tv_notifications(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<TvNotification>, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.get_notifications().await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn start_tv_update(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<CommandResult, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.open_software_update().await
+            })
+        })
+        .await
+}
+
+/// Typed description of TVs, streaming devices, commands and events for integrations
+/// (e.g. a Home Assistant custom component).
+#[tauri::command]
+async fn get_api_schema(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<serde_json::Value, AppError> {
+    Ok(state.config.read(api_schema::build).await)
+}
+
+/// Window behavior for the current platform.
+#[tauri::command]
+async fn get_window_behavior(state: tauri::State<'_, Arc<AppState>>) -> Result<WindowBehavior, AppError> {
+    let config = state.config.get().await;
+    Ok(*config.window_behavior.current())
+}
+
+/// Update the current platform's hide-on-focus-loss settings; applies immediately.
+#[tauri::command]
+async fn set_window_behavior(
+    state: tauri::State<'_, Arc<AppState>>,
+    hide_on_focus_loss: bool,
+    hide_delay_ms: u64,
+) -> Result<(), AppError> {
+    let behavior = WindowBehavior {
+        hide_on_focus_loss,
+        hide_delay_ms: hide_delay_ms.min(5000),
+    };
+    state
+        .config
+        .update(|config| {
+            *config.window_behavior.current_mut() = behavior;
+            Ok(())
+        })
+        .await?;
+    apply_window_behavior(&behavior);
+    Ok(())
+}
+
+/// Send an arbitrary SSAP request and return the TV's raw response. `luna://` URIs go through
+/// the alert workaround and return no TV response. Only available in debug builds or with
+/// `developer_mode` set in config, so experiments with undocumented endpoints don't need a rebuild.
+#[tauri::command]
+async fn send_raw_ssap(
+    state: tauri::State<'_, Arc<AppState>>,
+    uri: String,
+    payload_json: Option<String>,
+) -> Result<serde_json::Value, AppError> {
+    if !cfg!(debug_assertions) && !state.config.read(|c| c.developer_mode).await {
+        return Err("Raw SSAP requests require developer_mode in config".into());
+    }
+    let uri = if uri.contains("://") { uri } else { format!("ssap://{}", uri) };
+    let payload = match payload_json.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(json) => Some(
+            serde_json::from_str::<serde_json::Value>(json)
+                .map_err(|e| format!("Invalid payload JSON: {}", e))?,
+        ),
+    };
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                if !tv.connected {
+                    return Err(AppError::catalog("not_connected", &[]));
+                }
+                if uri.starts_with("luna://") {
+                    let result = tv
+                        .send_luna(&uri, payload.unwrap_or(serde_json::json!({})))
+                        .await?;
+                    return serde_json::to_value(result).map_err(|e| AppError::Other(e.to_string().into()));
+                }
+                tv.send_command(&uri, payload).await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn disconnect(state: tauri::State<'_, Arc<AppState>>) -> Result<(), AppError> {
+    let name = state
+        .tv
+        .run(move |tv| {
+            Box::pin(async move {
+                tv.disconnect().await;
+                tv.name.clone()
+            })
+        })
+        .await?;
+    state.record_usage(&name, UsageChange::Power(PowerState::Unknown));
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_status(state: tauri::State<'_, Arc<AppState>>) -> Result<bool, AppError> {
+    state
+        .tv
+        .run(move |tv| {
+            Box::pin(async move {
+                Ok(tv.connected)
+            })
+        })
+        .await?
+}
+
+#[tauri::command]
+async fn send_button(
+    state: tauri::State<'_, Arc<AppState>>,
+    button: String,
+) -> Result<CommandResult, AppError> {
+    let button = state.resolve_button(&button).await;
+    state.record_action(&format!("button_{}", button.to_lowercase()));
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.send_button(&button).await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn button_down(
+    state: tauri::State<'_, Arc<AppState>>,
+    button: String,
+) -> Result<CommandResult, AppError> {
+    let button = state.resolve_button(&button).await;
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.button_down(&button).await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn button_up(
+    state: tauri::State<'_, Arc<AppState>>,
+    button: String,
+) -> Result<CommandResult, AppError> {
+    let button = state.resolve_button(&button).await;
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.button_up(&button).await
+            })
+        })
+        .await
+}
+
+/// Hold `button` for `duration_ms` (default 1s, at most 5s) and release it.
+#[tauri::command]
+async fn long_press_button(
+    state: tauri::State<'_, Arc<AppState>>,
+    button: String,
+    duration_ms: Option<u64>,
+) -> Result<CommandResult, AppError> {
+    let duration = std::time::Duration::from_millis(duration_ms.unwrap_or(1000).min(5000));
+    let button = state.resolve_button(&button).await;
+    // The hold happens between two jobs, so other commands aren't stuck behind it
+    let down = button.clone();
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.button_down(&down).await
+            })
+        })
+        .await?;
+    tokio::time::sleep(duration).await;
+    state
+        .tv
+        .command(move |tv| Box::pin(async move { tv.button_up(&button).await }))
+        .await
+}
+
+#[tauri::command]
+async fn send_scroll(
+    state: tauri::State<'_, Arc<AppState>>,
+    dy: i32,
+) -> Result<CommandResult, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.send_scroll(dy).await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn volume_up(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, AppError> {
+    state.record_action("volume_up");
+    state.tv.volume_step(1).await
+}
+
+#[tauri::command]
+async fn volume_down(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, AppError> {
+    state.record_action("volume_down");
+    state.tv.volume_step(-1).await
+}
+
+#[tauri::command]
+async fn set_mute(
+    state: tauri::State<'_, Arc<AppState>>,
+    mute: bool,
+) -> Result<CommandResult, AppError> {
+    state.record_action(if mute { "mute" } else { "unmute" });
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.set_mute(mute).await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn power_off(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, AppError> {
+    state.record_action("power_off");
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                if !tv.connected {
+                    return Err(AppError::catalog("not_connected", &[]));
+                }
+                tv.power_off().await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn screen_off(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, AppError> {
+    state.record_action("screen_off");
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.screen_off().await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn list_bluetooth_devices(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<BluetoothDevice>, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.list_bluetooth_devices().await
+            })
+        })
+        .await
+}
+
+/// Installed apps, with their icons cached for offline use (see `get_app_icon`).
+#[tauri::command]
+async fn list_apps(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<TvApp>, AppError> {
+    // The icons download after the job so they don't hold up other commands
+    let (ip, mut apps) = state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                Ok::<_, AppError>((tv.ip.clone(), tv.list_apps().await?))
+            })
+        })
+        .await?;
+    let icons = apps
+        .iter()
+        .filter_map(|a| Some((a.id.clone(), a.icon_url.clone()?)))
+        .collect();
+    app_icons::cache_missing(&ip, icons).await;
+    for app in &mut apps {
+        app.icon_path = app_icons::path(&app.id).map(|p| p.display().to_string());
+    }
+    Ok(apps)
+}
+
+/// External inputs with the active TV's custom labels applied and their icons cached.
+#[tauri::command]
+async fn list_inputs(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<InputSource>, AppError> {
+    let labels = state
+        .config
+        .read(|c| {
+            c.get_active_tv()
+                .map(|(_, tv)| tv.input_labels.clone())
+                .unwrap_or_default()
+        })
+        .await;
+    let (ip, mut inputs) = state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                Ok::<_, AppError>((tv.ip.clone(), tv.list_inputs(&labels).await?))
+            })
+        })
+        .await?;
+    let icons = inputs
+        .iter()
+        .filter_map(|i| Some((i.id.clone(), i.icon_url.clone()?)))
+        .collect();
+    app_icons::cache_missing(&ip, icons).await;
+    for input in &mut inputs {
+        input.icon_path = app_icons::path(&input.id).map(|p| p.display().to_string());
+    }
+    Ok(inputs)
+}
+
+/// Cached icon of an app or input (PNG or JPEG bytes), or None if it hasn't been fetched yet
+/// with `list_apps` / `list_inputs`. Works while the TV is off.
+#[tauri::command]
+fn get_app_icon(id: String) -> Option<Vec<u8>> {
+    app_icons::read(&id)
+}
+
+#[tauri::command]
+async fn switch_input(
+    state: tauri::State<'_, Arc<AppState>>,
+    input_id: String,
+) -> Result<CommandResult, AppError> {
+    state.record_action(&format!("input_{}", input_id.to_lowercase()));
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.switch_input(&input_id).await
+            })
+        })
+        .await
+}
+
+/// Forget the active TV's pinned TLS certificate (after a factory reset or replacing the TV);
+/// the next connect pins whatever certificate it presents.
+#[tauri::command]
+async fn reset_cert_pin(state: tauri::State<'_, Arc<AppState>>) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            let name = config
+                .get_active_tv()
+                .map(|(name, _)| name.clone())
+                .ok_or_else(|| AppError::catalog("no_tv_configured", &[]))?;
+            let tv = config
+                .tvs
+                .get_mut(&name)
+                .ok_or_else(|| AppError::catalog("no_tv_configured", &[]))?;
+            tv.cert_fingerprint = None;
+            Ok(())
+        })
+        .await
+}
+
+/// Forget the pairing with a TV (e.g. after a factory reset) so the next connect asks for
+/// approval on the TV again. The TV's certificate pin goes too: a reset TV has a new one.
+/// webOS has no endpoint for revoking a key, so the TV keeps accepting it until it is reset.
+#[tauri::command]
+async fn forget_pairing(
+    state: tauri::State<'_, Arc<AppState>>,
+    tv_name: String,
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            if !config.forget_pairing(&tv_name) {
+                return Err("TV not found".into());
+            }
+            Ok(())
+        })
+        .await?;
+
+    // A session with this TV was registered with the forgotten key
+    state
+        .tv
+        .run(move |tv| {
+            Box::pin(async move {
+                if tv.name == tv_name {
+                    tv.disconnect().await;
+                }
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+/// Set the friendly label for one of the active TV's inputs; `None` or empty restores the
+/// TV's own label.
+#[tauri::command]
+async fn set_input_label(
+    state: tauri::State<'_, Arc<AppState>>,
+    input_id: String,
+    label: Option<String>,
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            let name = config
+                .get_active_tv()
+                .map(|(name, _)| name.clone())
+                .ok_or_else(|| AppError::catalog("no_tv_configured", &[]))?;
+            let tv = config
+                .tvs
+                .get_mut(&name)
+                .ok_or_else(|| AppError::catalog("no_tv_configured", &[]))?;
+            match label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty()) {
+                Some(label) => {
+                    tv.input_labels.insert(input_id, label);
+                }
+                None => {
+                    tv.input_labels.remove(&input_id);
+                }
+            }
+            Ok(())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn connect_bluetooth_device(
+    state: tauri::State<'_, Arc<AppState>>,
+    address: String,
+) -> Result<CommandResult, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.set_bluetooth_connected(&address, true).await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn disconnect_bluetooth_device(
+    state: tauri::State<'_, Arc<AppState>>,
+    address: String,
+) -> Result<CommandResult, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.set_bluetooth_connected(&address, false).await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn start_pixel_cleaning(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<CommandResult, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.start_pixel_cleaning().await
+            })
+        })
+        .await
+}
+
+/// Launch an app with deep-link params (content ids etc.); see `TvConnection::launch_app`.
+#[tauri::command]
+async fn launch_app_with_params(
+    state: tauri::State<'_, Arc<AppState>>,
+    app_id: String,
+    params: Option<serde_json::Value>,
+) -> Result<CommandResult, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.launch_app(&app_id, params).await
+            })
+        })
+        .await
+}
+
+/// Push a hosted web app (by id) or a dashboard/kiosk page (by URL) to the TV.
+#[tauri::command]
+async fn launch_webapp(
+    state: tauri::State<'_, Arc<AppState>>,
+    url_or_id: String,
+    params: Option<serde_json::Value>,
+) -> Result<CommandResult, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.launch_webapp(url_or_id.trim(), params).await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn screen_on(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, AppError> {
+    state.record_action("screen_on");
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.screen_on().await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn set_energy_saving(
+    state: tauri::State<'_, Arc<AppState>>,
+    level: EnergySavingLevel,
+) -> Result<CommandResult, AppError> {
+    state.record_action(&format!("energy_saving_{}", level.as_webos()));
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.set_energy_saving(level).await
+            })
+        })
+        .await
+}
+
+async fn apply_scene_impl(state: &AppState, name: &str) -> Result<CommandResult, AppError> {
+    let scene = state
+        .config
+        .read(|c| c.scenes.get(name).cloned())
+        .await
+        .ok_or_else(|| format!("Scene {} not found", name))?;
+    let name = name.to_string();
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.apply_scene(&name, &scene).await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn apply_scene(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+) -> Result<CommandResult, AppError> {
+    state.record_action(&format!("scene_{}", name));
+    apply_scene_impl(&state, &name).await
+}
+
+#[tauri::command]
+async fn get_button_aliases(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<HashMap<String, String>, AppError> {
+    Ok(state.config.read(|c| c.button_aliases.clone()).await)
+}
+
+/// Add or replace a button alias (e.g. "OK" -> "ENTER"); `None` removes it.
+#[tauri::command]
+async fn set_button_alias(
+    state: tauri::State<'_, Arc<AppState>>,
+    alias: String,
+    button: Option<String>,
+) -> Result<(), AppError> {
+    let alias = alias.trim().to_uppercase();
+    if let Some(button) = &button {
+        buttons::validate_alias(&alias, button)?;
+    }
+    state
+        .config
+        .update(|config| {
+            match button {
+                Some(button) => {
+                    config.button_aliases.insert(alias, button.trim().to_uppercase());
+                }
+                None => {
+                    config.button_aliases.remove(&alias);
+                }
+            }
+            Ok(())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn get_scenes(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<HashMap<String, Scene>, AppError> {
+    Ok(state.config.read(|c| c.scenes.clone()).await)
+}
+
+/// Create or replace a scene; `None` deletes it.
+#[tauri::command]
+async fn save_scene(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+    scene: Option<Scene>,
+) -> Result<(), AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Scene name is required".into());
+    }
+    state
+        .config
+        .update(|config| {
+            match scene {
+                Some(scene) => {
+                    config.scenes.insert(name, scene);
+                }
+                None => {
+                    config.scenes.remove(&name);
+                }
+            }
+            Ok(())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn get_sound_mode(state: tauri::State<'_, Arc<AppState>>) -> Result<SoundMode, AppError> {
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.get_sound_mode().await
+            })
+        })
+        .await
+}
+
+#[tauri::command]
+async fn set_sound_mode(
+    state: tauri::State<'_, Arc<AppState>>,
+    mode: SoundMode,
+) -> Result<CommandResult, AppError> {
+    state.record_action(&format!("sound_mode_{}", mode.name()));
+    state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                tv.ensure_active()?;
+                tv.set_sound_mode(mode).await
+            })
+        })
+        .await
+}
+
+/// If the TV is in Quick Start+ standby and still answering SSAP, turn it on over the existing
+/// connection. Returns None when Wake-on-LAN is still needed.
+async fn wake_from_standby(state: &AppState) -> Option<CommandResult> {
+    let (name, preferred) = {
+        let config = state.config.get().await;
+        let (name, tv_config) = config.get_active_tv()?;
+        (name.clone(), tv_config.power_on_method)
+    };
+
+    let woken = state
+        .tv
+        .command(move |tv| {
+            Box::pin(async move {
+                if !tv.connected {
+                    return Ok(None);
+                }
+                if tv.get_power_state().await != Ok(PowerState::Standby) {
+                    return Ok(None);
+                }
+                Ok(Some(tv.wake_from_standby(preferred).await))
+            })
+        })
+        .await.ok().flatten()?;
+    match woken {
+        Ok(method) => {
+            if preferred != Some(method) {
+                let _ = state
+                    .config
+                    .update(|config| {
+                        config.update_power_on_method(&name, method);
+                        Ok(())
+                    })
+                    .await;
+            }
+            Some(CommandResult::ok_with_message("TV turned on from standby"))
+        }
+        Err(e) => {
+            log::warn!("Wake from standby failed ({}), falling back to Wake-on-LAN", e);
+            None
+        }
     }
-    tv.send_button(&button).await
 }
 
-#[tauri::command]
-async fn volume_up(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, String> {
-    let mut tv = state.tv.lock().await;
-    if !tv.connected {
-        return Err("Not connected".to_string());
-    }
-    tv.volume_up().await
-}
+/// Power on the active TV: over SSAP when it's in standby, otherwise via Wake-on-LAN.
+async fn power_on_impl(state: &AppState) -> Result<CommandResult, AppError> {
+    let (mac, ip, use_ssl, wol, wol_interface) = {
+        let config = state.config.get().await;
+        let (_, tv_config) = config
+            .get_active_tv()
+            .ok_or_else(|| AppError::catalog("no_tv_configured", &[]))?;
+        // A hostname may not resolve while the TV is off; unicast to where it was last seen
+        let ip = match (tv_config.ip.parse::<std::net::IpAddr>(), &tv_config.last_seen_ip) {
+            (Err(_), Some(seen)) => seen.clone(),
+            _ => tv_config.ip.clone(),
+        };
+        let wol_interface = tv_config.wol_interface.clone();
+        (tv_config.mac.clone(), ip, tv_config.use_ssl, config.wol, wol_interface)
+    };
 
-#[tauri::command]
-async fn volume_down(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, String> {
-    let mut tv = state.tv.lock().await;
-    if !tv.connected {
-        return Err("Not connected".to_string());
+    if let Some(result) = wake_from_standby(state).await {
+        return Ok(result);
     }
-    tv.volume_down().await
-}
 
-#[tauri::command]
-async fn set_mute(
-    state: tauri::State<'_, Arc<AppState>>,
-    mute: bool,
-) -> Result<CommandResult, String> {
-    let mut tv = state.tv.lock().await;
-    if !tv.connected {
-        return Err("Not connected".to_string());
+    let mac = mac.ok_or("MAC address not saved. Connect to the TV while it's on and click 'Fetch MAC', or set it manually in settings.")?;
+    let source = wol_interface.as_deref().map(netif::source_addr).transpose()?;
+    let result = tv::wake_on_lan(&mac, None, Some(&ip), source, wol).await?;
+    if let Some(retry_after) = wol.clamped().retry_after() {
+        // Some TVs ignore the first packets; send them again unless the TV answered by then
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(retry_after).await;
+            if tv::probe(&ip, use_ssl).await {
+                return;
+            }
+            log::debug!("WoL: TV not up after {:?}, sending again", retry_after);
+            if let Err(e) = tv::wake_on_lan(&mac, None, Some(&ip), source, wol).await {
+                log::warn!("WoL retry failed: {}", e);
+            }
+        });
     }
-    tv.set_mute(mute).await
+    // The TV should be coming up: retry the connection fast again
+    state.reconnector.kick();
+    Ok(result)
 }
 
-#[tauri::command]
-async fn power_off(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, String> {
-    let mut tv = state.tv.lock().await;
-    if !tv.connected {
-        return Err("Not connected".to_string());
+const TV_WAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const STREAMING_WAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(8);
+
+/// Power on the TV and, when enabled, wake the streaming device at the same time so a slow
+/// ADB handshake doesn't hold up the TV. Fails only if the TV wake fails; a streaming device
+/// failure is reported in the message.
+async fn power_on_with_streaming(state: &AppState) -> Result<CommandResult, AppError> {
+    let (streaming_device, wol) = state
+        .config
+        .read(|c| {
+            let device = c
+                .active_streaming_device()
+                .map(|(_, device)| device.clone())
+                .filter(|_| c.active_wake_streaming_on_power_on());
+            (device, c.wol)
+        })
+        .await;
+
+    let tv_wake = async {
+        tokio::time::timeout(TV_WAKE_TIMEOUT, power_on_impl(state))
+            .await
+            .unwrap_or_else(|_| Err(AppError::Timeout("Timed out waking the TV".into())))
+    };
+    let streaming_wake = async {
+        let device = streaming_device?;
+        Some(
+            tokio::time::timeout(STREAMING_WAKE_TIMEOUT, wake_streaming_device_impl(&device, wol))
+                .await
+                .unwrap_or_else(|_| Err(AppError::Timeout("timed out".into()))),
+        )
+    };
+    let (tv_result, streaming_result) = tokio::join!(tv_wake, streaming_wake);
+
+    if let Some(Err(e)) = &streaming_result {
+        log::warn!("Waking streaming device failed: {}", e);
     }
-    tv.power_off().await
+    let mut result = match tv_result {
+        Ok(result) => result,
+        Err(e) => {
+            return Err(match streaming_result {
+                Some(Ok(_)) => AppError::Other(format!("{} (streaming device woke)", e).into()),
+                _ => e,
+            });
+        }
+    };
+    if let Some(Err(e)) = streaming_result {
+        let tv_message = result.message.take().unwrap_or_else(|| "TV waking".to_string());
+        result.message = Some(format!("{}; streaming device failed: {}", tv_message, e));
+    }
+    Ok(result)
 }
 
-#[tauri::command]
-async fn power_on(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, String> {
-    let (mac, wake_streaming, streaming_device) = {
-        let config = state.config.lock().await;
-        let (_, tv_config) = config.get_active_tv().ok_or("No TV configured")?;
-        let mac = tv_config
-            .mac
-            .as_ref()
-            .ok_or("MAC address not saved. Connect to the TV while it's on and click 'Fetch MAC', or set it manually in settings.")?
-            .clone();
-        let wake_streaming = config.wake_streaming_on_power_on;
-        let streaming_device = config.streaming_device.clone();
-        (mac, wake_streaming, streaming_device)
+/// How long `power_on` with `connect` keeps checking for the TV after waking it.
+const POWER_ON_CONNECT_WAIT: std::time::Duration = std::time::Duration::from_secs(45);
+const POWER_ON_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// After waking the TV, poll its SSAP port and connect as soon as it answers. The TV opens
+/// the port a little before it accepts sessions, so failed connects are retried until
+/// `POWER_ON_CONNECT_WAIT` is up. Emits "power-on-progress" with `stage` "waiting",
+/// "connecting", then "connected" or "failed" (with `message`).
+async fn connect_after_power_on(app: AppHandle, state: Arc<AppState>) {
+    let started = std::time::Instant::now();
+    let progress = |stage: &str, message: Option<String>| {
+        let _ = app.emit(
+            "power-on-progress",
+            serde_json::json!({
+                "stage": stage,
+                "elapsed_ms": started.elapsed().as_millis() as u64,
+                "message": message,
+            }),
+        );
+    };
+    let target = state
+        .config
+        .read(|c| c.get_active_tv().map(|(_, tv)| (tv.ip.clone(), tv.use_ssl)))
+        .await;
+    let Some((ip, use_ssl)) = target else {
+        return;
     };
 
-    let result = tv::wake_on_lan(&mac, None)?;
-    if wake_streaming {
-        if let Some(device) = streaming_device {
-            let _ = wake_streaming_device_impl(&device).await;
+    progress("waiting", None);
+    let mut last_error = None;
+    loop {
+        // Woken from standby over the existing session, or auto-reconnect got there first
+        if state.tv.run(|tv| Box::pin(async move { tv.connected })).await.unwrap_or(false) {
+            progress("connected", None);
+            return;
+        }
+        if tv::probe(&ip, use_ssl).await {
+            progress("connecting", None);
+            match connect_impl(app.clone(), state.clone()).await {
+                Ok(_) => {
+                    log::info!("Connected {:?} after power on", started.elapsed());
+                    progress("connected", None);
+                    return;
+                }
+                Err(e) => {
+                    log::debug!("Connect after power on failed: {}", e);
+                    last_error = Some(e.to_string());
+                }
+            }
         }
+        if started.elapsed() >= POWER_ON_CONNECT_WAIT {
+            let message = last_error.unwrap_or_else(|| {
+                format!("TV did not respond within {}s", POWER_ON_CONNECT_WAIT.as_secs())
+            });
+            progress("failed", Some(message));
+            return;
+        }
+        tokio::time::sleep(POWER_ON_POLL_INTERVAL).await;
+    }
+}
+
+/// Power on the TV. With `connect`, also wait for it to come up and connect in the
+/// background (see `connect_after_power_on`).
+#[tauri::command]
+async fn power_on(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    connect: Option<bool>,
+) -> Result<CommandResult, AppError> {
+    state.record_action("power_on");
+    let result = power_on_with_streaming(&state).await?;
+    if connect.unwrap_or(false) {
+        tauri::async_runtime::spawn(connect_after_power_on(app, state.inner().clone()));
     }
     Ok(result)
 }
 
 #[tauri::command]
-async fn fetch_mac(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, String> {
-    let config = state.config.lock().await;
-    let (name, _) = config.get_active_tv().ok_or("No TV configured")?;
+async fn fetch_mac(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, AppError> {
+    let config = state.config.get().await;
+    let (name, _) = config
+        .get_active_tv()
+        .ok_or_else(|| AppError::catalog("no_tv_configured", &[]))?;
     let name = name.clone();
     drop(config);
 
-    let mut tv = state.tv.lock().await;
-    if !tv.connected {
-        return Err("Not connected. Connect to the TV first.".to_string());
-    }
-
     // Get MAC of the connected interface (wifi or wired)
-    match tv.get_connected_mac().await {
-        Ok(Some(mac)) => {
-            let mut config = state.config.lock().await;
-            config.update_mac(&name, mac.clone());
-            config.save()?;
-            Ok(CommandResult::ok_with_message(&format!("MAC address saved: {}", mac)))
-        }
-        Ok(None) => {
-            Err("Could not find MAC address in TV response. Please enter manually.".to_string())
-        }
-        Err(e) => {
-            Err(format!("Failed to get MAC address: {}. Please enter manually.", e))
+    let (mac, capabilities) = state
+        .tv
+        .command(|tv| {
+            Box::pin(async move {
+                if !tv.connected {
+                    return Err(AppError::catalog("not_connected", &[]));
+                }
+                let mac = tv.get_connected_mac().await.map_err(|e| {
+                    format!("Failed to get MAC address: {}. Please enter manually.", e)
+                })?;
+                Ok((mac, tv.capabilities.clone()))
+            })
+        })
+        .await?;
+
+    state
+        .config
+        .update(|config| {
+            config.update_capabilities(&name, capabilities);
+            if let Some(mac) = &mac {
+                config.update_mac(&name, mac.clone());
+            }
+            Ok(())
+        })
+        .await?;
+    match mac {
+        Some(mac) => Ok(CommandResult::ok_with_message(&format!("MAC address saved: {}", mac))),
+        None => {
+            Err("Could not find MAC address in TV response. Please enter manually.".into())
         }
     }
 }
@@ -354,17 +2643,13 @@ async fn fetch_mac(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResu
 async fn set_mac(
     state: tauri::State<'_, Arc<AppState>>,
     mac: String,
-) -> Result<CommandResult, String> {
+) -> Result<CommandResult, AppError> {
     // Validate MAC format (basic check)
     let mac_clean = mac.replace([':', '-', ' '], "");
     if mac_clean.len() != 12 || !mac_clean.chars().all(|c| c.is_ascii_hexdigit()) {
-        return Err("Invalid MAC address format. Use format like AA:BB:CC:DD:EE:FF or AABBCCDDEEFF".to_string());
+        return Err("Invalid MAC address format. Use format like AA:BB:CC:DD:EE:FF or AABBCCDDEEFF".into());
     }
 
-    let mut config = state.config.lock().await;
-    let (name, _) = config.get_active_tv().ok_or("No TV configured")?;
-    let name = name.clone();
-
     // Normalize to colon-separated format
     let mac_formatted = mac_clean
         .as_bytes()
@@ -373,16 +2658,28 @@ async fn set_mac(
         .collect::<Vec<_>>()
         .join(":");
 
-    config.update_mac(&name, mac_formatted.clone());
-    config.save()?;
+    state
+        .config
+        .update(|config| {
+            let (name, _) = config
+            .get_active_tv()
+            .ok_or_else(|| AppError::catalog("no_tv_configured", &[]))?;
+            let name = name.clone();
+            config.update_mac(&name, mac_formatted.clone());
+            Ok(())
+        })
+        .await?;
 
     Ok(CommandResult::ok_with_message(&format!("MAC address set to: {}", mac_formatted)))
 }
 
-async fn wake_streaming_device_impl(device: &StreamingDeviceConfig) -> Result<CommandResult, String> {
+async fn wake_streaming_device_impl(
+    device: &StreamingDeviceConfig,
+    wol: WolConfig,
+) -> Result<CommandResult, AppError> {
     match device {
         StreamingDeviceConfig::Wol { mac, broadcast_ip } => {
-            tv::wake_on_lan(mac, broadcast_ip.as_deref())
+            tv::wake_on_lan(mac, broadcast_ip.as_deref(), None, None, wol).await
         }
         StreamingDeviceConfig::Adb { ip, port } => {
             tv::wake_adb(ip, port.unwrap_or(5555)).await
@@ -391,36 +2688,207 @@ async fn wake_streaming_device_impl(device: &StreamingDeviceConfig) -> Result<Co
     }
 }
 
+/// Wake the named streaming device, or the active one when no name is given.
 #[tauri::command]
-async fn wake_streaming_device(state: tauri::State<'_, Arc<AppState>>) -> Result<CommandResult, String> {
-    let config = state.config.lock().await;
-    let device = config
-        .streaming_device
-        .as_ref()
-        .ok_or("No streaming device configured. Add one in Settings (e.g. Android TV / Shield MAC for Wake-on-LAN, or Roku IP).")?
-        .clone();
+async fn wake_streaming_device(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: Option<String>,
+) -> Result<CommandResult, AppError> {
+    let config = state.config.get().await;
+    let device = match name.as_deref() {
+        Some(name) => config
+            .streaming_devices
+            .devices
+            .get(name)
+            .ok_or_else(|| format!("No streaming device named {}", name))?,
+        None => config
+            .active_streaming_device()
+            .map(|(_, device)| device)
+            .ok_or("No streaming device configured. Add one in Settings (e.g. Android TV / Shield MAC for Wake-on-LAN, or Roku IP).")?,
+    }
+    .clone();
+    let wol = config.wol;
     drop(config);
-    wake_streaming_device_impl(&device).await
+    wake_streaming_device_impl(&device, wol).await
 }
 
+/// Add, replace or (with `device: null`) remove a streaming device. Without a name this edits
+/// the device in effect for the active TV and makes it the active one.
 #[tauri::command]
 async fn set_streaming_device(
     state: tauri::State<'_, Arc<AppState>>,
+    name: Option<String>,
     device: Option<StreamingDeviceConfig>,
-) -> Result<(), String> {
-    let mut config = state.config.lock().await;
-    config.set_streaming_device(device);
-    config.save()
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            let select = name.is_none() && device.is_some();
+            let name = name
+                .or_else(|| config.active_streaming_device().map(|(name, _)| name.clone()))
+                .unwrap_or_else(|| DEFAULT_STREAMING_DEVICE.to_string());
+            config.set_streaming_device(name.clone(), device);
+            if select {
+                config.set_active_streaming_device(Some(name));
+            }
+            Ok(())
+        })
+        .await
+}
+
+/// Choose which streaming device "Wake streaming device" and Power On wake (for the active
+/// TV, if its profile picks its own); None for none.
+#[tauri::command]
+async fn select_streaming_device(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: Option<String>,
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            if let Some(name) = &name
+                && !config.streaming_devices.devices.contains_key(name)
+            {
+                return Err(format!("No streaming device named {}", name).into());
+            }
+            config.set_active_streaming_device(name);
+            Ok(())
+        })
+        .await
+}
+
+/// Language for backend messages; None (or an unsupported locale) means English.
+#[tauri::command]
+async fn set_locale(
+    state: tauri::State<'_, Arc<AppState>>,
+    locale: Option<String>,
+) -> Result<(), AppError> {
+    let locale = match locale.as_deref().filter(|l| !l.is_empty()) {
+        Some(l) => Some(
+            i18n::supported(l)
+                .ok_or_else(|| format!("Unsupported locale {}", l))?
+                .to_string(),
+        ),
+        None => None,
+    };
+    i18n::set_locale(locale.as_deref());
+    state
+        .config
+        .update(|config| {
+            config.locale = locale;
+            Ok(())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn set_auto_reconnect(
+    state: tauri::State<'_, Arc<AppState>>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            config.auto_reconnect = enabled;
+            Ok(())
+        })
+        .await?;
+    state.reconnector.kick();
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_timeouts(state: tauri::State<'_, Arc<AppState>>) -> Result<TimeoutConfig, AppError> {
+    Ok(state.config.read(|c| c.timeouts).await)
+}
+
+/// Set the connect/command timeouts and keepalive interval (clamped to sane ranges). The
+/// timeouts apply to the next command; the keepalive interval from the next connect.
+#[tauri::command]
+async fn set_timeouts(
+    state: tauri::State<'_, Arc<AppState>>,
+    timeouts: TimeoutConfig,
+) -> Result<TimeoutConfig, AppError> {
+    let timeouts = timeouts.clamped();
+    state
+        .config
+        .update(|config| {
+            config.timeouts = timeouts;
+            Ok(())
+        })
+        .await?;
+    Ok(timeouts)
+}
+
+#[tauri::command]
+async fn get_wol_config(state: tauri::State<'_, Arc<AppState>>) -> Result<WolConfig, AppError> {
+    Ok(state.config.read(|c| c.wol).await)
+}
+
+/// Set how many Wake-on-LAN packets to send, how far apart, and whether to send them again
+/// when the TV doesn't come up (clamped to sane ranges).
+#[tauri::command]
+async fn set_wol_config(
+    state: tauri::State<'_, Arc<AppState>>,
+    wol: WolConfig,
+) -> Result<WolConfig, AppError> {
+    let wol = wol.clamped();
+    state
+        .config
+        .update(|config| {
+            config.wol = wol;
+            Ok(())
+        })
+        .await?;
+    Ok(wol)
 }
 
 #[tauri::command]
 async fn set_wake_streaming_on_power_on(
     state: tauri::State<'_, Arc<AppState>>,
     enabled: bool,
-) -> Result<(), String> {
-    let mut config = state.config.lock().await;
-    config.wake_streaming_on_power_on = enabled;
-    config.save()
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            config.set_active_wake_streaming_on_power_on(enabled);
+            Ok(())
+        })
+        .await
+}
+
+/// Validate config, shortcuts, TV reachability and optional integrations for troubleshooting.
+#[tauri::command]
+async fn run_self_test(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<selftest::SelfTestReport, AppError> {
+    let config = state.config.get().await;
+    Ok(selftest::run(&config).await)
+}
+
+/// Walk through the connection to the active TV step by step (address, reachability, ports
+/// 3000/3001, TLS, pairing, input socket) for the troubleshooting checklist.
+#[tauri::command]
+async fn diagnose_connection(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<diagnose::ConnectionReport, AppError> {
+    let (name, tv_config) = state
+        .config
+        .read(|c| c.get_active_tv().map(|(name, tv)| (name.clone(), tv.clone())))
+        .await
+        .ok_or_else(|| AppError::catalog("no_tv_configured", &[]))?;
+    let session = state
+        .tv
+        .run(|tv| {
+            Box::pin(async move {
+                diagnose::Session {
+                    connected: tv.connected,
+                    input: tv.connected.then(|| tv.input_capabilities()),
+                }
+            })
+        })
+        .await?;
+    Ok(diagnose::run(&name, &tv_config, session).await)
 }
 
 #[tauri::command]
@@ -436,7 +2904,7 @@ const AUTOSTART_EXEC_ENV: &str = "TAURI_AUTOSTART_EXEC";
 
 #[cfg(feature = "autostart")]
 #[tauri::command]
-fn get_autostart_enabled(app: tauri::AppHandle) -> Result<bool, String> {
+fn get_autostart_enabled(app: tauri::AppHandle) -> Result<bool, AppError> {
     #[cfg(target_os = "linux")]
     if let Ok(exec) = std::env::var(AUTOSTART_EXEC_ENV) {
         if !exec.is_empty() {
@@ -447,15 +2915,15 @@ fn get_autostart_enabled(app: tauri::AppHandle) -> Result<bool, String> {
                 LinuxLaunchMode::XdgAutostart,
                 &[] as &[&str],
             );
-            return auto.is_enabled().map_err(|e| e.to_string());
+            return auto.is_enabled().map_err(|e| AppError::from(e.to_string()));
         }
     }
-    app.autolaunch().is_enabled().map_err(|e| e.to_string())
+    app.autolaunch().is_enabled().map_err(|e| AppError::from(e.to_string()))
 }
 
 #[cfg(feature = "autostart")]
 #[tauri::command]
-fn set_autostart_enabled(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+fn set_autostart_enabled(app: tauri::AppHandle, enabled: bool) -> Result<(), AppError> {
     #[cfg(target_os = "linux")]
     if let Ok(exec) = std::env::var(AUTOSTART_EXEC_ENV) {
         if !exec.is_empty() {
@@ -467,29 +2935,29 @@ fn set_autostart_enabled(app: tauri::AppHandle, enabled: bool) -> Result<(), Str
                 &[] as &[&str],
             );
             return if enabled {
-                auto.enable().map_err(|e| e.to_string())
+                auto.enable().map_err(|e| AppError::from(e.to_string()))
             } else {
-                auto.disable().map_err(|e| e.to_string())
+                auto.disable().map_err(|e| AppError::from(e.to_string()))
             };
         }
     }
     if enabled {
-        app.autolaunch().enable().map_err(|e| e.to_string())
+        app.autolaunch().enable().map_err(|e| AppError::from(e.to_string()))
     } else {
-        app.autolaunch().disable().map_err(|e| e.to_string())
+        app.autolaunch().disable().map_err(|e| AppError::from(e.to_string()))
     }
 }
 
 #[cfg(not(feature = "autostart"))]
 #[tauri::command]
-fn get_autostart_enabled(_app: tauri::AppHandle) -> Result<bool, String> {
+fn get_autostart_enabled(_app: tauri::AppHandle) -> Result<bool, AppError> {
     Ok(false)
 }
 
 #[cfg(not(feature = "autostart"))]
 #[tauri::command]
-fn set_autostart_enabled(_app: tauri::AppHandle, _enabled: bool) -> Result<(), String> {
-    Err("Autostart is not available in this build".to_string())
+fn set_autostart_enabled(_app: tauri::AppHandle, _enabled: bool) -> Result<(), AppError> {
+    Err("Autostart is not available in this build".into())
 }
 
 #[tauri::command]
@@ -505,23 +2973,25 @@ fn is_dev() -> bool {
 
 /// Current main window outer size in physical pixels. For dev UI.
 #[tauri::command]
-fn get_window_size(app: tauri::AppHandle) -> Result<(u32, u32), String> {
+fn get_window_size(app: tauri::AppHandle) -> Result<(u32, u32), AppError> {
     app.get_webview_window("main")
         .and_then(|w| w.outer_size().ok())
         .map(|s| (s.width, s.height))
-        .ok_or_else(|| "Window not available".to_string())
+        .ok_or_else(|| "Window not available".into())
 }
 
 #[tauri::command]
 async fn reset_window_size(
     app: tauri::AppHandle,
     state: tauri::State<'_, Arc<AppState>>,
-) -> Result<(), String> {
-    {
-        let mut config = state.config.lock().await;
-        config.window_size = None;
-        config.save()?;
-    }
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            config.set_active_window_size(None);
+            Ok(())
+        })
+        .await?;
     // Use default size from tauri.conf.json (app.windows[0]). Use Logical size so the window
     // has the same apparent size on all displays (e.g. Retina 2x vs 1x); Physical would make
     // the window look tiny on high-DPI Macs.
@@ -535,82 +3005,453 @@ async fn reset_window_size(
     if let Some(window) = app.get_webview_window("main") {
         let _ = window.set_size(tauri::Size::Logical(tauri::LogicalSize { width, height }));
     }
-    Ok(())
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_shortcut_settings(state: tauri::State<'_, Arc<AppState>>) -> Result<(String, bool), AppError> {
+    let config = state.config.get().await;
+    Ok((config.global_shortcut.clone(), config.shortcut_enabled))
+}
+
+#[tauri::command]
+async fn set_shortcut(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    shortcut: String,
+    enabled: bool,
+) -> Result<(), AppError> {
+    if enabled && !shortcut.is_empty() {
+        shortcut::parse(&shortcut)?;
+        if !shortcut::has_modifier(&shortcut) {
+            return Err(
+                "Global shortcut must include a modifier (Ctrl, Alt, Shift, or Super) so it doesn't capture keys during normal typing.".into()
+            );
+        }
+    }
+    let config = state
+        .config
+        .update(|config| {
+            config.global_shortcut = shortcut.clone();
+            config.shortcut_enabled = enabled;
+            Ok(config.clone())
+        })
+        .await?;
+    register_all_global_shortcuts(&app, &config)?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_tv_shortcut(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+    shortcut: Option<String>,
+) -> Result<(), AppError> {
+    let shortcut = shortcut.filter(|s| !s.trim().is_empty());
+    if let Some(ref shortcut) = shortcut {
+        shortcut::parse(shortcut)?;
+        if !shortcut::has_modifier(shortcut) {
+            return Err(
+                "Global shortcut must include a modifier (Ctrl, Alt, Shift, or Super) so it doesn't capture keys during normal typing.".into()
+            );
+        }
+    }
+    let config = state
+        .config
+        .update(|config| {
+            let tv = config.tvs.get_mut(&name).ok_or("TV not found")?;
+            tv.toggle_shortcut = shortcut;
+            Ok(config.clone())
+        })
+        .await?;
+    register_all_global_shortcuts(&app, &config)?;
+    Ok(())
+}
+
+/// Send this TV's Wake-on-LAN packets from a given interface name or local address; None
+/// goes back to picking the interface that reaches the TV.
+#[tauri::command]
+async fn set_tv_wol_interface(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+    interface: Option<String>,
+) -> Result<(), AppError> {
+    let interface = interface
+        .map(|i| i.trim().to_string())
+        .filter(|i| !i.is_empty());
+    if let Some(ref interface) = interface {
+        netif::source_addr(interface)?;
+    }
+    state
+        .config
+        .update(|config| {
+            let tv = config.tvs.get_mut(&name).ok_or("TV not found")?;
+            tv.wol_interface = interface;
+            Ok(())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn get_action_shortcuts(state: tauri::State<'_, Arc<AppState>>) -> Result<HashMap<String, ActionShortcutConfig>, AppError> {
+    let config = state.config.get().await;
+    Ok(config.active_action_shortcuts().clone())
+}
+
+#[tauri::command]
+async fn set_action_shortcuts(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    shortcuts: HashMap<String, ActionShortcutConfig>,
+) -> Result<(), AppError> {
+    check_action_shortcuts(&shortcuts)?;
+    let config = state
+        .config
+        .update(|config| {
+            config.set_active_action_shortcuts(shortcuts);
+            Ok(config.clone())
+        })
+        .await?;
+    register_all_global_shortcuts(&app, &config)?;
+    Ok(())
+}
+
+/// Any shortcut with global=true must have a modifier.
+fn check_action_shortcuts(shortcuts: &HashMap<String, ActionShortcutConfig>) -> Result<(), String> {
+    let missing: Vec<String> = shortcuts
+        .iter()
+        .filter(|(_, ac)| ac.global && !ac.shortcut.trim().is_empty())
+        .filter(|(_, ac)| !shortcut::has_modifier(&ac.shortcut))
+        .map(|(id, _)| id.clone())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Global shortcuts must include a modifier (Ctrl, Alt, Shift, or Super): {}",
+            missing.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_tv_profile(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+) -> Result<TvProfile, AppError> {
+    state
+        .config
+        .read(|c| c.tvs.get(&name).map(|tv| tv.profile.clone()))
+        .await
+        .ok_or_else(|| "TV not found".into())
+}
+
+/// Set the settings that differ for one TV (action shortcuts, streaming device, waking it on
+/// power on, window size); fields left out use the global settings.
+#[tauri::command]
+async fn set_tv_profile(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+    profile: TvProfile,
+) -> Result<(), AppError> {
+    if let Some(shortcuts) = &profile.action_shortcuts {
+        check_action_shortcuts(shortcuts)?;
+    }
+    let active = state
+        .config
+        .update(|config| {
+            let tv = config.tvs.get_mut(&name).ok_or("TV not found")?;
+            tv.profile = profile;
+            Ok(config.get_active_tv().is_some_and(|(active, _)| *active == name))
+        })
+        .await?;
+    if active {
+        apply_active_profile(&app, &state).await;
+    }
+    Ok(())
+}
+
+/// Apply what the active TV's profile changes outside the config: global shortcuts and the
+/// window size.
+async fn apply_active_profile(app: &AppHandle, state: &AppState) {
+    let config = state.config.get().await;
+    if let Err(e) = register_all_global_shortcuts(app, &config) {
+        log::warn!("Failed to register global shortcuts: {}", e);
+    }
+    if let (Some(size), Some(window)) =
+        (config.active_window_size(), app.get_webview_window("main"))
+    {
+        let _ = window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
+            width: size.width,
+            height: size.height,
+        }));
+    }
+}
+
+/// Run an action, log the outcome and emit "action-executed" so the frontend (and anything
+/// chaining off completions) sees every action regardless of where it was triggered.
+/// `macro_<name>` ids replay a recorded macro.
+async fn execute_action(
+    app: &AppHandle,
+    state: Arc<AppState>,
+    action_id: &str,
+    source: ActionSource,
+) -> Result<(), AppError> {
+    if let Some(name) = action_id.strip_prefix("macro_") {
+        let result = play_macro(app, &state, name).await;
+        let (outcome, error) = match &result {
+            Ok(()) => (AutomationOutcome::Succeeded, None),
+            Err(e) => (AutomationOutcome::Failed, Some(e.to_string())),
+        };
+        let reason = format!("started from {}", source.name());
+        state.automations.record("macro", name, &reason, outcome, error);
+        report_action(app, &state, action_id, source, &result).await;
+        return result;
+    }
+    state.record_action(action_id);
+    let result = run_action_impl(state.clone(), action_id).await;
+    report_action(app, &state, action_id, source, &result).await;
+    result
+}
+
+async fn report_action(
+    app: &AppHandle,
+    state: &AppState,
+    action_id: &str,
+    source: ActionSource,
+    result: &Result<(), AppError>,
+) {
+    match result {
+        Ok(()) => log::info!("Action {} ({:?}) succeeded", action_id, source),
+        Err(e) => log::warn!("Action {} ({:?}) failed: {}", action_id, source, e),
+    }
+    let _ = app.emit("action-executed", ActionExecuted::new(action_id, source, result));
+    // Hidden window: nothing on screen would show the outcome
+    if source == ActionSource::Shortcut && !WINDOW_VISIBLE.load(Ordering::SeqCst) {
+        let feedback = state
+            .config
+            .read(|c| c.action_feedback.get(action_id).copied())
+            .await;
+        if let Some(feedback) = feedback {
+            feedback::report(app, &feedback, action_id, result);
+        }
+    }
+}
+
+/// Replay a recorded macro with its original timing. Stops at the first failing step.
+async fn play_macro(app: &AppHandle, state: &Arc<AppState>, name: &str) -> Result<(), AppError> {
+    let steps = {
+        let config = state.config.get().await;
+        config
+            .macros
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Macro {} not found", name))?
+    };
+    for step in steps {
+        if step.action.starts_with("macro_") {
+            log::warn!("Skipping nested macro {} in {}", step.action, name);
+            continue;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(step.delay_ms)).await;
+        let result = if step.action == "power_off" {
+            let app = app.clone();
+            let reason = format!("step of macro {}", name);
+            state
+                .tv
+                .command(move |tv| {
+                    Box::pin(async move { automated_power_off(&app, tv, "macro", &reason).await })
+                })
+                .await
+        } else {
+            run_action_impl(state.clone(), &step.action).await
+        };
+        report_action(app, state, &step.action, ActionSource::Macro, &result).await;
+        result.map_err(|e| format!("Macro {} stopped at {}: {}", name, step.action, e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn start_macro_recording(state: tauri::State<'_, Arc<AppState>>) -> Result<(), AppError> {
+    let mut recorder = state.recorder.lock().unwrap();
+    if recorder.is_some() {
+        return Err("Already recording a macro".into());
+    }
+    *recorder = Some(MacroRecorder::new());
+    Ok(())
+}
+
+/// Stop recording and save the steps as `name` (replacing a macro with that name). Without a
+/// name the recording is discarded.
+#[tauri::command]
+async fn stop_macro_recording(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: Option<String>,
+) -> Result<Vec<MacroStep>, AppError> {
+    let recorder = state
+        .recorder
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or("Not recording a macro")?;
+    let steps = recorder.finish();
+    let Some(name) = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()) else {
+        return Ok(steps);
+    };
+    if steps.is_empty() {
+        return Err("Nothing was recorded".into());
+    }
+    state
+        .config
+        .update(|config| {
+            config.macros.insert(name, steps.clone());
+            Ok(())
+        })
+        .await?;
+    Ok(steps)
 }
 
 #[tauri::command]
-async fn get_shortcut_settings(state: tauri::State<'_, Arc<AppState>>) -> Result<(String, bool), String> {
-    let config = state.config.lock().await;
-    Ok((config.global_shortcut.clone(), config.shortcut_enabled))
+async fn get_macros(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<HashMap<String, Vec<MacroStep>>, AppError> {
+    let config = state.config.get().await;
+    Ok(config.macros.clone())
 }
 
 #[tauri::command]
-async fn set_shortcut(
-    app: tauri::AppHandle,
+async fn delete_macro(state: tauri::State<'_, Arc<AppState>>, name: String) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            config
+                .macros
+                .remove(&name)
+                .map(|_| ())
+                .ok_or_else(|| "Macro not found".into())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn get_action_feedback(
     state: tauri::State<'_, Arc<AppState>>,
-    shortcut: String,
-    enabled: bool,
-) -> Result<(), String> {
-    if enabled && !shortcut.is_empty() {
-        shortcut.parse::<Shortcut>().map_err(|e| {
-            format!(
-                "Invalid shortcut '{}': {}. Use modifiers first and only one main key (e.g. Shift+Alt+K)",
-                shortcut, e
-            )
-        })?;
-        if !shortcut_has_modifier(&shortcut) {
-            return Err(
-                "Global shortcut must include a modifier (Ctrl, Alt, Shift, or Super) so it doesn't capture keys during normal typing.".to_string()
-            );
-        }
-    }
-    {
-        let mut config = state.config.lock().await;
-        config.global_shortcut = shortcut.clone();
-        config.shortcut_enabled = enabled;
-        config.save()?;
-    }
-    register_all_global_shortcuts(&app)?;
-    Ok(())
+) -> Result<HashMap<String, FeedbackConfig>, AppError> {
+    let config = state.config.get().await;
+    Ok(config.action_feedback.clone())
 }
 
+/// Set sound/notification feedback for an action's global shortcut; `None` removes it.
 #[tauri::command]
-async fn get_action_shortcuts(state: tauri::State<'_, Arc<AppState>>) -> Result<HashMap<String, ActionShortcutConfig>, String> {
-    let config = state.config.lock().await;
-    Ok(config.action_shortcuts.clone())
+async fn set_action_feedback(
+    state: tauri::State<'_, Arc<AppState>>,
+    action_id: String,
+    feedback: Option<FeedbackConfig>,
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            match feedback {
+                Some(feedback) => {
+                    config.action_feedback.insert(action_id, feedback);
+                }
+                None => {
+                    config.action_feedback.remove(&action_id);
+                }
+            }
+            Ok(())
+        })
+        .await
 }
 
+/// Run an action by id from the window (for actions without a dedicated command).
 #[tauri::command]
-async fn set_action_shortcuts(
-    app: tauri::AppHandle,
+async fn run_action(
+    app: AppHandle,
     state: tauri::State<'_, Arc<AppState>>,
-    shortcuts: HashMap<String, ActionShortcutConfig>,
-) -> Result<(), String> {
-    // Validate: any shortcut with global=true must have a modifier
-    let missing: Vec<String> = shortcuts
-        .iter()
-        .filter(|(_, ac)| ac.global && !ac.shortcut.trim().is_empty())
-        .filter(|(_, ac)| !shortcut_has_modifier(&ac.shortcut))
-        .map(|(id, _)| id.clone())
-        .collect();
-    if !missing.is_empty() {
-        return Err(format!(
-            "Global shortcuts must include a modifier (Ctrl, Alt, Shift, or Super): {}",
-            missing.join(", ")
-        ));
+    action_id: String,
+) -> Result<(), AppError> {
+    execute_action(&app, state.inner().clone(), &action_id, ActionSource::Ui).await
+}
+
+/// Run an action by id (used for global shortcuts so they work when window is hidden).
+async fn run_action_impl(state: Arc<AppState>, action_id: &str) -> Result<(), AppError> {
+    if let Some(name) = action_id.strip_prefix("scene_") {
+        return apply_scene_impl(&state, name).await.map(|_| ());
     }
-    {
-        let mut config = state.config.lock().await;
-        config.action_shortcuts = shortcuts;
-        config.save()?;
+    // app_<id>: launch a TV app (tray menu entries)
+    if let Some(app_id) = action_id.strip_prefix("app_") {
+        let app_id = app_id.to_string();
+        return state
+            .tv
+            .command(move |tv| {
+                Box::pin(async move {
+                    tv.ensure_active()?;
+                    tv.launch_app(&app_id, None).await.map(|_| ())
+                })
+            })
+            .await;
     }
-    register_all_global_shortcuts(&app)?;
-    Ok(())
+    // button_<name>: any remote button or alias (recorded from the window's buttons)
+    if let Some(name) = action_id.strip_prefix("button_") {
+        let button = state.resolve_button(name).await;
+        return state
+            .tv
+            .command(move |tv| {
+                Box::pin(async move {
+                    tv.ensure_active()?;
+                    tv.send_button(&button).await.map(|_| ())
+                })
+            })
+            .await;
+    }
+    // Actions that wake devices run outside the connection job: waking waits on the TV itself
+    match action_id {
+        "power_on" => return power_on_with_streaming(&state).await.map(|_| ()),
+        "volume_up" => return state.tv.volume_step(1).await.map(|_| ()),
+        "volume_down" => return state.tv.volume_step(-1).await.map(|_| ()),
+        "power_toggle" => {
+            let powered_off = state
+                .tv
+                .command(|tv| {
+                    Box::pin(async move {
+                        if tv.connected && tv.power_state != PowerState::Standby {
+                            tv.power_off().await.map(|_| true)
+                        } else {
+                            Ok(false)
+                        }
+                    })
+                })
+                .await?;
+            if powered_off {
+                return Ok(());
+            }
+            return power_on_impl(&state).await.map(|_| ());
+        }
+        "wake_streaming_device" => {
+            let (device, wol) = state
+                .config
+                .read(|c| (c.active_streaming_device().map(|(_, d)| d.clone()), c.wol))
+                .await;
+            let device = device.ok_or("No streaming device configured")?;
+            return wake_streaming_device_impl(&device, wol).await.map(|_| ());
+        }
+        _ => {}
+    }
+    let action_id = action_id.to_string();
+    state
+        .tv
+        .command(move |tv| Box::pin(async move { run_tv_action(tv, &action_id).await }))
+        .await
 }
 
-/// Run an action by id (used for global shortcuts so they work when window is hidden).
-async fn run_action_impl(state: Arc<AppState>, action_id: &str) -> Result<(), String> {
-    let mut tv = state.tv.lock().await;
+/// Run an action that only needs the TV connection; runs as a connection job.
+async fn run_tv_action(tv: &mut TvConnection, action_id: &str) -> Result<(), AppError> {
+    if action_id != "power_off" {
+        tv.ensure_active()?;
+    }
     match action_id {
         "up" => tv.send_button("UP").await.map(|_| ()),
         "down" => tv.send_button("DOWN").await.map(|_| ()),
@@ -623,58 +3464,60 @@ async fn run_action_impl(state: Arc<AppState>, action_id: &str) -> Result<(), St
         "pause" => tv.send_button("PAUSE").await.map(|_| ()),
         "stop" => tv.send_button("STOP").await.map(|_| ()),
         "fast_forward" => tv.send_button("FAST_FORWARD").await.map(|_| ()),
-        "volume_up" => tv.volume_up().await.map(|_| ()),
-        "volume_down" => tv.volume_down().await.map(|_| ()),
         "mute" => tv.set_mute(true).await.map(|_| ()),
+        "mute_toggle" => tv.send_button("MUTE").await.map(|_| ()),
         "unmute" => tv.set_mute(false).await.map(|_| ()),
         "power_off" => tv.power_off().await.map(|_| ()),
         "home" => tv.send_button("HOME").await.map(|_| ()),
-        "power_on" => {
-            drop(tv);
-            let config = state.config.lock().await;
-            let (_, tv_config) = config.get_active_tv().ok_or("No TV configured")?;
-            let mac = tv_config
-                .mac
-                .as_ref()
-                .ok_or("MAC address not saved")?
-                .clone();
-            drop(config);
-            tv::wake_on_lan(&mac, None).map(|_| ())
+        "red" => tv.send_button("RED").await.map(|_| ()),
+        "green" => tv.send_button("GREEN").await.map(|_| ()),
+        "yellow" => tv.send_button("YELLOW").await.map(|_| ()),
+        "blue" => tv.send_button("BLUE").await.map(|_| ()),
+        "info" => tv.send_button("INFO").await.map(|_| ()),
+        "exit" => tv.send_button("EXIT").await.map(|_| ()),
+        "menu" => tv.send_button("MENU").await.map(|_| ()),
+        "qmenu" => tv.send_button("QMENU").await.map(|_| ()),
+        "guide" => tv.send_button("GUIDE").await.map(|_| ()),
+        "list" => tv.send_button("LIST").await.map(|_| ()),
+        "record" => tv.send_button("RECORD").await.map(|_| ()),
+        "screen_off" => tv.screen_off().await.map(|_| ()),
+        "screen_on" => tv.screen_on().await.map(|_| ()),
+        "show_tv_keypad" => tv.show_keypad().await.map(|_| ()),
+        "pixel_cleaning" => tv.start_pixel_cleaning().await.map(|_| ()),
+        // energy_saving_off, energy_saving_min, ..., energy_saving_screen_off
+        id if id.starts_with("energy_saving_") => {
+            let level = EnergySavingLevel::parse(&id["energy_saving_".len()..])
+                .ok_or_else(|| format!("Unknown energy saving level in action {}", id))?;
+            tv.set_energy_saving(level).await.map(|_| ())
         }
-        "wake_streaming_device" => {
-            drop(tv);
-            let config = state.config.lock().await;
-            let device = config
-                .streaming_device
-                .clone()
-                .ok_or("No streaming device configured")?;
-            drop(config);
-            wake_streaming_device_impl(&device).await.map(|_| ())
+        // input_hdmi_1, input_hdmi_2, ...
+        id if id.starts_with("input_") => tv
+            .switch_input(&id["input_".len()..].to_uppercase())
+            .await
+            .map(|_| ()),
+        // sound_mode_standard, sound_mode_cinema, sound_mode_clear_voice, ...
+        id if id.starts_with("sound_mode_") => {
+            let mode = SoundMode::parse(&id["sound_mode_".len()..])
+                .ok_or_else(|| format!("Unknown sound mode in action {}", id))?;
+            tv.set_sound_mode(mode).await.map(|_| ())
         }
         _ => Ok(()),
     }
 }
 
-/// Modifier key names (case-insensitive). Global hotkeys must include at least one
-/// so they don't capture keys during normal typing.
-const GLOBAL_MODIFIERS: &[&str] = &["ctrl", "control", "alt", "shift", "super", "command", "meta"];
-
-fn shortcut_has_modifier(s: &str) -> bool {
-    s.split('+')
-        .map(|p| p.trim().to_lowercase())
-        .any(|p| GLOBAL_MODIFIERS.contains(&p.as_str()))
-}
-
-/// Registers the toggle-window shortcut and all action shortcuts that have global=true.
-fn register_all_global_shortcuts(app: &AppHandle) -> Result<(), String> {
-    let config = Config::load();
+/// Registers the toggle-window shortcut, per-TV shortcuts and all action shortcuts that have global=true.
+fn register_all_global_shortcuts(app: &AppHandle, config: &Config) -> Result<(), String> {
     let manager = app.global_shortcut();
     manager.unregister_all().map_err(|e| e.to_string())?;
+    // Releases of the old shortcuts won't arrive anymore
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        stop_all_repeats(&state);
+    }
 
     // 1. Toggle-window shortcut (skip if invalid so saving action shortcuts doesn't fail)
     if config.shortcut_enabled && !config.global_shortcut.is_empty() {
-        if shortcut_has_modifier(&config.global_shortcut)
-            && let Ok(shortcut) = config.global_shortcut.parse::<Shortcut>()
+        if shortcut::has_modifier(&config.global_shortcut)
+            && let Ok(shortcut) = shortcut::parse(&config.global_shortcut)
         {
             let app_handle = app.clone();
             if let Err(e) = manager.on_shortcut(shortcut, move |_app, _shortcut, event| {
@@ -695,7 +3538,7 @@ fn register_all_global_shortcuts(app: &AppHandle) -> Result<(), String> {
             }) {
                 log::warn!("Failed to register toggle shortcut: {}", e);
             }
-        } else if !shortcut_has_modifier(&config.global_shortcut) {
+        } else if !shortcut::has_modifier(&config.global_shortcut) {
             log::warn!(
                 "Toggle shortcut '{}' has no modifier; global shortcut not registered",
                 config.global_shortcut
@@ -708,10 +3551,40 @@ fn register_all_global_shortcuts(app: &AppHandle) -> Result<(), String> {
         }
     }
 
-    // 2. Action shortcuts (global hotkeys that run a command)
-    for (action_id, ac) in &config.action_shortcuts {
-        if !ac.global || ac.shortcut.is_empty() || !shortcut_has_modifier(&ac.shortcut) {
-            if ac.global && !ac.shortcut.is_empty() && !shortcut_has_modifier(&ac.shortcut) {
+    // 2. Per-TV shortcuts: open the remote switched to that TV
+    for (tv_name, tv_config) in &config.tvs {
+        let Some(ref sc) = tv_config.toggle_shortcut else {
+            continue;
+        };
+        if sc.is_empty() || !shortcut::has_modifier(sc) {
+            continue;
+        }
+        let shortcut: Shortcut = match shortcut::parse(sc) {
+            Ok(s) => s,
+            Err(e) => {
+                log::warn!("Invalid shortcut '{}' for TV {}: {}", sc, tv_name, e);
+                continue;
+            }
+        };
+        let tv_name_run = tv_name.clone();
+        if let Err(e) = manager.on_shortcut(shortcut, move |app, _shortcut, event| {
+            if event.state != ShortcutState::Released {
+                return;
+            }
+            let app = app.clone();
+            let tv_name = tv_name_run.clone();
+            tauri::async_runtime::spawn(async move {
+                show_window_for_tv(&app, &tv_name).await;
+            });
+        }) {
+            log::warn!("Failed to register shortcut for TV {}: {}", tv_name, e);
+        }
+    }
+
+    // 3. Action shortcuts (global hotkeys that run a command)
+    for (action_id, ac) in config.active_action_shortcuts() {
+        if !ac.global || ac.shortcut.is_empty() || !shortcut::has_modifier(&ac.shortcut) {
+            if ac.global && !ac.shortcut.is_empty() && !shortcut::has_modifier(&ac.shortcut) {
                 log::warn!(
                     "Action shortcut '{}' for {} has no modifier; not registered as global",
                     ac.shortcut, action_id
@@ -719,7 +3592,7 @@ fn register_all_global_shortcuts(app: &AppHandle) -> Result<(), String> {
             }
             continue;
         }
-        let shortcut: Shortcut = match ac.shortcut.parse() {
+        let shortcut: Shortcut = match shortcut::parse(&ac.shortcut) {
             Ok(s) => s,
             Err(e) => {
                 log::warn!("Invalid action shortcut '{}' for {}: {}", ac.shortcut, action_id, e);
@@ -729,18 +3602,39 @@ fn register_all_global_shortcuts(app: &AppHandle) -> Result<(), String> {
         let action_id_emit = action_id.clone();
         let action_id_run = action_id.clone();
         let app_handle = app.clone();
+        let config_repeat = config.shortcut_repeat;
         if let Err(e) = manager.on_shortcut(shortcut, move |app, _shortcut, event| {
+            // Directional actions fire on press and repeat while held
+            if config_repeat.enabled && is_repeatable_action(&action_id_run) {
+                if let Some(state) = app.try_state::<Arc<AppState>>() {
+                    let state = state.inner().clone();
+                    match event.state {
+                        ShortcutState::Pressed => {
+                            start_repeat(app, state, &action_id_run, config_repeat)
+                        }
+                        ShortcutState::Released => stop_repeat(&state, &action_id_run),
+                    }
+                }
+                return;
+            }
             if event.state != ShortcutState::Released {
                 return;
             }
+            // show_<panel> actions open the window on a specific view instead of talking to the TV
+            // (show_tv_keypad is the exception: it opens the keypad on the TV itself)
+            if let Some(panel) = action_id_run.strip_prefix("show_")
+                && action_id_run != "show_tv_keypad"
+            {
+                show_window(&app_handle, Some(panel));
+                return;
+            }
             // Run action in Rust so it works when window is hidden
             if let Some(state) = app.try_state::<Arc<AppState>>() {
                 let state = state.inner().clone();
                 let action_id = action_id_run.clone();
+                let app = app.clone();
                 tauri::async_runtime::spawn(async move {
-                    if let Err(e) = run_action_impl(state, &action_id).await {
-                        log::warn!("Global shortcut action {} failed: {}", action_id, e);
-                    }
+                    let _ = execute_action(&app, state, &action_id, ActionSource::Shortcut).await;
                 });
             }
             // Also emit to frontend so UI can update when window is visible
@@ -755,40 +3649,397 @@ fn register_all_global_shortcuts(app: &AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// Actions that auto-repeat while their global shortcut is held.
+fn is_repeatable_action(action_id: &str) -> bool {
+    matches!(
+        action_id,
+        "up" | "down" | "left" | "right" | "volume_up" | "volume_down"
+    )
+}
+
+/// Run the action now and keep repeating it while the shortcut is held, for at most
+/// `max_hold_ms`. OS key repeat sends more Pressed events; those are ignored while a repeat
+/// task is running.
+fn start_repeat(app: &AppHandle, state: Arc<AppState>, action_id: &str, repeat: ShortcutRepeat) {
+    let mut repeats = state.repeats.lock().unwrap();
+    if repeats.get(action_id).is_some_and(|h| !h.inner().is_finished()) {
+        return;
+    }
+    let app = app.clone();
+    let task_state = state.clone();
+    let id = action_id.to_string();
+    let handle = tauri::async_runtime::spawn(async move {
+        if execute_action(&app, task_state.clone(), &id, ActionSource::Shortcut)
+            .await
+            .is_err()
+        {
+            return;
+        }
+        let give_up = tokio::time::Instant::now()
+            + std::time::Duration::from_millis(repeat.max_hold_ms);
+        tokio::time::sleep(std::time::Duration::from_millis(repeat.delay_ms)).await;
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_millis(repeat.interval_ms.max(30)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            if tokio::time::Instant::now() >= give_up {
+                log::debug!("Repeat of {} stopped after {}ms held", id, repeat.max_hold_ms);
+                return;
+            }
+            task_state.record_action(&id);
+            if let Err(e) = run_action_impl(task_state.clone(), &id).await {
+                log::warn!("Repeat of {} stopped: {}", id, e);
+                return;
+            }
+        }
+    });
+    repeats.insert(action_id.to_string(), handle);
+}
+
+fn stop_repeat(state: &AppState, action_id: &str) {
+    if let Some(handle) = state.repeats.lock().unwrap().remove(action_id) {
+        handle.abort();
+    }
+}
+
+/// Stop every running repeat, for when their key releases may never arrive.
+fn stop_all_repeats(state: &AppState) {
+    for (_, handle) in state.repeats.lock().unwrap().drain() {
+        handle.abort();
+    }
+}
+
+/// Show the remote switched to `tv_name`. If that wasn't the active TV, the current connection
+/// is dropped and "active-tv-changed" tells the frontend to reload config and reconnect.
+async fn show_window_for_tv(app: &AppHandle, tv_name: &str) {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return;
+    };
+    let state = state.inner().clone();
+    let (known, switched) = state
+        .config
+        .read(|config| {
+            (
+                config.tvs.contains_key(tv_name),
+                config.active_tv.as_deref() != Some(tv_name),
+            )
+        })
+        .await;
+    if !known {
+        log::warn!("Shortcut for unknown TV {}", tv_name);
+        return;
+    }
+    if switched {
+        let _ = state
+            .config
+            .update(|config| {
+                config.active_tv = Some(tv_name.to_string());
+                Ok(())
+            })
+            .await;
+    }
+    if switched
+        && let Ok(previous) = state
+            .tv
+            .run(|tv| {
+                Box::pin(async move {
+                    tv.disconnect().await;
+                    tv.name.clone()
+                })
+            })
+            .await
+    {
+        state.record_usage(&previous, UsageChange::Power(PowerState::Unknown));
+    }
+    show_window(app, None);
+    if switched {
+        let _ = app.emit("active-tv-changed", tv_name);
+    }
+}
+
+/// Show and focus the remote. With a panel (e.g. "apps", "inputs", "text_entry", "settings"),
+/// also emits "navigate" so the frontend jumps straight to that view.
+fn show_window(app: &AppHandle, panel: Option<&str>) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        WINDOW_VISIBLE.store(true, Ordering::SeqCst);
+        if let Some(panel) = panel {
+            let _ = window.emit("navigate", panel);
+        }
+    }
+}
+
+// ============ Tray ============
+
+const TRAY_ID: &str = "main";
+
+// Status shown after the app name in the tray tooltip when nothing is busy
+static TRAY_STATUS: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+fn tray_icon() -> Image<'static> {
+    Image::from_bytes(include_bytes!("../icons/icon.png")).expect("Failed to load tray icon")
+}
+
+/// Set the status shown after the app name in the tray tooltip (None resets it).
+fn set_tray_status(app: &AppHandle, status: Option<&str>) {
+    *TRAY_STATUS.lock().unwrap() = status.map(|s| s.to_string());
+    let busy = app
+        .try_state::<Arc<AppState>>()
+        .is_some_and(|state| state.busy.borrow().is_some());
+    if !busy {
+        refresh_tray_tooltip(app);
+    }
+}
+
+fn refresh_tray_tooltip(app: &AppHandle) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let tooltip = match TRAY_STATUS.lock().unwrap().as_deref() {
+            Some(status) => format!("LG TV Remote — {}", status),
+            None => "LG TV Remote".to_string(),
+        };
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+/// While an operation is busy, blink the tray icon and name the operation in the tooltip,
+/// so long operations give feedback even when the window is hidden.
+fn spawn_tray_busy_indicator(app: AppHandle, mut busy: tokio::sync::watch::Receiver<Option<String>>) {
+    tauri::async_runtime::spawn(async move {
+        let normal = tray_icon();
+        let mut rgba = normal.rgba().to_vec();
+        for px in rgba.chunks_exact_mut(4) {
+            px[3] /= 3;
+        }
+        let dimmed = Image::new_owned(rgba, normal.width(), normal.height());
+
+        loop {
+            let operation = busy.borrow_and_update().as_deref().map(str::to_string);
+            if let Some(tray) = app.tray_by_id(TRAY_ID) {
+                match operation {
+                    Some(operation) => {
+                        let _ = tray.set_tooltip(Some(format!("LG TV Remote — {}…", operation)));
+                        let mut dim = false;
+                        loop {
+                            dim = !dim;
+                            let _ = tray.set_icon(Some(if dim { dimmed.clone() } else { normal.clone() }));
+                            tokio::select! {
+                                changed = busy.changed() => {
+                                    if changed.is_err() {
+                                        return;
+                                    }
+                                    break;
+                                }
+                                _ = tokio::time::sleep(std::time::Duration::from_millis(500)) => {}
+                            }
+                        }
+                        continue;
+                    }
+                    None => {
+                        let _ = tray.set_icon(Some(normal.clone()));
+                        refresh_tray_tooltip(&app);
+                    }
+                }
+            }
+            if busy.changed().await.is_err() {
+                return;
+            }
+        }
+    });
+}
+
 // ============ Window Positioning ============
 
 fn position_window_near_tray(window: &WebviewWindow, x: f64, y: f64) {
     let window_size = window.outer_size().unwrap_or_default();
 
-    // Get monitor info
-    if let Some(monitor) = window.current_monitor().ok().flatten() {
+    // Use the monitor under the click: current_monitor() of a hidden window is often the
+    // wrong screen on multi-monitor setups.
+    let monitor = window
+        .monitor_from_point(x, y)
+        .ok()
+        .flatten()
+        .or_else(|| window.current_monitor().ok().flatten())
+        .or_else(|| window.primary_monitor().ok().flatten());
+
+    if let Some(monitor) = monitor {
         let monitor_pos = monitor.position();
         let monitor_size = monitor.size();
+        let (pos_x, pos_y) = popup::position(
+            (x as i32, y as i32),
+            (window_size.width as i32, window_size.height as i32),
+            (monitor_pos.x, monitor_pos.y),
+            (monitor_size.width as i32, monitor_size.height as i32),
+        );
+        let _ = window.set_position(PhysicalPosition::new(pos_x, pos_y));
+    }
+}
 
-        // Calculate position - try to position below/beside the click point
-        let mut pos_x = x as i32 - (window_size.width as i32 / 2);
-        let mut pos_y = y as i32;
-
-        // Keep window on screen
-        let right_edge = monitor_pos.x + monitor_size.width as i32;
-        let bottom_edge = monitor_pos.y + monitor_size.height as i32;
+/// Configured tray left-click behavior; mirrors `Config::tray_left_click` for the sync tray handler.
+static TRAY_LEFT_CLICK: std::sync::Mutex<TrayClickAction> =
+    std::sync::Mutex::new(TrayClickAction::OpenRemote);
 
-        if pos_x + window_size.width as i32 > right_edge {
-            pos_x = right_edge - window_size.width as i32;
+fn on_tray_left_click(app: &AppHandle, x: f64, y: f64) {
+    let action = *TRAY_LEFT_CLICK.lock().unwrap();
+    let action_id = match action {
+        TrayClickAction::OpenRemote => {
+            open_window_at(app, x, y);
+            return;
         }
-        if pos_x < monitor_pos.x {
-            pos_x = monitor_pos.x;
+        TrayClickAction::ToggleWindow => {
+            toggle_window(app, x, y);
+            return;
         }
+        TrayClickAction::None => return,
+        TrayClickAction::Mute => "mute_toggle",
+        TrayClickAction::PowerToggle => "power_toggle",
+    };
+    if let Some(state) = app.try_state::<Arc<AppState>>() {
+        let state = state.inner().clone();
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let _ = execute_action(&app, state, action_id, ActionSource::Tray).await;
+        });
+    }
+}
+
+/// Menu id prefix of configured tray entries; the rest of the id is the action to run.
+const TRAY_ACTION_PREFIX: &str = "action:";
+
+/// "Open Remote", the configured entries in order, then "Quit".
+fn build_tray_menu(
+    app: &AppHandle,
+    items: &[TrayMenuItem],
+) -> tauri::Result<tauri::menu::Menu<tauri::Wry>> {
+    let mut menu = MenuBuilder::new(app).text("show", "Open Remote").separator();
+    for item in items {
+        let Some(action_id) = item.action_id() else {
+            menu = menu.separator();
+            continue;
+        };
+        let id = format!("{}{}", TRAY_ACTION_PREFIX, action_id);
+        // Apps show their icon once it's cached (PNG only)
+        let icon = match item {
+            TrayMenuItem::App { id, .. } => app_icons::read(id)
+                .and_then(|bytes| Image::from_bytes(&bytes).ok())
+                .map(|image| image.to_owned()),
+            _ => None,
+        };
+        menu = match icon {
+            Some(icon) => menu.icon(id, item.label(), icon),
+            None => menu.text(id, item.label()),
+        };
+    }
+    if !items.is_empty() {
+        menu = menu.separator();
+    }
+    menu.text("quit", "Quit").build()
+}
 
-        // If clicking near bottom (tray), show window above
-        if pos_y + window_size.height as i32 > bottom_edge {
-            pos_y = y as i32 - window_size.height as i32;
+fn on_tray_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        "show" => show_window(app, None),
+        "quit" => app.exit(0),
+        _ => {
+            let Some(action_id) = id.strip_prefix(TRAY_ACTION_PREFIX) else {
+                return;
+            };
+            if let Some(state) = app.try_state::<Arc<AppState>>() {
+                let state = state.inner().clone();
+                let app = app.clone();
+                let action_id = action_id.to_string();
+                tauri::async_runtime::spawn(async move {
+                    let _ = execute_action(&app, state, &action_id, ActionSource::Tray).await;
+                });
+            }
         }
-        if pos_y < monitor_pos.y {
-            pos_y = monitor_pos.y;
+    }
+}
+
+/// Replace the tray menu entries; the menu is rebuilt when the config change lands.
+#[tauri::command]
+async fn set_tray_menu(
+    state: tauri::State<'_, Arc<AppState>>,
+    items: Vec<TrayMenuItem>,
+) -> Result<(), AppError> {
+    for item in &items {
+        let id = match item {
+            TrayMenuItem::Action { id, .. }
+            | TrayMenuItem::Input { id, .. }
+            | TrayMenuItem::App { id, .. } => id,
+            TrayMenuItem::Macro { name, .. } => name,
+            TrayMenuItem::Separator => continue,
+        };
+        if id.trim().is_empty() {
+            return Err("Tray menu entries need an id".into());
         }
+    }
+    state
+        .config
+        .update(|config| {
+            config.tray_menu = items;
+            Ok(())
+        })
+        .await
+}
 
-        let _ = window.set_position(PhysicalPosition::new(pos_x, pos_y));
+#[tauri::command]
+async fn get_favorite_actions(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<String>, AppError> {
+    Ok(state.config.read(|c| c.favorite_actions.clone()).await)
+}
+
+/// Replace the quick-bar actions with `actions`, in order. Unknown ids and duplicates are
+/// rejected so a typo doesn't leave a dead button.
+#[tauri::command]
+async fn set_favorite_actions(
+    state: tauri::State<'_, Arc<AppState>>,
+    actions: Vec<String>,
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            let mut seen = std::collections::HashSet::new();
+            for id in &actions {
+                if !actions::is_known(id, config) {
+                    return Err(format!("Unknown action: {}", id).into());
+                }
+                if !seen.insert(id.as_str()) {
+                    return Err(format!("{} is in the list twice", id).into());
+                }
+            }
+            config.favorite_actions = actions;
+            Ok(())
+        })
+        .await
+}
+
+#[tauri::command]
+async fn set_tray_left_click(
+    state: tauri::State<'_, Arc<AppState>>,
+    action: TrayClickAction,
+) -> Result<(), AppError> {
+    state
+        .config
+        .update(|config| {
+            config.tray_left_click = action;
+            Ok(())
+        })
+        .await?;
+    *TRAY_LEFT_CLICK.lock().unwrap() = action;
+    Ok(())
+}
+
+fn open_window_at(app: &AppHandle, x: f64, y: f64) {
+    if let Some(window) = app.get_webview_window("main") {
+        if !WINDOW_VISIBLE.load(Ordering::SeqCst) {
+            position_window_near_tray(&window, x, y);
+        }
+        let _ = window.show();
+        let _ = window.set_focus();
+        WINDOW_VISIBLE.store(true, Ordering::SeqCst);
     }
 }
 
@@ -810,7 +4061,7 @@ fn toggle_window(app: &AppHandle, x: f64, y: f64) {
 // ============ Updater ============
 
 #[tauri::command]
-async fn check_for_updates(app: AppHandle) -> Result<Option<serde_json::Value>, String> {
+async fn check_for_updates(app: AppHandle) -> Result<Option<serde_json::Value>, AppError> {
     let update = app
         .updater()
         .map_err(|e| e.to_string())?
@@ -828,7 +4079,7 @@ async fn check_for_updates(app: AppHandle) -> Result<Option<serde_json::Value>,
 }
 
 #[tauri::command]
-async fn download_and_install_update(app: AppHandle) -> Result<(), String> {
+async fn download_and_install_update(app: AppHandle) -> Result<(), AppError> {
     let update = app
         .updater()
         .map_err(|e| e.to_string())?
@@ -843,7 +4094,7 @@ async fn download_and_install_update(app: AppHandle) -> Result<(), String> {
             .map_err(|e| e.to_string())?;
         app.restart();
     } else {
-        Err("No update available".to_string())
+        Err("No update available".into())
     }
 }
 
@@ -852,14 +4103,34 @@ async fn download_and_install_update(app: AppHandle) -> Result<(), String> {
 fn main() {
     env_logger::init();
 
+    let startup_config = Config::load();
+    i18n::set_locale(startup_config.locale.as_deref());
     let state = Arc::new(AppState {
-        tv: Mutex::new(TvConnection::new()),
-        config: Mutex::new(Config::load()),
+        tv: ConnectionManager::spawn(),
+        config: ConfigStore::new(startup_config.clone()),
+        keepalive: std::sync::Mutex::new(KeepaliveState::default()),
+        busy: tokio::sync::watch::Sender::new(None),
+        recorder: std::sync::Mutex::new(None),
+        reconnector: reconnect::Reconnector::default(),
+        repeats: std::sync::Mutex::new(HashMap::new()),
+        event_subscriptions: std::sync::Mutex::new(EventSubscriptions::default()),
+        usage: UsageHistory::load(startup_config.active_tv.as_deref()),
+        tv_state: StateCache::load(),
+        automations: AutomationLog::load(),
+        mirrored_notifications: std::sync::Mutex::new(std::collections::HashSet::new()),
+        volume: std::sync::Mutex::new(None),
+        parental_check: tokio::sync::Notify::new(),
+        pin_pairing: std::sync::Mutex::new(None),
+        pending_hide: PendingHide::default(),
+        presence: std::sync::Mutex::new(None),
     });
+    state.tv.set_limits(startup_config.command_queue);
+    state.tv.set_timeouts(startup_config.timeouts);
 
     let builder = tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_updater::Builder::new().build());
 
     #[cfg(feature = "autostart")]
@@ -872,12 +4143,25 @@ fn main() {
     #[cfg_attr(not(target_os = "macos"), allow(unused_mut))]
     let mut app = builder
         .manage(state.clone())
-        .setup(|app| {
+        .setup(move |app| {
+            spawn_keepalive_watchdog(state.clone(), app.handle().clone());
+            spawn_auto_reconnect(state.clone(), app.handle().clone());
+            spawn_mdns_tracker(state.clone(), app.handle().clone());
+            spawn_presence_monitor(state.clone(), app.handle().clone());
+            spawn_sleep_watch(state.clone(), app.handle().clone());
+            spawn_parental_enforcer(state.clone(), app.handle().clone());
+            let writer = state.clone();
+            tauri::async_runtime::spawn(async move { writer.tv_state.run_writer().await });
+            let writer = state.clone();
+            tauri::async_runtime::spawn(async move { writer.usage.run_writer().await });
+
             // Hide window on startup - we're a tray app
             if let Some(window) = app.get_webview_window("main") {
                 // Apply saved window size
-                let config = Config::load();
-                if let Some(size) = config.window_size {
+                let config = &startup_config;
+                apply_window_behavior(config.window_behavior.current());
+                *TRAY_LEFT_CLICK.lock().unwrap() = config.tray_left_click;
+                if let Some(size) = config.active_window_size() {
                     let _ = window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
                         width: size.width,
                         height: size.height,
@@ -890,6 +4174,7 @@ fn main() {
                 // Handle window events
                 let window_clone = window.clone();
                 let _app_handle = app.app_handle().clone();
+                let window_state = state.clone();
                 window.on_window_event(move |event| {
                     match event {
                         // On Windows, clicking X sends CloseRequested and destroys the window
@@ -900,48 +4185,62 @@ fn main() {
                             let _ = window_clone.hide();
                             WINDOW_VISIBLE.store(false, Ordering::SeqCst);
                         }
-                        // Hide when focus is lost (e.g. user clicked outside). On Windows, use a
-                        // short delay and cancel if the window gets focus back or Resized/Moved.
+                        // Hide when focus is lost (e.g. user clicked outside). With a hide delay
+                        // (default on Windows), cancel if the window gets focus back or is
+                        // Resized/Moved before it expires.
                         tauri::WindowEvent::Focused(false) => {
-                            #[cfg(target_os = "windows")]
-                            {
-                                CANCEL_PENDING_HIDE.store(false, Ordering::SeqCst);
-                                let w = window_clone.clone();
-                                let a = _app_handle.clone();
-                                std::thread::spawn(move || {
-                                    std::thread::sleep(std::time::Duration::from_millis(200));
-                                    if !CANCEL_PENDING_HIDE.load(Ordering::SeqCst) {
-                                        let _ = a.run_on_main_thread(move || {
-                                            let _ = w.hide();
-                                            WINDOW_VISIBLE.store(false, Ordering::SeqCst);
-                                        });
-                                    }
-                                });
+                            if let Some(state) = _app_handle.try_state::<Arc<AppState>>() {
+                                stop_all_repeats(&state);
                             }
-                            #[cfg(not(target_os = "windows"))]
-                            {
-                                let _ = window_clone.hide();
-                                WINDOW_VISIBLE.store(false, Ordering::SeqCst);
+                            match popup::on_focus_lost(
+                                HIDE_ON_FOCUS_LOSS.load(Ordering::SeqCst),
+                                HIDE_DELAY_MS.load(Ordering::SeqCst),
+                            ) {
+                                FocusLoss::Keep => {}
+                                FocusLoss::HideNow => {
+                                    window_state.pending_hide.cancel();
+                                    let _ = window_clone.hide();
+                                    WINDOW_VISIBLE.store(false, Ordering::SeqCst);
+                                }
+                                FocusLoss::HideAfter(delay) => {
+                                    let app = _app_handle.clone();
+                                    let window = window_clone.clone();
+                                    window_state.pending_hide.schedule(delay, move |ticket| {
+                                        let _ = app.run_on_main_thread(move || {
+                                            if ticket.is_current() {
+                                                let _ = window.hide();
+                                                WINDOW_VISIBLE.store(false, Ordering::SeqCst);
+                                            }
+                                        });
+                                    });
+                                }
                             }
                         }
                         tauri::WindowEvent::Focused(true) => {
-                            #[cfg(target_os = "windows")]
-                            CANCEL_PENDING_HIDE.store(true, Ordering::SeqCst);
+                            window_state.pending_hide.cancel();
                         }
                         tauri::WindowEvent::Resized(size) => {
-                            #[cfg(target_os = "windows")]
-                            CANCEL_PENDING_HIDE.store(true, Ordering::SeqCst);
+                            window_state.pending_hide.cancel();
                             // Save inner size so set_size(saved) reproduces the same outer size
                             if size.width > 0 && size.height > 0 {
                                 let (w, h) = outer_to_inner_size(size.width, size.height);
-                                let mut config = Config::load();
-                                config.window_size = Some(WindowSize { width: w, height: h });
-                                let _ = config.save();
+                                let state = window_state.clone();
+                                // Resizing fires many events a second; save once it stops
+                                tauri::async_runtime::spawn(async move {
+                                    state
+                                        .config
+                                        .update_deferred(|config| {
+                                            config.set_active_window_size(Some(WindowSize {
+                                                width: w,
+                                                height: h,
+                                            }));
+                                        })
+                                        .await;
+                                });
                             }
                         }
                         tauri::WindowEvent::Moved(_) => {
-                            #[cfg(target_os = "windows")]
-                            CANCEL_PENDING_HIDE.store(true, Ordering::SeqCst);
+                            window_state.pending_hide.cancel();
                         }
                         _ => {}
                     }
@@ -949,19 +4248,12 @@ fn main() {
             }
 
             // Build tray menu (required for KDE/SNI to show the icon)
-            let show = MenuItemBuilder::with_id("show", "Open Remote").build(app)?;
-            let quit = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
-            let menu = MenuBuilder::new(app)
-                .item(&show)
-                .separator()
-                .item(&quit)
-                .build()?;
+            let menu = build_tray_menu(app.handle(), &startup_config.tray_menu)?;
 
             // Create tray icon
-            let icon = Image::from_bytes(include_bytes!("../icons/icon.png"))
-                .expect("Failed to load tray icon");
+            let icon = tray_icon();
 
-            let _tray = TrayIconBuilder::new()
+            let _tray = TrayIconBuilder::with_id(TRAY_ID)
                 .icon(icon)
                 .menu(&menu)
                 .tooltip("LG TV Remote")
@@ -974,25 +4266,54 @@ fn main() {
                         ..
                     } = event
                     {
-                        toggle_window(tray.app_handle(), position.x, position.y);
+                        on_tray_left_click(tray.app_handle(), position.x, position.y);
                     }
                 })
-                .on_menu_event(|app, event| {
-                    match event.id().as_ref() {
-                        "show" => {
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                                WINDOW_VISIBLE.store(true, Ordering::SeqCst);
+                .on_menu_event(|app, event| on_tray_menu_event(app, event.id().as_ref()))
+                .build(app)?;
+            spawn_tray_busy_indicator(app.handle().clone(), state.busy.subscribe());
+
+            // Let the frontend know when settings change outside its own edits, rebuild the
+            // tray menu when its entries change, apply new command queue limits and timeouts,
+            // and switch to the new active TV's profile
+            let mut config_changes = state.config.subscribe();
+            let handle = app.handle().clone();
+            let config_state = state.clone();
+            let mut tray_menu = startup_config.tray_menu.clone();
+            let mut active_tv = startup_config.get_active_tv().map(|(name, _)| name.clone());
+            tauri::async_runtime::spawn(async move {
+                while config_changes.changed().await.is_ok() {
+                    let generation = *config_changes.borrow_and_update();
+                    let _ = handle.emit("config-changed", generation);
+                    let (items, queue, timeouts, active) = config_state
+                        .config
+                        .read(|c| {
+                            let active = c.get_active_tv().map(|(name, _)| name.clone());
+                            (c.tray_menu.clone(), c.command_queue, c.timeouts, active)
+                        })
+                        .await;
+                    config_state.tv.set_limits(queue);
+                    config_state.tv.set_timeouts(timeouts);
+                    if active != active_tv {
+                        apply_active_profile(&handle, &config_state).await;
+                        active_tv = active;
+                    }
+                    if items == tray_menu {
+                        continue;
+                    }
+                    match build_tray_menu(&handle, &items) {
+                        Ok(menu) => {
+                            if let Some(tray) = handle.tray_by_id(TRAY_ID) {
+                                let _ = tray.set_menu(Some(menu));
                             }
                         }
-                        "quit" => app.exit(0),
-                        _ => {}
+                        Err(e) => log::warn!("Failed to rebuild tray menu: {}", e),
                     }
-                })
-                .build(app)?;
+                    tray_menu = items;
+                }
+            });
 
-            if let Err(e) = register_all_global_shortcuts(app.app_handle()) {
+            if let Err(e) = register_all_global_shortcuts(app.app_handle(), &startup_config) {
                 log::warn!("Failed to register global shortcuts: {}", e);
             }
 
@@ -1029,30 +4350,112 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             get_app_version,
             is_dev,
+            run_self_test,
             get_window_size,
             get_config,
             save_tv,
+            remove_tv,
             set_active_tv,
             connect,
+            discover_tvs,
             authenticate,
             disconnect,
             get_status,
+            get_power_state,
+            get_tv_presence,
+            measure_latency,
+            diagnose_connection,
+            start_trace,
+            stop_trace,
+            export_trace,
+            export_config,
+            import_config,
+            import_pairing_keys,
+            get_system_info,
+            send_raw_ssap,
+            get_window_behavior,
+            run_action,
+            launch_app_with_params,
+            start_pixel_cleaning,
+            list_bluetooth_devices,
+            connect_bluetooth_device,
+            disconnect_bluetooth_device,
+            get_action_feedback,
+            set_action_feedback,
+            start_macro_recording,
+            stop_macro_recording,
+            get_macros,
+            delete_macro,
+            set_tray_left_click,
+            set_tray_menu,
+            get_favorite_actions,
+            set_favorite_actions,
+            button_down,
+            button_up,
+            long_press_button,
+            set_auto_reconnect,
+            get_timeouts,
+            set_timeouts,
+            get_wol_config,
+            set_wol_config,
+            get_current_channel,
+            get_api_schema,
+            apply_scene,
+            get_scenes,
+            save_scene,
+            check_tv_update,
+            start_tv_update,
+            get_button_aliases,
+            set_button_alias,
+            launch_webapp,
+            subscribe_event,
+            unsubscribe_event,
+            get_usage_stats,
+            get_energy_estimate,
+            get_volume_status,
+            list_inputs,
+            list_apps,
+            get_app_icon,
+            switch_input,
+            set_input_label,
+            get_parental_schedule,
+            set_parental_schedule,
+            get_input_capabilities,
+            submit_pin,
+            reset_cert_pin,
+            forget_pairing,
+            set_locale,
+            get_last_tv_state,
+            get_tv_notifications,
+            get_automation_log,
+            get_sound_mode,
+            set_sound_mode,
+            set_window_behavior,
             send_button,
+            send_scroll,
             volume_up,
             volume_down,
             set_mute,
             power_off,
             power_on,
+            screen_off,
+            screen_on,
+            set_energy_saving,
             fetch_mac,
             set_mac,
             wake_streaming_device,
             set_streaming_device,
+            select_streaming_device,
             set_wake_streaming_on_power_on,
             quit_app,
             get_shortcut_settings,
             set_shortcut,
+            set_tv_shortcut,
+            set_tv_wol_interface,
             get_action_shortcuts,
             set_action_shortcuts,
+            get_tv_profile,
+            set_tv_profile,
             reset_window_size,
             get_autostart_enabled,
             set_autostart_enabled,
@@ -1067,5 +4470,28 @@ fn main() {
     #[cfg(target_os = "macos")]
     app.set_activation_policy(tauri::ActivationPolicy::Accessory);
 
-    app.run(|_, _| {});
+    app.run(|app, event| {
+        // Close both sockets so the TV doesn't keep a half-open session around and refuse
+        // the next connect for a while, and write settings still waiting to be saved
+        if let tauri::RunEvent::Exit = event
+            && let Some(state) = app.try_state::<Arc<AppState>>()
+        {
+            let state = state.inner().clone();
+            let close = state
+                .tv
+                .run(|tv| Box::pin(async move { tv.disconnect().await }));
+            let closed = tauri::async_runtime::block_on(tokio::time::timeout(
+                std::time::Duration::from_secs(2),
+                close,
+            ));
+            if closed.is_err() {
+                log::warn!("Timed out closing the TV session on exit");
+            }
+            if let Err(e) = tauri::async_runtime::block_on(state.config.flush()) {
+                log::warn!("Could not save config on exit: {}", e);
+            }
+            state.usage.record_gap_all();
+            state.usage.flush();
+        }
+    });
 }
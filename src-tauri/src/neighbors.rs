@@ -0,0 +1,131 @@
+//! Finding a TV by its MAC address in the OS neighbor (ARP) table. After a router reboot the
+//! TV may come back with another DHCP lease; its MAC, saved for Wake-on-LAN, still identifies
+//! it. If the table doesn't list it, touching every address of the old /24 makes the OS ARP
+//! for them, which fills the table without depending on the TV answering on any port.
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+/// Sweeps send a packet to every host of the /24; don't repeat one on every reconnect attempt.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(600);
+const SWEEP_CONNECT_TIMEOUT: Duration = Duration::from_millis(400);
+
+static LAST_SWEEP: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Current address of the device with `mac`, looking in the neighbor table and, at most every
+/// `SWEEP_INTERVAL`, after sweeping the /24 of `last_ip` (the address it used to have).
+pub async fn find_by_mac(mac: &str, last_ip: &str) -> Option<IpAddr> {
+    let mac = normalize_mac(mac)?;
+    if let Some(ip) = lookup(&mac).await {
+        return Some(ip);
+    }
+    let IpAddr::V4(last_ip) = last_ip.trim().parse::<IpAddr>().ok()? else {
+        return None;
+    };
+    {
+        let mut last_sweep = LAST_SWEEP.lock().unwrap();
+        if last_sweep.is_some_and(|at| at.elapsed() < SWEEP_INTERVAL) {
+            return None;
+        }
+        *last_sweep = Some(Instant::now());
+    }
+    log::debug!("Sweeping {}/24 for {}", last_ip, mac);
+    sweep(last_ip).await;
+    lookup(&mac).await
+}
+
+/// Connect to port 9 of every address in the /24; whether anything answers doesn't matter,
+/// the OS resolves each address first.
+async fn sweep(around: Ipv4Addr) {
+    let [a, b, c, _] = around.octets();
+    let mut tasks = tokio::task::JoinSet::new();
+    for host in 1..=254 {
+        let ip = Ipv4Addr::new(a, b, c, host);
+        tasks.spawn(async move {
+            let _ = tokio::time::timeout(SWEEP_CONNECT_TIMEOUT, TcpStream::connect((ip, 9))).await;
+        });
+    }
+    while tasks.join_next().await.is_some() {}
+}
+
+async fn lookup(mac: &str) -> Option<IpAddr> {
+    let table = tokio::task::spawn_blocking(read_table).await.ok()?;
+    table
+        .into_iter()
+        .find(|(_, entry)| entry == mac)
+        .map(|(ip, _)| ip)
+}
+
+/// "AA-BB-C-..." / "aa:bb:0c:..." -> "aa:bb:0c:...", or None if it isn't a MAC. macOS prints
+/// octets without leading zeros.
+fn normalize_mac(mac: &str) -> Option<String> {
+    let octets: Vec<u8> = mac
+        .trim()
+        .split([':', '-'])
+        .map(|octet| u8::from_str_radix(octet, 16).ok())
+        .collect::<Option<_>>()?;
+    if octets.len() != 6 || octets.iter().all(|&o| o == 0) {
+        return None;
+    }
+    Some(
+        octets
+            .iter()
+            .map(|o| format!("{:02x}", o))
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
+}
+
+/// (address, normalized MAC) pairs of the neighbor table.
+#[cfg(target_os = "linux")]
+fn read_table() -> Vec<(IpAddr, String)> {
+    // IP address  HW type  Flags  HW address  Mask  Device
+    let Ok(table) = std::fs::read_to_string("/proc/net/arp") else {
+        return Vec::new();
+    };
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let ip = fields.first()?.parse().ok()?;
+            let mac = normalize_mac(fields.get(3)?)?;
+            Some((ip, mac))
+        })
+        .collect()
+}
+
+/// (address, normalized MAC) pairs of the neighbor table, from `arp -a`: "? (10.0.0.5) at
+/// a:b:c:d:e:f on en0" on macOS, "  10.0.0.5   aa-bb-cc-dd-ee-ff   dynamic" on Windows.
+#[cfg(not(target_os = "linux"))]
+fn read_table() -> Vec<(IpAddr, String)> {
+    let mut command = std::process::Command::new("arp");
+    command.arg("-a");
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let Ok(output) = command.output() else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut ip = None;
+            let mut mac = None;
+            for word in line.split_whitespace() {
+                let word = word.trim_matches(|c| c == '(' || c == ')');
+                if ip.is_none() {
+                    ip = word.parse::<IpAddr>().ok();
+                } else if mac.is_none() {
+                    mac = normalize_mac(word);
+                }
+            }
+            Some((ip?, mac?))
+        })
+        .collect()
+}
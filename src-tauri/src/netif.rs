@@ -0,0 +1,54 @@
+//! Choosing the local network interface for traffic to the TV. On machines with several
+//! interfaces up (Ethernet and Wi-Fi, a VPN), a socket bound to 0.0.0.0 leaves the choice to
+//! whichever interface the OS prefers for broadcasts, so Wake-on-LAN could go out on the wrong
+//! network. Asking the routing table which local address reaches the TV picks the interface on
+//! the TV's subnet. When that still picks the wrong one (VPN routes, Docker bridges), a TV can
+//! name the interface or local address to use instead.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+
+/// Local address the OS would use to reach `target` (an IP or hostname). Connecting a UDP
+/// socket only consults the routing table; nothing is sent.
+pub fn local_addr_for(target: &str) -> Option<IpAddr> {
+    let remote: SocketAddr = (target.trim(), 9).to_socket_addrs().ok()?.next()?;
+    let unspecified: IpAddr = match remote {
+        SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((unspecified, 0)).ok()?;
+    socket.connect(remote).ok()?;
+    let local = socket.local_addr().ok()?.ip();
+    (!local.is_unspecified()).then_some(local)
+}
+
+/// Broadcast address of the /24 holding both `local` and `target`, if they share one. Most
+/// home networks are /24; on others this is None and the configured broadcast IP applies.
+pub fn shared_subnet_broadcast(local: IpAddr, target: &str) -> Option<Ipv4Addr> {
+    let (IpAddr::V4(local), Ok(IpAddr::V4(target))) = (local, target.trim().parse::<IpAddr>())
+    else {
+        return None;
+    };
+    let [a, b, c, _] = local.octets();
+    (target.octets()[..3] == [a, b, c]).then_some(Ipv4Addr::new(a, b, c, 255))
+}
+
+/// Local address for a configured WoL interface: an address of this machine, or the name of a
+/// network interface (e.g. "eth0", "en0"), which uses its IPv4 address when it has one.
+pub fn source_addr(interface: &str) -> Result<IpAddr, String> {
+    let interface = interface.trim();
+    let interfaces = if_addrs::get_if_addrs()
+        .map_err(|e| format!("Could not list network interfaces: {}", e))?;
+    if let Ok(ip) = interface.parse::<IpAddr>() {
+        return if interfaces.iter().any(|i| i.ip() == ip) {
+            Ok(ip)
+        } else {
+            Err(format!("{} is not an address of this machine", ip))
+        };
+    }
+    interfaces
+        .iter()
+        .filter(|i| i.name == interface)
+        .map(|i| i.ip())
+        .min_by_key(|ip| !ip.is_ipv4())
+        .ok_or_else(|| format!("No network interface named {}", interface))
+}
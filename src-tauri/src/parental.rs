@@ -0,0 +1,58 @@
+//! Allowed-hours check for parental schedule enforcement.
+
+use crate::config::{AllowedWindow, ParentalConfig};
+use chrono::{DateTime, Datelike, Local, NaiveTime, Weekday};
+
+fn parse_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time {} (use HH:MM)", s))
+}
+
+fn parse_day(s: &str) -> Result<Weekday, String> {
+    s.trim()
+        .parse::<Weekday>()
+        .map_err(|_| format!("Invalid day {} (use mon, tue, ...)", s))
+}
+
+pub fn validate(config: &ParentalConfig) -> Result<(), String> {
+    for window in &config.allowed {
+        parse_time(&window.start)?;
+        parse_time(&window.end)?;
+        for day in &window.days {
+            parse_day(day)?;
+        }
+    }
+    if config.enabled && config.allowed.is_empty() {
+        return Err("Add at least one allowed time window".to_string());
+    }
+    Ok(())
+}
+
+fn window_allows(window: &AllowedWindow, now: DateTime<Local>) -> bool {
+    let (Ok(start), Ok(end)) = (parse_time(&window.start), parse_time(&window.end)) else {
+        return false;
+    };
+    let time = now.time();
+    let day_matches = |day: Weekday| {
+        window.days.is_empty()
+            || window
+                .days
+                .iter()
+                .any(|d| parse_day(d).is_ok_and(|d| d == day))
+    };
+    if start <= end {
+        day_matches(now.weekday()) && time >= start && time < end
+    } else {
+        // Past midnight: the evening part belongs to today, the early part to yesterday
+        (time >= start && day_matches(now.weekday()))
+            || (time < end && day_matches(now.weekday().pred()))
+    }
+}
+
+/// True when the TV may be on now. Enforcement that is off, or has no windows configured,
+/// allows everything.
+pub fn is_allowed(config: &ParentalConfig, now: DateTime<Local>) -> bool {
+    !config.enabled
+        || config.allowed.is_empty()
+        || config.allowed.iter().any(|w| window_allows(w, now))
+}
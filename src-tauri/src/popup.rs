@@ -0,0 +1,277 @@
+//! The tray popup window: where it opens next to the tray icon, and when it hides after
+//! losing focus. Kept free of window handles so the placement and the hide sequencing can be
+//! tested on their own; main.rs applies the results to the real window.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Top-left position for a popup of `size` next to a tray click at `click`, kept inside the
+/// monitor at `monitor_pos` with `monitor_size`. Opens above the click when it would run off
+/// the bottom (bottom panel/taskbar). All values are physical pixels.
+pub fn position(
+    click: (i32, i32),
+    size: (i32, i32),
+    monitor_pos: (i32, i32),
+    monitor_size: (i32, i32),
+) -> (i32, i32) {
+    let (x, y) = click;
+    let (width, height) = size;
+
+    // Calculate position - try to position below/beside the click point
+    let mut pos_x = x - width / 2;
+    let mut pos_y = y;
+
+    // Keep window on screen
+    let right_edge = monitor_pos.0 + monitor_size.0;
+    let bottom_edge = monitor_pos.1 + monitor_size.1;
+
+    if pos_x + width > right_edge {
+        pos_x = right_edge - width;
+    }
+    if pos_x < monitor_pos.0 {
+        pos_x = monitor_pos.0;
+    }
+
+    // If clicking near bottom (tray), show window above
+    if pos_y + height > bottom_edge {
+        pos_y = y - height;
+    }
+    if pos_y < monitor_pos.1 {
+        pos_y = monitor_pos.1;
+    }
+
+    (pos_x, pos_y)
+}
+
+/// What to do when the popup loses focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusLoss {
+    Keep,
+    HideNow,
+    /// Hide unless focus comes back (or the window is resized/moved) within the delay.
+    HideAfter(Duration),
+}
+
+/// The platform's window behavior from config, applied to a focus loss.
+pub fn on_focus_lost(hide_on_focus_loss: bool, hide_delay_ms: u64) -> FocusLoss {
+    if !hide_on_focus_loss {
+        FocusLoss::Keep
+    } else if hide_delay_ms == 0 {
+        FocusLoss::HideNow
+    } else {
+        FocusLoss::HideAfter(Duration::from_millis(hide_delay_ms))
+    }
+}
+
+/// The delayed hide scheduled on focus loss (with a hide delay, the default on Windows). At
+/// most one is pending: scheduling replaces it, and focus coming back, a resize or a move
+/// cancels it.
+#[derive(Default)]
+pub struct PendingHide {
+    task: std::sync::Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    /// Bumped by every schedule/cancel, so a hide that was already on its way to the main
+    /// thread can tell it was overtaken.
+    generation: Arc<AtomicU64>,
+}
+
+/// Handed to the hide once its delay is up. The hide runs on the main thread, like the window
+/// events that cancel it, and must only go ahead if `is_current` still holds there.
+pub struct HideTicket {
+    current: Arc<AtomicU64>,
+    generation: u64,
+}
+
+impl HideTicket {
+    /// True if nothing was scheduled or cancelled since this hide was scheduled.
+    pub fn is_current(&self) -> bool {
+        self.current.load(Ordering::SeqCst) == self.generation
+    }
+}
+
+impl PendingHide {
+    /// Call `hide` after `delay`, replacing any hide still pending.
+    pub fn schedule<F>(&self, delay: Duration, hide: F)
+    where
+        F: FnOnce(HideTicket) + Send + 'static,
+    {
+        let ticket = HideTicket {
+            generation: self.generation.fetch_add(1, Ordering::SeqCst) + 1,
+            current: self.generation.clone(),
+        };
+        let task = tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(delay).await;
+            hide(ticket);
+        });
+        if let Some(old) = self.task.lock().unwrap().replace(task) {
+            old.abort();
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        if let Some(task) = self.task.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::sync::mpsc;
+
+    const SIZE: (i32, i32) = (400, 600);
+    const ORIGIN: (i32, i32) = (0, 0);
+    const SCREEN: (i32, i32) = (1920, 1080);
+
+    #[test]
+    fn opens_centered_below_a_top_tray_click() {
+        assert_eq!(position((1000, 30), SIZE, ORIGIN, SCREEN), (800, 30));
+    }
+
+    #[test]
+    fn opens_above_a_bottom_tray_click() {
+        assert_eq!(position((1000, 1060), SIZE, ORIGIN, SCREEN), (800, 460));
+    }
+
+    #[test]
+    fn clamps_at_the_right_edge() {
+        assert_eq!(position((1910, 30), SIZE, ORIGIN, SCREEN), (1520, 30));
+        assert_eq!(position((1920, 1080), SIZE, ORIGIN, SCREEN), (1520, 480));
+        // Click reported past the edge (e.g. a panel on the screen border)
+        assert_eq!(position((2000, 30), SIZE, ORIGIN, SCREEN), (1520, 30));
+    }
+
+    #[test]
+    fn clamps_at_the_left_edge() {
+        assert_eq!(position((10, 30), SIZE, ORIGIN, SCREEN), (0, 30));
+        assert_eq!(position((0, 1080), SIZE, ORIGIN, SCREEN), (0, 480));
+        assert_eq!(position((-50, 30), SIZE, ORIGIN, SCREEN), (0, 30));
+    }
+
+    #[test]
+    fn clamps_at_the_top_edge() {
+        assert_eq!(position((1000, -20), SIZE, ORIGIN, SCREEN), (800, 0));
+        // Too tall to fit above the click either
+        assert_eq!(position((1000, 700), (400, 900), ORIGIN, SCREEN), (800, 0));
+    }
+
+    #[test]
+    fn opens_above_a_click_past_the_bottom_edge() {
+        assert_eq!(position((1000, 1200), SIZE, ORIGIN, SCREEN), (800, 600));
+    }
+
+    #[test]
+    fn stays_on_a_monitor_left_of_the_primary() {
+        let origin = (-1920, 0);
+        assert_eq!(position((-10, 1060), SIZE, origin, SCREEN), (-400, 460));
+        assert_eq!(position((-1915, 30), SIZE, origin, SCREEN), (-1920, 30));
+    }
+
+    #[test]
+    fn stays_on_a_monitor_below_and_right_of_the_primary() {
+        let origin = (1920, 1080);
+        assert_eq!(
+            position((3830, 2150), SIZE, origin, (1920, 1080)),
+            (3440, 1550)
+        );
+        assert_eq!(
+            position((1925, 1085), SIZE, origin, (1920, 1080)),
+            (1920, 1085)
+        );
+    }
+
+    #[test]
+    fn popup_larger_than_the_monitor_starts_at_its_origin() {
+        assert_eq!(
+            position((500, 300), (2500, 1500), (0, 0), (1920, 1080)),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn focus_loss_follows_the_window_behavior() {
+        assert_eq!(on_focus_lost(false, 0), FocusLoss::Keep);
+        assert_eq!(on_focus_lost(false, 300), FocusLoss::Keep);
+        assert_eq!(on_focus_lost(true, 0), FocusLoss::HideNow);
+        assert_eq!(
+            on_focus_lost(true, 300),
+            FocusLoss::HideAfter(Duration::from_millis(300))
+        );
+    }
+
+    /// Schedules a hide that counts itself if its ticket is still current when it runs.
+    fn schedule_counted(pending: &PendingHide, delay_ms: u64, hides: &Arc<AtomicUsize>) {
+        let hides = hides.clone();
+        pending.schedule(Duration::from_millis(delay_ms), move |ticket| {
+            if ticket.is_current() {
+                hides.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn focus_loss_hides_after_the_delay() {
+        let pending = PendingHide::default();
+        let hides = Arc::new(AtomicUsize::new(0));
+        schedule_counted(&pending, 20, &hides);
+        assert_eq!(hides.load(Ordering::SeqCst), 0);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(hides.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn focus_regained_cancels_the_hide() {
+        let pending = PendingHide::default();
+        let hides = Arc::new(AtomicUsize::new(0));
+        schedule_counted(&pending, 50, &hides);
+        pending.cancel();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(hides.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn flapping_focus_leaves_one_hide() {
+        let pending = PendingHide::default();
+        let hides = Arc::new(AtomicUsize::new(0));
+        for _ in 0..5 {
+            schedule_counted(&pending, 30, &hides);
+            pending.cancel();
+            schedule_counted(&pending, 30, &hides);
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(hides.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn focus_regained_before_the_hide_reaches_the_main_thread() {
+        let pending = PendingHide::default();
+        let (tickets, mut fired) = mpsc::unbounded_channel();
+        pending.schedule(Duration::ZERO, move |ticket| {
+            let _ = tickets.send(ticket);
+        });
+        let ticket = fired.recv().await.unwrap();
+        assert!(ticket.is_current());
+        pending.cancel();
+        assert!(!ticket.is_current());
+    }
+
+    #[tokio::test]
+    async fn rescheduling_overtakes_a_fired_hide() {
+        let pending = PendingHide::default();
+        let (tickets, mut fired) = mpsc::unbounded_channel();
+        let first = tickets.clone();
+        pending.schedule(Duration::ZERO, move |ticket| {
+            let _ = first.send(ticket);
+        });
+        let stale = fired.recv().await.unwrap();
+        pending.schedule(Duration::ZERO, move |ticket| {
+            let _ = tickets.send(ticket);
+        });
+        let latest = fired.recv().await.unwrap();
+        assert!(!stale.is_current());
+        assert!(latest.is_current());
+    }
+}
@@ -0,0 +1,31 @@
+//! Whether the active TV is on while the app has no session with it. A periodic probe of its
+//! SSAP port tells "on but not connected" from "off" before pairing or connecting, for the
+//! tray tooltip and the window's status line.
+
+use serde::Serialize;
+use std::time::Duration;
+
+/// How often the TV is probed while not connected.
+pub const INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Presence {
+    /// A session is open; its own power tracking applies.
+    Connected,
+    /// The TV answers on its SSAP port (on, or in Quick Start+ standby).
+    On,
+    /// Nothing answers.
+    Off,
+}
+
+impl Presence {
+    /// Tray tooltip status; None while connected, where the session sets the status.
+    pub fn tray_status(self) -> Option<&'static str> {
+        match self {
+            Presence::Connected => None,
+            Presence::On => Some("TV on, not connected"),
+            Presence::Off => Some("TV off"),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Retry schedule for background auto-reconnect. Retries start fast and back off, so a TV
+//! that's off for the night isn't probed every few seconds until morning; a user action or
+//! Wake-on-LAN resets the schedule.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+const FIRST_RETRY: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(120);
+/// After this many failures in a row, only retry at `SLOW_RETRY`.
+const SLOW_AFTER_FAILURES: u32 = 10;
+const SLOW_RETRY: Duration = Duration::from_secs(300);
+
+/// Delay before the next attempt after `failures` consecutive failures.
+pub fn retry_delay(failures: u32) -> Duration {
+    if failures >= SLOW_AFTER_FAILURES {
+        return SLOW_RETRY;
+    }
+    let doublings = failures.saturating_sub(1).min(8);
+    (FIRST_RETRY * 2u32.pow(doublings)).min(MAX_BACKOFF)
+}
+
+#[derive(Default)]
+pub struct Reconnector {
+    failures: AtomicU32,
+    kick: Notify,
+}
+
+impl Reconnector {
+    /// Back to fast retries and wake the reconnect loop now (user action, Wake-on-LAN).
+    pub fn kick(&self) {
+        self.failures.store(0, Ordering::SeqCst);
+        self.kick.notify_one();
+    }
+
+    pub fn record_success(&self) {
+        self.failures.store(0, Ordering::SeqCst);
+    }
+
+    /// Count a failed attempt and return how long to wait before the next one.
+    pub fn record_failure(&self) -> Duration {
+        let failures = self.failures.fetch_add(1, Ordering::SeqCst) + 1;
+        retry_delay(failures)
+    }
+
+    /// Sleep for `delay`, or less if kicked.
+    pub async fn wait(&self, delay: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = self.kick.notified() => {}
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::config::{Config, StreamingDeviceConfig};
+use serde::Serialize;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    pub fn new(name: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Result of `run_self_test`, rendered by the settings page's troubleshoot button.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    /// False if any check failed (warnings don't count).
+    pub ok: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+/// Run all checks against the in-memory config (and the file on disk for the parse check).
+pub async fn run(config: &Config) -> SelfTestReport {
+    let mut checks = vec![check_config_file(), check_shortcuts(config)];
+    checks.push(check_active_tv(config).await);
+    checks.push(check_interface(config));
+    checks.push(check_wol_socket());
+    checks.push(check_adb(config).await);
+    checks.push(check_dbus());
+
+    let ok = checks.iter().all(|c| c.status != CheckStatus::Fail);
+    SelfTestReport { ok, checks }
+}
+
+fn check_config_file() -> CheckResult {
+    const NAME: &str = "config";
+    let path = Config::config_path();
+    if !path.exists() {
+        return CheckResult::new(
+            NAME,
+            CheckStatus::Pass,
+            "No config file yet; using defaults",
+        );
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => match serde_json::from_str::<Config>(&contents) {
+            Ok(_) => CheckResult::new(NAME, CheckStatus::Pass, path.display().to_string()),
+            Err(e) => CheckResult::new(
+                NAME,
+                CheckStatus::Fail,
+                format!(
+                    "{} does not parse ({}); defaults are being used",
+                    path.display(),
+                    e
+                ),
+            ),
+        },
+        Err(e) => CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            format!("Could not read {}: {}", path.display(), e),
+        ),
+    }
+}
+
+fn check_shortcuts(config: &Config) -> CheckResult {
+    const NAME: &str = "shortcuts";
+    let mut problems = Vec::new();
+    if config.shortcut_enabled && !config.global_shortcut.is_empty() {
+        if let Err(e) = crate::shortcut::parse(&config.global_shortcut) {
+            problems.push(format!("toggle '{}': {}", config.global_shortcut, e));
+        } else if !crate::shortcut::has_modifier(&config.global_shortcut) {
+            problems.push(format!("toggle '{}': no modifier", config.global_shortcut));
+        }
+    }
+    for (id, ac) in config.active_action_shortcuts() {
+        if !ac.global || ac.shortcut.trim().is_empty() {
+            continue;
+        }
+        if let Err(e) = crate::shortcut::parse(&ac.shortcut) {
+            problems.push(format!("{} '{}': {}", id, ac.shortcut, e));
+        } else if !crate::shortcut::has_modifier(&ac.shortcut) {
+            problems.push(format!("{} '{}': no modifier", id, ac.shortcut));
+        }
+    }
+    if problems.is_empty() {
+        CheckResult::new(NAME, CheckStatus::Pass, "All global shortcuts are valid")
+    } else {
+        problems.sort();
+        CheckResult::new(NAME, CheckStatus::Warn, problems.join("; "))
+    }
+}
+
+/// Resolve the active TV and try a TCP connection to its SSAP port. A successful connect
+/// also proves the TV answers ARP on the local network.
+async fn check_active_tv(config: &Config) -> CheckResult {
+    const NAME: &str = "active_tv";
+    let Some((name, tv)) = config.get_active_tv() else {
+        return CheckResult::new(NAME, CheckStatus::Skipped, "No TV configured");
+    };
+    let port = if tv.use_ssl { 3001 } else { 3000 };
+    let addr = match tokio::net::lookup_host((tv.ip.as_str(), port)).await {
+        Ok(mut addrs) => match addrs.next() {
+            Some(addr) => addr,
+            None => {
+                return CheckResult::new(
+                    NAME,
+                    CheckStatus::Fail,
+                    format!("{} ({}) did not resolve", name, tv.ip),
+                );
+            }
+        },
+        Err(e) => {
+            return CheckResult::new(
+                NAME,
+                CheckStatus::Fail,
+                format!("{} ({}) did not resolve: {}", name, tv.ip, e),
+            );
+        }
+    };
+    match tokio::time::timeout(Duration::from_secs(2), tokio::net::TcpStream::connect(addr)).await {
+        Ok(Ok(_)) => CheckResult::new(
+            NAME,
+            CheckStatus::Pass,
+            format!("{} reachable at {}", name, addr),
+        ),
+        Ok(Err(e)) => CheckResult::new(
+            NAME,
+            CheckStatus::Warn,
+            format!(
+                "{} at {} refused the connection ({}); is the TV on?",
+                name, addr, e
+            ),
+        ),
+        Err(_) => CheckResult::new(
+            NAME,
+            CheckStatus::Warn,
+            format!(
+                "{} at {} did not answer; the TV may be off or on another network",
+                name, addr
+            ),
+        ),
+    }
+}
+
+/// Which local address (and so which interface) traffic to the active TV leaves from.
+fn check_interface(config: &Config) -> CheckResult {
+    const NAME: &str = "network_interface";
+    let Some((_, tv)) = config.get_active_tv() else {
+        return CheckResult::new(NAME, CheckStatus::Skipped, "No TV configured");
+    };
+    match crate::netif::local_addr_for(&tv.ip) {
+        Some(local) => CheckResult::new(
+            NAME,
+            CheckStatus::Pass,
+            format!("Reaching {} from local address {}", tv.ip, local),
+        ),
+        None => CheckResult::new(
+            NAME,
+            CheckStatus::Warn,
+            format!("No route to {}; Wake-on-LAN uses the default interface", tv.ip),
+        ),
+    }
+}
+
+fn check_wol_socket() -> CheckResult {
+    const NAME: &str = "wake_on_lan";
+    match std::net::UdpSocket::bind("0.0.0.0:0").and_then(|s| s.set_broadcast(true)) {
+        Ok(()) => CheckResult::new(NAME, CheckStatus::Pass, "Broadcast UDP socket available"),
+        Err(e) => CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            format!("Cannot open a broadcast UDP socket: {}", e),
+        ),
+    }
+}
+
+async fn check_adb(config: &Config) -> CheckResult {
+    const NAME: &str = "adb";
+    if !matches!(
+        config.active_streaming_device(),
+        Some((_, StreamingDeviceConfig::Adb { .. }))
+    ) {
+        return CheckResult::new(
+            NAME,
+            CheckStatus::Skipped,
+            "No ADB streaming device configured",
+        );
+    }
+    match tokio::process::Command::new("adb")
+        .arg("version")
+        .output()
+        .await
+    {
+        Ok(out) if out.status.success() => {
+            let version = String::from_utf8_lossy(&out.stdout);
+            CheckResult::new(
+                NAME,
+                CheckStatus::Pass,
+                version
+                    .lines()
+                    .next()
+                    .unwrap_or("adb available")
+                    .to_string(),
+            )
+        }
+        Ok(out) => CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            format!(
+                "adb failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            ),
+        ),
+        Err(e) => CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            format!(
+                "adb not found in PATH ({}). Install Android platform tools.",
+                e
+            ),
+        ),
+    }
+}
+
+fn check_dbus() -> CheckResult {
+    const NAME: &str = "dbus";
+    if !cfg!(target_os = "linux") {
+        return CheckResult::new(NAME, CheckStatus::Skipped, "Only used on Linux");
+    }
+    match std::env::var("DBUS_SESSION_BUS_ADDRESS") {
+        Ok(addr) if !addr.is_empty() => CheckResult::new(NAME, CheckStatus::Pass, addr),
+        _ => CheckResult::new(
+            NAME,
+            CheckStatus::Warn,
+            "No session bus (DBUS_SESSION_BUS_ADDRESS unset); tray icon and autostart may not work",
+        ),
+    }
+}
@@ -0,0 +1,176 @@
+//! Shortcut string normalization. Shortcuts are stored as recorded (the frontend matches local
+//! shortcuts against those strings); before handing one to the global-shortcut plugin we map
+//! OS-specific modifier names and key synonyms to one canonical spelling, so a config recorded
+//! on macOS ("Cmd+Option+K") keeps working on Linux/Windows and vice versa.
+
+use tauri_plugin_global_shortcut::Shortcut;
+
+/// Canonical modifier names, in the order they are emitted.
+const MODIFIER_ORDER: &[&str] = &["CommandOrControl", "Ctrl", "Alt", "Shift", "Super"];
+
+/// Map a modifier name (any case, any OS spelling) to its canonical form.
+fn canonical_modifier(name: &str) -> Option<&'static str> {
+    match name.to_lowercase().as_str() {
+        "ctrl" | "control" | "ctl" => Some("Ctrl"),
+        "alt" | "option" | "opt" | "altgr" => Some("Alt"),
+        "shift" => Some("Shift"),
+        "super" | "meta" | "win" | "windows" | "cmd" | "command" | "logo" | "os" => Some("Super"),
+        "cmdorctrl" | "commandorcontrol" | "cmdorcontrol" | "commandorctrl" => {
+            Some("CommandOrControl")
+        }
+        _ => None,
+    }
+}
+
+/// Map key synonyms and layout-specific names to the names the plugin understands.
+fn canonical_key(name: &str) -> String {
+    let canonical = match name.to_lowercase().as_str() {
+        "return" | "enter" => "Enter",
+        "esc" | "escape" => "Escape",
+        "del" | "delete" => "Delete",
+        "ins" | "insert" => "Insert",
+        "bksp" | "backspace" => "Backspace",
+        "pgup" | "pageup" => "PageUp",
+        "pgdn" | "pagedown" => "PageDown",
+        "space" | "spacebar" | " " => "Space",
+        "up" | "arrowup" => "ArrowUp",
+        "down" | "arrowdown" => "ArrowDown",
+        "left" | "arrowleft" => "ArrowLeft",
+        "right" | "arrowright" => "ArrowRight",
+        "plus" => "=",
+        "minus" => "-",
+        _ => "",
+    };
+    if !canonical.is_empty() {
+        return canonical.to_string();
+    }
+    // Letters are case-insensitive ("k" and "K" are the same key); F-keys normalize to "F7"
+    if name.chars().count() == 1 {
+        return name.to_uppercase();
+    }
+    let lower = name.to_lowercase();
+    if let Some(n) = lower.strip_prefix('f')
+        && !n.is_empty()
+        && n.chars().all(|c| c.is_ascii_digit())
+    {
+        return format!("F{}", n);
+    }
+    name.to_string()
+}
+
+/// Normalize a shortcut to canonical form: modifiers first in a fixed order, then exactly one
+/// key. Normalizing an already-normalized string returns it unchanged.
+pub fn normalize(s: &str) -> Result<String, String> {
+    let mut modifiers: Vec<&'static str> = Vec::new();
+    let mut key: Option<String> = None;
+
+    // "+" itself can be the key (e.g. "Ctrl++"), so split carefully
+    let mut parts: Vec<&str> = s.split('+').map(|p| p.trim()).collect();
+    if s.trim_end().ends_with("++") {
+        parts.truncate(parts.len() - 2);
+        parts.push("=");
+    }
+
+    for part in parts {
+        if part.is_empty() {
+            continue;
+        }
+        if let Some(m) = canonical_modifier(part) {
+            if !modifiers.contains(&m) {
+                modifiers.push(m);
+            }
+        } else if key.is_some() {
+            return Err(format!(
+                "'{}' has more than one main key; use modifiers first and only one main key (e.g. Shift+Alt+K)",
+                s
+            ));
+        } else {
+            key = Some(canonical_key(part));
+        }
+    }
+
+    let key = key.ok_or_else(|| format!("'{}' has no main key", s))?;
+    modifiers.sort_by_key(|m| MODIFIER_ORDER.iter().position(|o| o == m));
+    let mut out: Vec<String> = modifiers.into_iter().map(|m| m.to_string()).collect();
+    out.push(key);
+    Ok(out.join("+"))
+}
+
+/// Normalize and parse a shortcut for the global-shortcut plugin.
+pub fn parse(s: &str) -> Result<Shortcut, String> {
+    let normalized = normalize(s)?;
+    normalized
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut '{}': {}", s, e))
+}
+
+/// True if the shortcut contains at least one modifier. Global hotkeys must, so they don't
+/// capture keys during normal typing.
+pub fn has_modifier(s: &str) -> bool {
+    s.split('+').any(|p| canonical_modifier(p.trim()).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHORTCUTS: &[&str] = &[
+        "Ctrl+Alt+K",
+        "Shift+Super+F7",
+        "CommandOrControl+Shift+ArrowUp",
+        "Alt+Space",
+        "Ctrl++",
+        "Ctrl+Shift+-",
+        "Super+Enter",
+        "Ctrl+Alt+Shift+Super+PageDown",
+    ];
+
+    #[test]
+    fn parse_format_parse_round_trips() {
+        for s in SHORTCUTS {
+            let shortcut = parse(s).unwrap();
+            let formatted = shortcut.into_string();
+            assert_eq!(
+                parse(&formatted).unwrap(),
+                shortcut,
+                "{} -> {}",
+                s,
+                formatted
+            );
+        }
+    }
+
+    #[test]
+    fn normalize_round_trips() {
+        for s in SHORTCUTS {
+            let normalized = normalize(s).unwrap();
+            assert_eq!(normalize(&normalized).unwrap(), normalized);
+            assert_eq!(parse(&normalized).unwrap(), parse(s).unwrap());
+        }
+    }
+
+    #[test]
+    fn os_spellings_parse_to_the_same_shortcut() {
+        assert_eq!(normalize("Option+Cmd+k").unwrap(), "Alt+Super+K");
+        assert_eq!(
+            parse("Option+Cmd+k").unwrap(),
+            parse("Super+Alt+K").unwrap()
+        );
+        assert_eq!(parse("control+esc").unwrap(), parse("Ctrl+Escape").unwrap());
+        assert_eq!(parse("ctrl+plus").unwrap(), parse("Ctrl++").unwrap());
+        assert_eq!(parse("Win+f12").unwrap(), parse("Super+F12").unwrap());
+    }
+
+    #[test]
+    fn rejects_shortcuts_without_exactly_one_key() {
+        assert!(normalize("Ctrl+Shift").is_err());
+        assert!(normalize("Ctrl+A+B").is_err());
+    }
+
+    #[test]
+    fn has_modifier_needs_a_modifier() {
+        assert!(has_modifier("cmd+K"));
+        assert!(!has_modifier("K"));
+        assert!(!has_modifier("F7"));
+    }
+}
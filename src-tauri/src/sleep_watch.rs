@@ -0,0 +1,144 @@
+//! System suspend and resume. A session left open across a suspend stays half-open on the TV,
+//! which may then refuse the next connect for a while, so it is closed cleanly first.
+//!
+//! On Linux, logind announces the suspend (`PrepareForSleep`, read through `gdbus monitor`),
+//! and a delay lock (held through `systemd-inhibit`) makes it wait until the session is closed.
+//! Elsewhere only the resume is noticed: the wall clock jumps ahead of a timer that didn't run
+//! while the machine slept.
+
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+
+/// How often the wall clock is compared against the timer.
+const TICK: Duration = Duration::from_secs(10);
+/// A tick that took this much longer than `TICK` by the wall clock means the machine slept.
+const JUMP: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum SleepEvent {
+    /// The machine is about to suspend; it waits until the `SleepDelay` is dropped (for at most
+    /// logind's `InhibitDelayMaxSec`, 5s by default).
+    Suspending(SleepDelay),
+    /// The machine woke up. May arrive twice for one resume (logind and the clock check).
+    Resumed,
+}
+
+/// Holds off a suspend in progress until dropped.
+#[derive(Debug)]
+pub struct SleepDelay {
+    #[cfg(target_os = "linux")]
+    inhibitor: Option<tokio::process::Child>,
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for SleepDelay {
+    fn drop(&mut self) {
+        // `cat` under systemd-inhibit exits on EOF, which releases the lock
+        if let Some(mut inhibitor) = self.inhibitor.take() {
+            drop(inhibitor.stdin.take());
+        }
+    }
+}
+
+/// Start watching; the channel lives as long as the app.
+pub fn watch() -> mpsc::UnboundedReceiver<SleepEvent> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    #[cfg(target_os = "linux")]
+    tauri::async_runtime::spawn(watch_logind(tx.clone()));
+    tauri::async_runtime::spawn(watch_clock(tx));
+    rx
+}
+
+async fn watch_clock(tx: mpsc::UnboundedSender<SleepEvent>) {
+    let mut last = SystemTime::now();
+    loop {
+        tokio::time::sleep(TICK).await;
+        let now = SystemTime::now();
+        let elapsed = now.duration_since(last).unwrap_or_default();
+        last = now;
+        if elapsed > TICK + JUMP {
+            log::info!("Clock jumped {:?} ahead; assuming the system resumed", elapsed - TICK);
+            if tx.send(SleepEvent::Resumed).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Take a logind delay lock on suspend, held until the returned process's stdin is closed.
+/// None if systemd-inhibit is missing or logind refuses the lock.
+#[cfg(target_os = "linux")]
+fn inhibit_sleep() -> Option<tokio::process::Child> {
+    let inhibitor = tokio::process::Command::new("systemd-inhibit")
+        .args([
+            "--what=sleep",
+            "--mode=delay",
+            "--who=LG TV Remote",
+            "--why=Closing the TV session",
+            "cat",
+        ])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+    match inhibitor {
+        Ok(inhibitor) => Some(inhibitor),
+        Err(e) => {
+            log::debug!("systemd-inhibit not available ({}); suspend won't wait for the disconnect", e);
+            None
+        }
+    }
+}
+
+/// Follow logind's `PrepareForSleep(true)` / `PrepareForSleep(false)` signals, holding a delay
+/// lock while awake so a suspend waits for the session to be closed.
+#[cfg(target_os = "linux")]
+async fn watch_logind(tx: mpsc::UnboundedSender<SleepEvent>) {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    let child = tokio::process::Command::new("gdbus")
+        .args([
+            "monitor",
+            "--system",
+            "--dest",
+            "org.freedesktop.login1",
+            "--object-path",
+            "/org/freedesktop/login1",
+        ])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            log::debug!("gdbus not available ({}); suspend is only noticed on resume", e);
+            return;
+        }
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return;
+    };
+    let mut inhibitor = inhibit_sleep();
+    let mut lines = BufReader::new(stdout).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if !line.contains("PrepareForSleep") {
+            continue;
+        }
+        let event = if line.contains("(true,)") {
+            SleepEvent::Suspending(SleepDelay {
+                inhibitor: inhibitor.take(),
+            })
+        } else {
+            // Ready for the next suspend
+            if inhibitor.is_none() {
+                inhibitor = inhibit_sleep();
+            }
+            SleepEvent::Resumed
+        };
+        if tx.send(event).is_err() {
+            break;
+        }
+    }
+    log::debug!("Stopped watching logind for suspend");
+}
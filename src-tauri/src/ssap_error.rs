@@ -0,0 +1,101 @@
+//! Human-readable explanations for webOS SSAP errors. The TV reports failures as terse codes
+//! ("401 insufficient permissions", `errorCode: -101`); this maps the common ones to what went
+//! wrong and what to try, so errors shown in the UI are actionable.
+
+use serde_json::Value;
+
+struct KnownError {
+    /// Leading code of the error string, or the payload's `errorCode`.
+    code: &'static str,
+    explanation: &'static str,
+    fix: &'static str,
+}
+
+const KNOWN_ERRORS: &[KnownError] = &[
+    KnownError {
+        code: "401",
+        explanation: "the TV hasn't granted this app permission for that",
+        fix: "re-pair the TV (Authenticate) and accept the prompt",
+    },
+    KnownError {
+        code: "403",
+        explanation: "the TV refused the request in its current state",
+        fix: "turn the TV fully on, or check it isn't blocked by a TV setting",
+    },
+    KnownError {
+        code: "404",
+        explanation: "this TV's model or firmware doesn't have that service",
+        fix: "update the TV firmware; the feature may not exist on this model",
+    },
+    KnownError {
+        code: "409",
+        explanation: "the TV is busy with a conflicting request",
+        fix: "wait a moment and try again",
+    },
+    KnownError {
+        code: "500",
+        explanation: "the TV app handling the request failed",
+        fix: "try again; restart the TV if it keeps happening",
+    },
+    KnownError {
+        code: "-100",
+        explanation: "the TV rejected the request's parameters",
+        fix: "check the value is valid for this TV",
+    },
+    KnownError {
+        code: "-101",
+        explanation: "the app or resource the request refers to isn't available",
+        fix: "check the app is installed or the input/channel exists",
+    },
+    KnownError {
+        code: "-102",
+        explanation: "the TV is still starting up the requested app",
+        fix: "try again in a few seconds",
+    },
+    KnownError {
+        code: "-1000",
+        explanation: "the TV's service isn't ready",
+        fix: "wait for the TV to finish booting and try again",
+    },
+];
+
+fn lookup(code: &str) -> Option<&'static KnownError> {
+    KNOWN_ERRORS.iter().find(|known| known.code == code)
+}
+
+/// Append the explanation and suggested fix to a raw error string like
+/// "404 no such service or method"; unknown errors are returned unchanged.
+pub fn explain(raw: &str) -> String {
+    let code = raw.split_whitespace().next().unwrap_or("");
+    match lookup(code) {
+        Some(known) => format!("{} — {} ({})", raw, known.explanation, known.fix),
+        None => raw.to_string(),
+    }
+}
+
+/// True if the TV answered that this client isn't registered (anymore): another remote app
+/// registered in its place and the TV dropped our session.
+pub fn is_not_registered(response: &Value) -> bool {
+    response["type"] == "error"
+        && response["error"]
+            .as_str()
+            .is_some_and(|e| e.starts_with("401") && e.contains("not registered"))
+}
+
+/// Error message for a failed SSAP response (`type: "error"` or `returnValue: false`).
+pub fn from_response(response: &Value, fallback: &str) -> String {
+    if let Some(error) = response["error"].as_str() {
+        return explain(error);
+    }
+    let payload = &response["payload"];
+    let text = payload["errorText"].as_str().unwrap_or(fallback);
+    let code = match &payload["errorCode"] {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        _ => return text.to_string(),
+    };
+    match lookup(&code) {
+        Some(known) => format!("{} — {} ({})", text, known.explanation, known.fix),
+        None => format!("{} (code {})", text, code),
+    }
+}
@@ -0,0 +1,87 @@
+//! Latest TV state seen through subscriptions (power, volume, foreground app). Events can
+//! arrive in bursts (holding volume up), so the snapshot is written to state.json at most
+//! once per `PERSIST_INTERVAL` rather than on every change; after a crash or restart it holds
+//! the last known state.
+
+use crate::config::Config;
+use crate::tv::{ForegroundApp, PowerState, VolumeStatus};
+use crate::usage::now;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+const PERSIST_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TvSnapshot {
+    /// TV the state belongs to.
+    pub tv: Option<String>,
+    pub power: Option<PowerState>,
+    pub volume: Option<VolumeStatus>,
+    pub foreground_app: Option<ForegroundApp>,
+    /// Unix seconds of the last change.
+    pub updated: u64,
+}
+
+pub struct StateCache {
+    snapshot: Mutex<TvSnapshot>,
+    /// Signalled on every change; the writer saves once per interval however many arrived.
+    dirty: Notify,
+}
+
+impl StateCache {
+    fn path() -> PathBuf {
+        Config::config_path().with_file_name("state.json")
+    }
+
+    pub fn load() -> Self {
+        let snapshot = fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            snapshot: Mutex::new(snapshot),
+            dirty: Notify::new(),
+        }
+    }
+
+    pub fn snapshot(&self) -> TvSnapshot {
+        self.snapshot.lock().unwrap().clone()
+    }
+
+    /// Change the snapshot for `tv`; state from a different TV is cleared first.
+    pub fn update(&self, tv: &str, f: impl FnOnce(&mut TvSnapshot)) {
+        {
+            let mut snapshot = self.snapshot.lock().unwrap();
+            if snapshot.tv.as_deref() != Some(tv) {
+                *snapshot = TvSnapshot {
+                    tv: Some(tv.to_string()),
+                    ..Default::default()
+                };
+            }
+            f(&mut snapshot);
+            snapshot.updated = now();
+        }
+        self.dirty.notify_one();
+    }
+
+    fn save(&self) {
+        let result = serde_json::to_string(&self.snapshot())
+            .map_err(|e| e.to_string())
+            .and_then(|contents| fs::write(Self::path(), contents).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::warn!("Failed to save TV state: {}", e);
+        }
+    }
+
+    /// Write the snapshot after changes, at most once per `PERSIST_INTERVAL`. Runs forever.
+    pub async fn run_writer(&self) {
+        loop {
+            self.dirty.notified().await;
+            self.save();
+            tokio::time::sleep(PERSIST_INTERVAL).await;
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Optional TV subscriptions the frontend (or an API client) turns on only while a view needs
+//! them, so an idle remote doesn't keep the TV pushing program guide or settings updates.
+//! Requests are counted per kind; the subscription is kept across reconnects until the last
+//! subscriber unsubscribes.
+
+use crate::error::AppError;
+use crate::tv::TvConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter};
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// Now/next program on Live TV -> "program-info-changed"
+    ProgramInfo,
+    /// Tuned channel -> "channel-changed"
+    Channel,
+    /// Picture mode, backlight, energy saving -> "picture-settings-changed"
+    PictureSettings,
+    /// Speaker / ARC / Bluetooth output -> "sound-output-changed"
+    SoundOutput,
+}
+
+impl EventKind {
+    fn uri(self) -> &'static str {
+        match self {
+            EventKind::ProgramInfo => "ssap://tv/getChannelProgramInfo",
+            EventKind::Channel => "ssap://tv/getCurrentChannel",
+            EventKind::PictureSettings => "ssap://settings/getSystemSettings",
+            EventKind::SoundOutput => "ssap://audio/getSoundOutput",
+        }
+    }
+
+    fn payload(self) -> Option<Value> {
+        match self {
+            EventKind::PictureSettings => Some(json!({
+                "category": "picture",
+                "keys": ["pictureMode", "backlight", "energySaving"],
+            })),
+            _ => None,
+        }
+    }
+
+    pub fn event_name(self) -> &'static str {
+        match self {
+            EventKind::ProgramInfo => "program-info-changed",
+            EventKind::Channel => "channel-changed",
+            EventKind::PictureSettings => "picture-settings-changed",
+            EventKind::SoundOutput => "sound-output-changed",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct EventSubscriptions {
+    /// Subscriber count per kind.
+    refs: HashMap<EventKind, u32>,
+    /// Subscription id and forwarding task in the current session.
+    active: HashMap<EventKind, (String, JoinHandle<()>)>,
+}
+
+impl EventSubscriptions {
+    /// Count a subscriber; true if this is the first one (the TV subscription must be started).
+    pub fn add_ref(&mut self, kind: EventKind) -> bool {
+        let refs = self.refs.entry(kind).or_insert(0);
+        *refs += 1;
+        *refs == 1
+    }
+
+    /// Drop a subscriber; returns the active subscription id to cancel once nobody is left.
+    pub fn release(&mut self, kind: EventKind) -> Option<String> {
+        let refs = self.refs.get_mut(&kind)?;
+        *refs = refs.saturating_sub(1);
+        if *refs > 0 {
+            return None;
+        }
+        self.refs.remove(&kind);
+        let (id, task) = self.active.remove(&kind)?;
+        task.abort();
+        Some(id)
+    }
+
+    pub fn wanted(&self) -> Vec<EventKind> {
+        self.refs.keys().copied().collect()
+    }
+
+    pub fn is_wanted(&self, kind: EventKind) -> bool {
+        self.refs.contains_key(&kind)
+    }
+
+    pub fn set_active(&mut self, kind: EventKind, id: String, task: JoinHandle<()>) {
+        if let Some((_, old)) = self.active.insert(kind, (id, task)) {
+            old.abort();
+        }
+    }
+}
+
+/// Subscribe on the TV and forward every push as a Tauri event. The task is a session task.
+pub async fn start(
+    app: &AppHandle,
+    tv: &mut TvConnection,
+    kind: EventKind,
+) -> Result<(String, JoinHandle<()>), AppError> {
+    let (id, mut rx) = tv.subscribe_with_id(kind.uri(), kind.payload()).await?;
+    let app = app.clone();
+    let task = tv.tasks.spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            if msg["type"] != "error" {
+                let _ = app.emit(kind.event_name(), &msg["payload"]);
+            }
+        }
+    });
+    Ok((id, task))
+}
@@ -0,0 +1,96 @@
+use std::future::Future;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+/// Background tasks tied to one TV session (keepalive, subscriptions, pollers). They are
+/// cancelled together when the session ends so nothing from an old connection keeps running
+/// against a new one.
+pub struct SessionTasks {
+    token: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl Default for SessionTasks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionTasks {
+    pub fn new() -> Self {
+        Self {
+            token: CancellationToken::new(),
+            tracker: TaskTracker::new(),
+        }
+    }
+
+    /// Spawn a task that is cancelled when the current session ends.
+    pub fn spawn<F>(&self, fut: F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let token = self.token.clone();
+        self.tracker.spawn(async move {
+            tokio::select! {
+                _ = token.cancelled() => {}
+                _ = fut => {}
+            }
+        })
+    }
+
+    /// Cancel every task of the current session and start tracking a fresh one.
+    pub fn cancel_all(&mut self) {
+        if !self.tracker.is_empty() {
+            log::debug!("Cancelling {} session task(s)", self.tracker.len());
+        }
+        self.token.cancel();
+        self.tracker.close();
+        self.token = CancellationToken::new();
+        self.tracker = TaskTracker::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn cancel_all_ends_running_tasks() {
+        let mut tasks = SessionTasks::new();
+        let handles: Vec<_> = (0..3)
+            .map(|_| tasks.spawn(std::future::pending()))
+            .collect();
+        assert_eq!(tasks.tracker.len(), 3);
+
+        let old = tasks.tracker.clone();
+        tasks.cancel_all();
+        for handle in handles {
+            tokio::time::timeout(Duration::from_secs(1), handle)
+                .await
+                .expect("task not cancelled")
+                .unwrap();
+        }
+        assert!(old.is_closed());
+        old.wait().await;
+        assert!(old.is_empty());
+        assert!(tasks.tracker.is_empty());
+        assert!(!tasks.tracker.is_closed());
+    }
+
+    #[tokio::test]
+    async fn tasks_after_cancel_all_keep_running() {
+        let mut tasks = SessionTasks::new();
+        tasks.cancel_all();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tasks.spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let _ = tx.send(());
+        });
+        tokio::time::timeout(Duration::from_secs(1), rx)
+            .await
+            .expect("task was cancelled")
+            .unwrap();
+    }
+}
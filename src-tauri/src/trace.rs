@@ -0,0 +1,114 @@
+//! Opt-in recording of the frames exchanged with the TV, for debugging model-specific endpoint
+//! behavior (firmware that 404s on getStatus, odd payload shapes) from a user's machine. Frames
+//! go into a ring buffer while tracing is on and are written out as JSON on export. Client keys
+//! are redacted before a frame is stored.
+
+use crate::config::Config;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Frames kept; older ones are dropped first.
+const CAPACITY: usize = 2000;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceFrame {
+    /// Unix time in milliseconds.
+    pub at_ms: u64,
+    pub direction: Direction,
+    /// "ssap" for the main socket, "input" for the pointer input socket.
+    pub socket: &'static str,
+    /// Parsed JSON for SSAP frames, the raw text otherwise.
+    pub frame: Value,
+}
+
+#[derive(Default)]
+pub struct ProtocolTrace {
+    enabled: AtomicBool,
+    frames: Mutex<VecDeque<TraceFrame>>,
+}
+
+impl ProtocolTrace {
+    /// Start recording from an empty buffer.
+    pub fn start(&self) {
+        self.frames.lock().unwrap().clear();
+        self.enabled.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop recording; the frames stay until the next start.
+    pub fn stop(&self) {
+        self.enabled.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    pub fn record(&self, direction: Direction, socket: &'static str, text: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+        let frame = match serde_json::from_str::<Value>(text) {
+            Ok(mut json) => {
+                redact(&mut json);
+                json
+            }
+            Err(_) => Value::String(text.to_string()),
+        };
+        let at_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let mut frames = self.frames.lock().unwrap();
+        if frames.len() == CAPACITY {
+            frames.pop_front();
+        }
+        frames.push_back(TraceFrame {
+            at_ms,
+            direction,
+            socket,
+            frame,
+        });
+    }
+
+    /// Write the recorded frames as a JSON array to `path`, or to a timestamped file next to
+    /// config.json. Returns where it was written.
+    pub fn export(&self, path: Option<PathBuf>) -> Result<PathBuf, String> {
+        let frames: Vec<TraceFrame> = self.frames.lock().unwrap().iter().cloned().collect();
+        let path = path.unwrap_or_else(|| {
+            let name = format!("trace-{}.json", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+            Config::config_path().with_file_name(name)
+        });
+        let json = serde_json::to_string_pretty(&frames).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json)
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+/// Replace every "client-key" value (handshake payloads and registration replies).
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key == "client-key" {
+                    *value = Value::String("<redacted>".to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
@@ -1,14 +1,381 @@
+use crate::buttons;
+use crate::config::{
+    NetworkStatusEndpoint, PowerOnMethod, Scene, TimeoutConfig, TvCapabilities, WolConfig,
+};
+use crate::error::AppError;
+use crate::netif;
+use crate::ssap_error;
+use crate::tasks::SessionTasks;
+use crate::trace::{Direction, ProtocolTrace};
+use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, StreamExt};
 use native_tls::TlsConnector;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::sync::Arc;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, OnceLock};
 use tokio::net::TcpStream;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, oneshot, Mutex, Notify};
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 
 type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type WsSink = SplitSink<WsStream, Message>;
+
+/// A reconnect to the same TV with the same key within this window of its last registration
+/// ack is treated as a session resumption (see `TvConnection::connect`).
+const SESSION_RESUME_WINDOW: std::time::Duration = std::time::Duration::from_secs(120);
+
+const POWER_STATE_URI: &str = "ssap://com.webos.service.tvpower/power/getPowerState";
+pub const INPUT_SOCKET_URI: &str = "ssap://com.webos.service.networkinput/getPointerInputSocket";
+pub const SYSTEM_INFO_URI: &str = "ssap://system/getSystemInfo";
+pub const SOFTWARE_INFO_URI: &str = "ssap://com.webos.service.update/getCurrentSWInformation";
+/// Cheap request answered by every firmware, timed by `ConnectionManager::measure_latency`.
+pub const LATENCY_URI: &str = "ssap://com.webos.service.connectionmanager/getinfo";
+
+/// A request that went out on the SSAP socket; `wait` for its reply. Holds only the router,
+/// not the connection, so jobs queued behind the request don't wait for the TV to answer.
+pub struct PendingReply {
+    id: String,
+    reply: oneshot::Receiver<Value>,
+    timeout: std::time::Duration,
+    router: Arc<std::sync::Mutex<Router>>,
+}
+
+impl PendingReply {
+    /// Wait for the reader task to hand over the reply with our id.
+    pub async fn wait(self) -> Result<Value, AppError> {
+        match tokio::time::timeout(self.timeout, self.reply).await {
+            Ok(Ok(data)) if ssap_error::is_not_registered(&data) => {
+                log::warn!("TV no longer knows this session: {}", data);
+                Err(AppError::catalog("session_replaced", &[]))
+            }
+            Ok(Ok(data)) => Ok(data),
+            Ok(Err(_)) => Err(AppError::catalog("connection_closed", &[])),
+            Err(_) => {
+                // Timeout - connection may be dead
+                self.router.lock().unwrap().expire(&self.id);
+                Err(AppError::catalog("command_timeout", &[]))
+            }
+        }
+    }
+}
+
+/// A handshake sent by `TvConnection::start_connect`; `wait` for the TV to accept it. Holds
+/// only the session's socket and router, not the connection, so jobs queued behind the
+/// connect run while the user answers the pairing prompt.
+pub struct PendingRegistration {
+    registration: mpsc::UnboundedReceiver<Value>,
+    pin_results: mpsc::UnboundedReceiver<Value>,
+    pins: Option<mpsc::Receiver<PinSubmission>>,
+    ws: Arc<Mutex<WsSink>>,
+    router: Arc<std::sync::Mutex<Router>>,
+    trace: Arc<ProtocolTrace>,
+    timeout: std::time::Duration,
+    /// Resuming a recent session: the TV only has to confirm the key it already trusts.
+    resume: bool,
+    /// Key sent with the handshake; the TV may answer with a new one.
+    client_key: Option<String>,
+    /// Certificate pin the connect checked, for `restart_connect`.
+    pinned_cert: Option<String>,
+    /// Requests sent along with a resumed handshake instead of after it.
+    early: Option<EarlyRequests>,
+    /// Their replies, once `wait` has them.
+    early_replies: Option<EarlyReplies>,
+}
+
+/// What `finish_connect` would otherwise ask for once registered.
+struct EarlyRequests {
+    power_state: PendingReply,
+    input_socket: PendingReply,
+}
+
+struct EarlyReplies {
+    power_state: Result<Value, AppError>,
+    input_socket: Result<Value, AppError>,
+}
+
+impl PendingRegistration {
+    /// Wait for the TV to accept the handshake. Returns the client key it issued, if any.
+    /// When resuming, the replies to the requests sent with the handshake arrive meanwhile.
+    pub async fn wait(&mut self) -> Result<Option<String>, AppError> {
+        let early = self.early.take();
+        let timeout = self.timeout;
+        let registration = async {
+            tokio::time::timeout(timeout, self.wait_registered())
+                .await
+                .map_err(|_| AppError::catalog("registration_timeout", &[]))
+                .and_then(|result| result)
+        };
+        let (registered, early_replies) = match early {
+            Some(early) => {
+                let (registered, power_state, input_socket) = tokio::join!(
+                    registration,
+                    early.power_state.wait(),
+                    early.input_socket.wait()
+                );
+                let replies = EarlyReplies {
+                    power_state,
+                    input_socket,
+                };
+                (registered, Some(replies))
+            }
+            None => (registration.await, None),
+        };
+        self.early_replies = early_replies;
+        {
+            let mut router = self.router.lock().unwrap();
+            router.subscriptions.remove("register_0");
+            router.subscriptions.remove("pin_0");
+        }
+        registered
+    }
+
+    /// True if this connect resumed a recent session rather than doing a full handshake.
+    pub fn is_resume(&self) -> bool {
+        self.resume
+    }
+
+    /// Forward PINs typed by the user (PIN pairing) until the TV registers us, and report
+    /// back whether the TV took them.
+    async fn wait_registered(&mut self) -> Result<Option<String>, AppError> {
+        let Self {
+            registration,
+            pin_results,
+            pins,
+            ws,
+            trace,
+            ..
+        } = self;
+        let mut pin_reply: Option<oneshot::Sender<Result<(), AppError>>> = None;
+        loop {
+            let next_pin = async {
+                match pins.as_mut() {
+                    Some(rx) => rx.recv().await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                data = registration.recv() => {
+                    let Some(data) = data else {
+                        return Err(AppError::catalog("connection_closed", &[]));
+                    };
+                    if data["type"] == "registered" {
+                        if let Some(reply) = pin_reply.take() {
+                            let _ = reply.send(Ok(()));
+                        }
+                        return Ok(data["payload"]["client-key"].as_str().map(|s| s.to_string()));
+                    } else if data["type"] == "error" {
+                        return Err(AppError::PairingRejected(format!(
+                            "Registration error: {}",
+                            ssap_error::from_response(&data, "Unknown")
+                        ).into()));
+                    }
+                    // Keep waiting for other message types (like pairing prompts)
+                }
+                Some(data) = pin_results.recv() => {
+                    // Wrong PIN: let the user try again while the TV still shows it
+                    if data["type"] == "error" {
+                        if let Some(reply) = pin_reply.take() {
+                            let _ = reply.send(Err(AppError::PairingRejected(format!(
+                                "PIN rejected: {}",
+                                ssap_error::from_response(&data, "Unknown")
+                            ).into())));
+                        }
+                    }
+                }
+                Some((pin, reply)) = next_pin => {
+                    let request = json!({
+                        "type": "request",
+                        "id": "pin_0",
+                        "uri": "ssap://pairing/setPin",
+                        "payload": { "pin": pin },
+                    });
+                    match send_frame(ws, trace, &request).await {
+                        Ok(()) => pin_reply = Some(reply),
+                        Err(e) => {
+                            let _ = reply.send(Err(AppError::ConnectionLost(format!(
+                                "Failed to send PIN: {}",
+                                e
+                            ).into())));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Send a frame on the SSAP socket without registering for a reply.
+async fn send_frame(
+    ws: &Mutex<WsSink>,
+    trace: &ProtocolTrace,
+    msg: &Value,
+) -> Result<(), AppError> {
+    let text = msg.to_string();
+    trace.record(Direction::Sent, "ssap", &text);
+    let result = ws.lock().await.send(Message::Text(text.into())).await;
+    result.map_err(|e| AppError::ConnectionLost(e.to_string().into()))
+}
+
+/// An opened pointer input socket, ready for `TvConnection::install_input_socket`.
+pub struct InputSocket(WsStream);
+
+/// The TV's last registration acknowledgment for a client key.
+struct SessionAck {
+    ip: String,
+    client_key: String,
+    at: std::time::Instant,
+}
+
+/// Routes frames read from the SSAP socket by their `id`: replies go to the request waiting
+/// for them, subscription pushes to the subscriber, and anything else (frames without an id,
+/// ids nobody registered) to the unsolicited channel rather than to whichever request happens
+/// to be in flight. Shared between the connection and its reader task.
+#[derive(Default)]
+struct Router {
+    pending: HashMap<String, oneshot::Sender<Value>>,
+    subscriptions: HashMap<String, mpsc::UnboundedSender<Value>>,
+    /// Requests that timed out; their late replies are dropped, not reported as unsolicited.
+    expired: HashSet<String>,
+    unsolicited: Option<mpsc::UnboundedSender<Value>>,
+    closed: bool,
+    /// The TV closed the socket with a close frame (rather than the connection failing).
+    closed_by_tv: bool,
+    /// The TV stopped answering WebSocket pings.
+    pong_timed_out: bool,
+    /// Notified when the router closes, so the keepalive notices right away.
+    down: Arc<Notify>,
+}
+
+impl Router {
+    fn add_request(&mut self, id: &str) -> Result<oneshot::Receiver<Value>, AppError> {
+        if self.closed {
+            return Err(AppError::catalog("connection_closed", &[]));
+        }
+        let (tx, rx) = oneshot::channel();
+        self.pending.insert(id.to_string(), tx);
+        Ok(rx)
+    }
+
+    fn add_subscription(&mut self, id: &str) -> Result<mpsc::UnboundedReceiver<Value>, AppError> {
+        if self.closed {
+            return Err(AppError::catalog("connection_closed", &[]));
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.subscriptions.insert(id.to_string(), tx);
+        Ok(rx)
+    }
+
+    fn dispatch(&mut self, data: Value) {
+        let Some(id) = data["id"].as_str().map(|s| s.to_string()) else {
+            log::debug!("SSAP message without id: {}", data);
+            self.report_unsolicited(data);
+            return;
+        };
+        if let Some(tx) = self.pending.remove(&id) {
+            let _ = tx.send(data);
+        } else if let Some(tx) = self.subscriptions.get(&id) {
+            if tx.send(data).is_err() {
+                // Subscriber went away
+                self.subscriptions.remove(&id);
+            }
+        } else if self.expired.remove(&id) {
+            log::debug!("Late reply to timed-out request {}: {}", id, data);
+        } else {
+            log::debug!("SSAP message for unknown id {}: {}", id, data);
+            self.report_unsolicited(data);
+        }
+    }
+
+    fn report_unsolicited(&mut self, data: Value) {
+        if let Some(tx) = &self.unsolicited
+            && tx.send(data).is_err()
+        {
+            self.unsolicited = None;
+        }
+    }
+
+    /// A request gave up waiting: forget it, and recognize its reply if it still comes.
+    fn expire(&mut self, id: &str) {
+        self.pending.remove(id);
+        // Bounded: a TV that never answers shouldn't grow this for the whole session
+        if self.expired.len() >= 64 {
+            self.expired.clear();
+        }
+        self.expired.insert(id.to_string());
+    }
+
+    /// Drop all waiters so pending requests fail and subscription channels end.
+    fn close(&mut self) {
+        self.closed = true;
+        self.pending.clear();
+        self.subscriptions.clear();
+        self.expired.clear();
+        self.unsolicited = None;
+        self.down.notify_one();
+    }
+}
+
+/// Reads the SSAP socket for the lifetime of a session and hands frames to the router. Also
+/// sends a WebSocket ping every keepalive interval and ends the session when the pong doesn't
+/// come back within the command timeout, which catches a dead link without an SSAP request.
+async fn read_loop(
+    mut stream: SplitStream<WsStream>,
+    sink: Arc<Mutex<WsSink>>,
+    router: Arc<std::sync::Mutex<Router>>,
+    trace: Arc<ProtocolTrace>,
+    timeouts: TimeoutConfig,
+) {
+    let mut next_ping = tokio::time::Instant::now() + timeouts.keepalive_interval();
+    let mut pong_due: Option<tokio::time::Instant> = None;
+    loop {
+        let wake = pong_due.map_or(next_ping, |due| due.min(next_ping));
+        tokio::select! {
+            msg = stream.next() => match msg {
+                Some(Ok(Message::Text(text))) => {
+                    trace.record(Direction::Received, "ssap", &text);
+                    if let Ok(data) = serde_json::from_str::<Value>(&text) {
+                        router.lock().unwrap().dispatch(data);
+                    }
+                }
+                Some(Ok(Message::Pong(_))) => pong_due = None,
+                Some(Ok(Message::Close(frame))) => {
+                    log::debug!("TV closed the SSAP socket: {:?}", frame);
+                    router.lock().unwrap().closed_by_tv = true;
+                    break;
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => {
+                    log::debug!("SSAP socket read error: {}", e);
+                    break;
+                }
+                None => break,
+            },
+            _ = tokio::time::sleep_until(wake) => {
+                let now = tokio::time::Instant::now();
+                if pong_due.is_some_and(|due| now >= due) {
+                    log::warn!("No pong from the TV within {:?}", timeouts.command_timeout());
+                    router.lock().unwrap().pong_timed_out = true;
+                    break;
+                }
+                if now >= next_ping {
+                    next_ping = now + timeouts.keepalive_interval();
+                    pong_due.get_or_insert(now + timeouts.command_timeout());
+                    let ping = Message::Ping(Default::default());
+                    if let Err(e) = sink.lock().await.send(ping).await {
+                        log::debug!("SSAP ping failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    log::debug!("SSAP socket closed");
+    router.lock().unwrap().close();
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandResult {
@@ -49,36 +416,562 @@ impl CommandResult {
     }
 }
 
+/// TV power state as reported by `com.webos.service.tvpower/power/getPowerState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerState {
+    On,
+    /// Quick Start+ / Active Standby: panel is off but SSAP still answers.
+    Standby,
+    Off,
+    Unknown,
+}
+
+impl PowerState {
+    pub fn from_response(response: &Value) -> Self {
+        if response["type"] == "error" {
+            return PowerState::Unknown;
+        }
+        let payload = &response["payload"];
+        let state = payload["state"].as_str().unwrap_or("");
+        // "processing" is set while the TV is transitioning, e.g. "Request Active Standby"
+        let processing = payload["processing"].as_str().unwrap_or("");
+        if state == "Active Standby"
+            || state == "Suspend"
+            || processing.contains("Standby")
+            || processing.contains("Suspend")
+            || processing.contains("Power Off")
+        {
+            PowerState::Standby
+        } else if state == "Active" || state == "Screen Off" {
+            PowerState::On
+        } else if state == "Power Off" {
+            PowerState::Off
+        } else {
+            PowerState::Unknown
+        }
+    }
+}
+
+/// Picture energy-saving level (`energySaving` in the picture settings category).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnergySavingLevel {
+    Off,
+    Min,
+    Med,
+    Max,
+    ScreenOff,
+}
+
+impl EnergySavingLevel {
+    pub fn as_webos(self) -> &'static str {
+        match self {
+            EnergySavingLevel::Off => "off",
+            EnergySavingLevel::Min => "min",
+            EnergySavingLevel::Med => "med",
+            EnergySavingLevel::Max => "max",
+            EnergySavingLevel::ScreenOff => "screen_off",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        serde_json::from_value(json!(s)).ok()
+    }
+}
+
+/// Model and firmware details from `system/getSystemInfo` and `update/getCurrentSWInformation`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SystemInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+    /// e.g. "webOSTV 5.0"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product_name: Option<String>,
+    /// e.g. "04.30.50"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub firmware_version: Option<String>,
+}
+
+impl SystemInfo {
+    /// From the payload of a `system/getSystemInfo` reply.
+    pub fn from_payload(payload: &Value) -> Self {
+        Self {
+            model_name: payload["modelName"].as_str().map(|s| s.to_string()),
+            serial_number: payload["serialNumber"].as_str().map(|s| s.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Add product name and firmware version from a `getCurrentSWInformation` reply.
+    pub fn add_software_info(&mut self, payload: &Value) {
+        self.product_name = payload["product_name"].as_str().map(|s| s.to_string());
+        self.firmware_version = match (payload["major_ver"].as_str(), payload["minor_ver"].as_str()) {
+            (Some(major), Some(minor)) => Some(format!("{}.{}", major, minor)),
+            _ => None,
+        };
+    }
+}
+
+/// Sound mode (`soundMode` in the sound settings category).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoundMode {
+    Standard,
+    Cinema,
+    ClearVoice,
+    Sports,
+    Music,
+    Game,
+}
+
+impl SoundMode {
+    const ALL: [SoundMode; 6] = [
+        SoundMode::Standard,
+        SoundMode::Cinema,
+        SoundMode::ClearVoice,
+        SoundMode::Sports,
+        SoundMode::Music,
+        SoundMode::Game,
+    ];
+
+    pub fn as_webos(self) -> &'static str {
+        match self {
+            SoundMode::Standard => "standard",
+            SoundMode::Cinema => "movie",
+            SoundMode::ClearVoice => "news",
+            SoundMode::Sports => "sports",
+            SoundMode::Music => "music",
+            SoundMode::Game => "game",
+        }
+    }
+
+    /// Our name for the mode, as used in `sound_mode_<name>` actions.
+    pub fn name(self) -> &'static str {
+        match self {
+            SoundMode::Standard => "standard",
+            SoundMode::Cinema => "cinema",
+            SoundMode::ClearVoice => "clear_voice",
+            SoundMode::Sports => "sports",
+            SoundMode::Music => "music",
+            SoundMode::Game => "game",
+        }
+    }
+
+    pub fn from_webos(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.as_webos() == s)
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name() == s)
+    }
+}
+
+/// Firmware update status from `com.webos.service.update`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TvUpdateStatus {
+    /// Installed firmware, e.g. "04.30.50".
+    pub current_version: Option<String>,
+    /// None when the TV didn't say (older firmware doesn't expose the check over SSAP).
+    pub update_available: Option<bool>,
+    pub available_version: Option<String>,
+}
+
+impl TvUpdateStatus {
+    fn apply_check_payload(&mut self, payload: &Value) {
+        self.update_available = payload["updateAvailable"]
+            .as_bool()
+            .or(payload["isUpdateAvailable"].as_bool())
+            .or(payload["status"].as_str().map(|s| s == "available" || s == "downloaded"));
+        self.available_version = payload["version"]
+            .as_str()
+            .or(payload["updateVersion"].as_str())
+            .map(|s| s.to_string());
+    }
+}
+
+/// A Bluetooth device paired with the TV, from `bluetooth2/device/getStatus`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BluetoothDevice {
+    pub address: String,
+    pub name: String,
+    /// True while an audio (A2DP) profile is connected.
+    pub connected: bool,
+}
+
+impl BluetoothDevice {
+    /// Paired audio devices from a getStatus payload. Non-audio devices (Magic Remote,
+    /// keyboards) are left out.
+    pub fn list_from_payload(payload: &Value) -> Vec<Self> {
+        let Some(devices) = payload["devices"].as_array() else {
+            return Vec::new();
+        };
+        devices
+            .iter()
+            .filter(|d| d["paired"].as_bool().unwrap_or(true))
+            .filter(|d| {
+                let class = d["typeOfDevice"].as_str().or(d["classOfDevice"].as_str());
+                class.is_none_or(|c| c.to_lowercase().contains("audio"))
+                    || d["supportedProfiles"]
+                        .as_array()
+                        .is_some_and(|p| p.iter().any(|p| p == "a2dp"))
+            })
+            .filter_map(|d| {
+                let address = d["address"].as_str()?.to_string();
+                let name = d["name"].as_str().unwrap_or(&address).to_string();
+                let connected = d["connectedProfiles"]
+                    .as_array()
+                    .is_some_and(|p| p.iter().any(|p| p == "a2dp"));
+                Some(Self {
+                    address,
+                    name,
+                    connected,
+                })
+            })
+            .collect()
+    }
+}
+
+/// External input (HDMI, component, ...) from `tv/getExternalInputList`.
+#[derive(Debug, Clone, Serialize)]
+pub struct InputSource {
+    /// e.g. "HDMI_1"
+    pub id: String,
+    /// User label from the config if set, otherwise the TV's.
+    pub label: String,
+    /// The TV's own label, e.g. "HDMI1" or the name from HDMI-CEC.
+    pub tv_label: String,
+    pub app_id: Option<String>,
+    /// Something is plugged in (when the TV reports it).
+    pub connected: Option<bool>,
+    /// Icon URL on the TV's web server.
+    #[serde(skip)]
+    pub icon_url: Option<String>,
+    /// Cached copy of the icon (see `app_icons`), once downloaded.
+    pub icon_path: Option<String>,
+}
+
+impl InputSource {
+    pub fn list_from_payload(payload: &Value, labels: &HashMap<String, String>) -> Vec<Self> {
+        let Some(devices) = payload["devices"].as_array() else {
+            return Vec::new();
+        };
+        devices
+            .iter()
+            .filter_map(|d| {
+                let id = d["id"].as_str()?.to_string();
+                let tv_label = d["label"].as_str().unwrap_or(&id).to_string();
+                let label = labels.get(&id).cloned().unwrap_or_else(|| tv_label.clone());
+                Some(Self {
+                    label,
+                    tv_label,
+                    app_id: d["appId"].as_str().map(|s| s.to_string()),
+                    connected: d["connected"].as_bool(),
+                    icon_url: d["icon"].as_str().map(|s| s.to_string()),
+                    icon_path: None,
+                    id,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Installed app from `com.webos.applicationManager/listLaunchPoints`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TvApp {
+    /// App id for `launch_app` and `app_<id>` actions.
+    pub id: String,
+    pub title: String,
+    /// Icon URL on the TV's web server.
+    #[serde(skip)]
+    pub icon_url: Option<String>,
+    /// Cached copy of the icon (see `app_icons`), once downloaded.
+    pub icon_path: Option<String>,
+}
+
+impl TvApp {
+    pub fn list_from_payload(payload: &Value) -> Vec<Self> {
+        let Some(launch_points) = payload["launchPoints"].as_array() else {
+            return Vec::new();
+        };
+        launch_points
+            .iter()
+            .filter_map(|p| {
+                let id = p["id"].as_str()?.to_string();
+                Some(Self {
+                    title: p["title"].as_str().unwrap_or(&id).to_string(),
+                    icon_url: p["icon"].as_str().map(|s| s.to_string()),
+                    icon_path: None,
+                    id,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Volume and mute state from an `audio/getStatus` / `audio/getVolume` response or
+/// subscription push.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeStatus {
+    pub volume: Option<u64>,
+    pub muted: Option<bool>,
+}
+
+impl VolumeStatus {
+    pub fn from_payload(payload: &Value) -> Option<Self> {
+        // webOS 5+ nests the values in volumeStatus; older firmware has them at the top level
+        let status = if payload["volumeStatus"].is_object() {
+            &payload["volumeStatus"]
+        } else {
+            payload
+        };
+        let volume = status["volume"].as_u64();
+        let muted = status["muteStatus"]
+            .as_bool()
+            .or(status["muted"].as_bool())
+            .or(status["mute"].as_bool());
+        if volume.is_none() && muted.is_none() {
+            return None;
+        }
+        Some(Self { volume, muted })
+    }
+}
+
+/// Foreground app from `getForegroundAppInfo`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForegroundApp {
+    pub app_id: String,
+    /// True for HDMI / external input "apps" (com.webos.app.hdmi1, ...), false for streaming
+    /// and other apps, so the UI can switch between input picker and transport controls.
+    pub is_input: bool,
+}
+
+impl ForegroundApp {
+    pub fn from_payload(payload: &Value) -> Option<Self> {
+        let app_id = payload["appId"].as_str()?.to_string();
+        let is_input = app_id.starts_with("com.webos.app.hdmi")
+            || app_id.starts_with("com.webos.app.externalinput")
+            || app_id == "com.webos.app.livetv";
+        Some(Self { app_id, is_input })
+    }
+}
+
+/// Tuned broadcast channel from `tv/getCurrentChannel`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrentChannel {
+    /// e.g. "7-1"
+    pub number: Option<String>,
+    pub name: Option<String>,
+    pub channel_id: Option<String>,
+    /// e.g. "Cable Digital TV"
+    pub channel_type: Option<String>,
+}
+
+impl CurrentChannel {
+    pub fn from_payload(payload: &Value) -> Self {
+        let string = |key: &str| payload[key].as_str().map(|s| s.to_string());
+        Self {
+            number: string("channelNumber"),
+            name: string("channelName"),
+            channel_id: string("channelId"),
+            channel_type: string("channelTypeName"),
+        }
+    }
+}
+
+/// Something the TV would show in its notification center.
+#[derive(Debug, Clone, Serialize)]
+pub struct TvNotification {
+    /// Stable across reads, so a notification is only mirrored once.
+    pub id: String,
+    /// e.g. "software_update".
+    pub kind: String,
+    pub message: String,
+}
+
+/// A PIN typed by the user during PIN pairing, with a reply for whether the TV accepted it.
+pub type PinSubmission = (String, oneshot::Sender<Result<(), AppError>>);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InputCapabilities {
+    pub input_socket: bool,
+    pub reason: Option<String>,
+    pub unavailable_buttons: Vec<String>,
+}
+
+/// Round-trip times to the TV in milliseconds (median of the samples), to tell a slow network
+/// from a slow TV from a busy app.
+#[derive(Debug, Clone, Serialize)]
+pub struct Latency {
+    pub samples: u32,
+    /// TCP connect to the SSAP port: the network alone.
+    pub network_ms: f64,
+    /// An SSAP request and its reply: network plus the TV's processing.
+    pub ssap_ms: f64,
+    /// WebSocket ping/pong on the input socket that carries button presses; None without one.
+    pub input_ms: Option<f64>,
+    /// Time the measurement waited behind other commands in the app's queue.
+    pub queue_ms: f64,
+}
+
+impl Latency {
+    /// Medians of the measured rounds.
+    pub fn from_samples(
+        samples: u32,
+        queue_wait: std::time::Duration,
+        network: Vec<std::time::Duration>,
+        ssap: Vec<std::time::Duration>,
+        input: Vec<std::time::Duration>,
+    ) -> Self {
+        let median = |mut values: Vec<std::time::Duration>| {
+            values.sort();
+            values.get(values.len() / 2).map(|d| d.as_secs_f64() * 1000.0)
+        };
+        Self {
+            samples,
+            network_ms: median(network).unwrap_or_default(),
+            ssap_ms: median(ssap).unwrap_or_default(),
+            input_ms: median(input),
+            queue_ms: queue_wait.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+/// The addresses and sockets a latency measurement needs, from `TvConnection::latency_probe`.
+pub struct LatencyProbe {
+    host: String,
+    port: u16,
+    input_ws: Option<Arc<Mutex<WsStream>>>,
+    timeouts: TimeoutConfig,
+}
+
+impl LatencyProbe {
+    /// Time a TCP connect to the SSAP port.
+    pub async fn network(&self) -> Result<std::time::Duration, AppError> {
+        let start = std::time::Instant::now();
+        tokio::time::timeout(
+            self.timeouts.connect_timeout(),
+            TcpStream::connect((self.host.as_str(), self.port)),
+        )
+        .await
+        .map_err(|_| AppError::catalog("connection_timeout", &[]))?
+        .map_err(|_| AppError::catalog("tv_unreachable", &[]))?;
+        Ok(start.elapsed())
+    }
+
+    /// Time a WebSocket ping on the input socket; None without one or when no pong comes.
+    pub async fn input_ping(&self) -> Option<std::time::Duration> {
+        let mut ws = self.input_ws.as_ref()?.lock().await;
+        let start = std::time::Instant::now();
+        tokio::time::timeout(self.timeouts.command_timeout(), async {
+            ws.send(Message::Ping(Default::default())).await.ok()?;
+            while let Some(Ok(message)) = ws.next().await {
+                if let Message::Pong(_) = message {
+                    return Some(());
+                }
+            }
+            None
+        })
+        .await
+        .ok()??;
+        Some(start.elapsed())
+    }
+}
+
 pub struct TvConnection {
-    ws: Option<Arc<Mutex<WsStream>>>,
+    ws: Option<Arc<Mutex<WsSink>>>,
+    router: Arc<std::sync::Mutex<Router>>,
     input_ws: Option<Arc<Mutex<WsStream>>>,
     msg_id: u32,
     pub connected: bool,
+    pub power_state: PowerState,
+    /// As configured: IPv4/IPv6 literal or hostname.
     pub ip: String,
+    /// Address `ip` resolved to for the current session.
+    pub resolved_ip: Option<IpAddr>,
     pub name: String,
     pub use_ssl: bool,
+    /// SSAP port, when not the default for `use_ssl` (a mock TV on localhost).
+    pub port: Option<u16>,
+    /// Background tasks for this session; cancelled on disconnect/reconnect.
+    pub tasks: SessionTasks,
+    /// Survives disconnects so quick reconnects can resume.
+    session_ack: Option<SessionAck>,
+    /// Why the TV refused the pointer input socket, if it did. Buttons then fall back to
+    /// SSAP requests where one exists.
+    input_refused: Option<String>,
+    /// SHA-256 fingerprint of the certificate the TV presented on the last connect (wss only).
+    pub cert_fingerprint: Option<String>,
+    /// Probing results for this TV; loaded from and saved to `TvConfig::capabilities`.
+    pub capabilities: TvCapabilities,
+    /// Another remote app took over the session (see `detect_session_replaced`). Cleared by
+    /// the next connect.
+    pub session_replaced: bool,
+    /// Between `start_connect` and `finish_connect`: the socket is open but the TV hasn't
+    /// accepted us yet, so requests are refused.
+    registering: bool,
+    /// Frame recorder, shared with the connection manager so it can be toggled and exported
+    /// without queueing a job.
+    pub trace: Arc<ProtocolTrace>,
+    /// Connect and command timeouts (from `Config::timeouts`).
+    pub timeouts: TimeoutConfig,
+}
+
+impl Default for TvConnection {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TvConnection {
     pub fn new() -> Self {
         Self {
             ws: None,
+            router: Arc::new(std::sync::Mutex::new(Router::default())),
             input_ws: None,
             msg_id: 0,
             connected: false,
+            power_state: PowerState::Unknown,
             ip: String::new(),
+            resolved_ip: None,
             name: String::new(),
             use_ssl: true,
+            port: None,
+            tasks: SessionTasks::new(),
+            session_ack: None,
+            input_refused: None,
+            cert_fingerprint: None,
+            capabilities: TvCapabilities::default(),
+            session_replaced: false,
+            registering: false,
+            trace: Arc::new(ProtocolTrace::default()),
+            timeouts: TimeoutConfig::default(),
         }
     }
 
-    fn handshake_payload(client_key: Option<&str>) -> Value {
+    /// Port of the TV's SSAP socket.
+    pub fn ssap_port(&self) -> u16 {
+        self.port.unwrap_or(if self.use_ssl { 3001 } else { 3000 })
+    }
+
+    /// True if the TV acknowledged `client_key` at `ip` recently enough to resume.
+    fn can_resume(&self, ip: &str, client_key: &str) -> bool {
+        self.session_ack.as_ref().is_some_and(|ack| {
+            ack.ip == ip
+                && ack.client_key == client_key
+                && ack.at.elapsed() < SESSION_RESUME_WINDOW
+        })
+    }
+
+    fn handshake_payload(client_key: Option<&str>, pin_pairing: bool) -> Value {
         let mut payload = json!({
             "type": "register",
             "id": "register_0",
             "payload": {
                 "forcePairing": false,
-                "pairingType": "PROMPT",
+                "pairingType": if pin_pairing { "PIN" } else { "PROMPT" },
                 "manifest": {
                     "manifestVersion": 1,
                     "appVersion": "1.1",
@@ -131,14 +1024,15 @@ impl TvConnection {
         payload
     }
 
-    async fn connect_ws(uri: &str, use_ssl: bool) -> Result<WsStream, String> {
+    /// Open a WebSocket to the TV. TVs use self-signed certificates, so TLS accepts any
+    /// certificate and instead checks it against `pinned` (trust on first use) when set.
+    async fn connect_ws(
+        uri: &str,
+        use_ssl: bool,
+        pinned: Option<&str>,
+    ) -> Result<WsStream, AppError> {
         if use_ssl {
-            let connector = TlsConnector::builder()
-                .danger_accept_invalid_certs(true)
-                .danger_accept_invalid_hostnames(true)
-                .build()
-                .map_err(|e| format!("TLS error: {}", e))?;
-
+            let connector = tls_connector().map_err(|e| format!("TLS error: {}", e))?;
             let connector = tokio_tungstenite::Connector::NativeTls(connector);
 
             let (ws, _) = tokio_tungstenite::connect_async_tls_with_config(
@@ -150,6 +1044,17 @@ impl TvConnection {
             .await
             .map_err(|e| format!("WebSocket connection failed: {}", e))?;
 
+            if let Some(pinned) = pinned {
+                let actual = cert_fingerprint(&ws)
+                    .ok_or_else(|| AppError::ConnectionLost("TV presented no TLS certificate".into()))?;
+                if !actual.eq_ignore_ascii_case(pinned) {
+                    return Err(AppError::Other(format!(
+                        "TV certificate changed (expected {}, got {}). If the TV was reset or \
+                         replaced, reset its certificate pin in settings.",
+                        pinned, actual
+                    ).into()));
+                }
+            }
             Ok(ws)
         } else {
             let (ws, _) = tokio_tungstenite::connect_async(uri)
@@ -159,286 +1064,1105 @@ impl TvConnection {
         }
     }
 
-    pub async fn connect(
+    /// Open the SSAP socket and send the handshake. The TV's answer may take until the user
+    /// accepts the prompt or types the PIN, so wait for it outside the connection queue with
+    /// `PendingRegistration::wait`, then pass both to `finish_connect`. Until then the
+    /// connection counts as connected (so nothing reconnects over the pairing) but takes no
+    /// requests.
+    pub async fn start_connect(
         &mut self,
         name: &str,
         ip: &str,
         client_key: Option<&str>,
         use_ssl: bool,
-    ) -> Result<CommandResult, String> {
+        pinned_cert: Option<&str>,
+        pins: Option<mpsc::Receiver<PinSubmission>>,
+    ) -> Result<PendingRegistration, AppError> {
         self.disconnect().await;
 
         self.name = name.to_string();
         self.ip = ip.to_string();
         self.use_ssl = use_ssl;
+        self.session_replaced = false;
+
+        // Resuming a session the TV acknowledged moments ago: it was reachable and trusted our
+        // key, so skip the reachability probe and don't wait for the ack before sending the
+        // first requests. The ack is still checked before reporting success.
+        let resume = client_key.is_some_and(|key| self.can_resume(ip, key));
+
+        // `ip` may also be a hostname (e.g. lgtv.lan) or an IPv6 literal; resolve it now so a
+        // DHCP hostname follows the TV's current lease
+        let addr = resolve(ip, self.ssap_port())
+            .await
+            .ok_or_else(|| AppError::catalog("host_not_found", &[("host", ip)]))?;
+        if addr.ip().to_string() != ip {
+            log::debug!("{} resolved to {}", ip, addr.ip());
+        }
+        self.resolved_ip = Some(addr.ip());
+
+        // Fail fast when the TV is off instead of waiting out the WebSocket timeout
+        if !resume && !probe_port(&addr.ip().to_string(), addr.port()).await {
+            return Err(AppError::catalog("tv_unreachable", &[]));
+        }
 
         let protocol = if use_ssl { "wss" } else { "ws" };
-        let port = if use_ssl { 3001 } else { 3000 };
-        let uri = format!("{}://{}:{}", protocol, ip, port);
+        // SocketAddr puts IPv6 addresses in brackets, as URIs need
+        let uri = format!("{}://{}", protocol, addr);
 
         let ws = tokio::time::timeout(
-            std::time::Duration::from_secs(5),
-            Self::connect_ws(&uri, use_ssl),
+            self.timeouts.connect_timeout(),
+            Self::connect_ws(&uri, use_ssl, pinned_cert),
         )
         .await
-        .map_err(|_| "Connection timeout".to_string())?
-        .map_err(|e| e.to_string())?;
-
-        let ws = Arc::new(Mutex::new(ws));
-        self.ws = Some(ws.clone());
-
-        // Send handshake
-        let handshake = Self::handshake_payload(client_key);
-        {
-            let mut ws = ws.lock().await;
-            ws.send(Message::Text(handshake.to_string().into()))
-                .await
-                .map_err(|e| format!("Failed to send handshake: {}", e))?;
+        .map_err(|_| AppError::catalog("connection_timeout", &[]))??;
+        self.cert_fingerprint = cert_fingerprint(&ws);
+
+        // The session reader task owns the read half from the start: the registration reply
+        // and PIN results are routed by id like everything else, so nothing here depends on
+        // which frame happens to arrive next. The TV may send a pairing "response" before
+        // "registered", so listen like a subscription.
+        let router = Arc::new(std::sync::Mutex::new(Router::default()));
+        let registration = router.lock().unwrap().add_subscription("register_0")?;
+        let pin_results = router.lock().unwrap().add_subscription("pin_0")?;
+        if resume {
+            router.lock().unwrap().subscriptions.remove("pin_0");
+        }
+        self.start_session(ws, router);
+        self.registering = true;
+
+        let ws = self.ws.clone().ok_or_else(|| AppError::catalog("not_connected", &[]))?;
+        let handshake = Self::handshake_payload(client_key, pins.is_some());
+        if let Err(e) = send_frame(&ws, &self.trace, &handshake).await {
+            self.disconnect().await;
+            return Err(AppError::ConnectionLost(format!("Failed to send handshake: {}", e).into()));
         }
 
-        // Wait for registration response
-        let timeout_secs = if client_key.is_some() { 5 } else { 60 };
-        let response = tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), async {
-            loop {
-                let msg = {
-                    let mut ws = ws.lock().await;
-                    ws.next().await
-                };
-
-                match msg {
-                    Some(Ok(Message::Text(text))) => {
-                        if let Ok(data) = serde_json::from_str::<Value>(&text) {
-                            if data["type"] == "registered" {
-                                let new_key = data["payload"]["client-key"]
-                                    .as_str()
-                                    .map(|s| s.to_string());
-                                return Ok(new_key);
-                            } else if data["type"] == "error" {
-                                return Err(format!(
-                                    "Registration error: {}",
-                                    data["error"].as_str().unwrap_or("Unknown")
-                                ));
-                            }
-                            // Keep waiting for other message types (like pairing prompts)
-                        }
-                    }
-                    Some(Ok(_)) => continue,
-                    Some(Err(e)) => return Err(format!("WebSocket error: {}", e)),
-                    None => return Err("Connection closed".to_string()),
+        // A resumed key is all but certain to be accepted, so don't spend a round trip each
+        // on the power state and the input socket after the TV confirms it: ask now and let
+        // the replies arrive along with the confirmation
+        let mut early = None;
+        if resume {
+            let sent = async {
+                Ok::<_, AppError>(EarlyRequests {
+                    power_state: self.request_on(&ws, POWER_STATE_URI, None).await?,
+                    input_socket: self.request_on(&ws, INPUT_SOCKET_URI, None).await?,
+                })
+            };
+            match sent.await {
+                Ok(requests) => early = Some(requests),
+                Err(e) => {
+                    self.disconnect().await;
+                    return Err(e);
                 }
             }
+        }
+
+        // With PIN pairing the TV shows a PIN instead of a prompt; the user types it into the
+        // app and it arrives through `pins`.
+        let timeout = match (client_key, &pins) {
+            (Some(_), _) => self.timeouts.connect_timeout(),
+            (None, Some(_)) => std::time::Duration::from_secs(120),
+            (None, None) => std::time::Duration::from_secs(60),
+        };
+        Ok(PendingRegistration {
+            registration,
+            pin_results,
+            pins,
+            ws,
+            router: self.router.clone(),
+            trace: self.trace.clone(),
+            timeout,
+            resume,
+            client_key: client_key.map(str::to_string),
+            pinned_cert: pinned_cert.map(str::to_string),
+            early,
+            early_replies: None,
         })
+    }
+
+    /// Start over with a full handshake after `pending`, a resume, failed: the TV may have
+    /// forgotten the session or the key. Fails if a disconnect or another connect replaced
+    /// the session meanwhile.
+    pub async fn restart_connect(
+        &mut self,
+        pending: PendingRegistration,
+    ) -> Result<PendingRegistration, AppError> {
+        if !self.registering || !Arc::ptr_eq(&self.router, &pending.router) {
+            return Err(AppError::catalog("connection_closed", &[]));
+        }
+        self.session_ack = None;
+        let (name, ip, use_ssl) = (self.name.clone(), self.ip.clone(), self.use_ssl);
+        self.start_connect(
+            &name,
+            &ip,
+            pending.client_key.as_deref(),
+            use_ssl,
+            pending.pinned_cert.as_deref(),
+            None,
+        )
         .await
-        .map_err(|_| "Registration timeout - check TV for pairing prompt".to_string())?;
+    }
 
-        let new_key = response?;
-        self.connected = true;
+    /// Finish a connect once `start_connect`'s registration has been waited for: read the
+    /// power state, open the input socket and remember the ack for quick reconnects. Fails
+    /// without touching the connection when a disconnect or another connect replaced the
+    /// session during the wait.
+    pub async fn finish_connect(
+        &mut self,
+        pending: PendingRegistration,
+        registered: Result<Option<String>, AppError>,
+    ) -> Result<CommandResult, AppError> {
+        if !self.registering || !Arc::ptr_eq(&self.router, &pending.router) {
+            return registered.and(Err(AppError::catalog("connection_closed", &[])));
+        }
+        self.registering = false;
+        let new_key = match registered {
+            Ok(key) => key,
+            Err(e) => {
+                if pending.resume {
+                    // Key no longer trusted (or the TV went away): next connect does a full
+                    // handshake
+                    self.session_ack = None;
+                }
+                self.disconnect().await;
+                return Err(e);
+            }
+        };
+        if pending.resume {
+            log::debug!("Resumed session with {}", self.ip);
+        }
 
-        // Connect input socket for button commands
-        if let Err(e) = self.connect_input_socket().await {
+        // Quick Start+ TVs accept the connection while in standby; remember that so we
+        // only allow power commands until the panel is turned back on. A resume asked for
+        // this and the input socket with the handshake.
+        let (power_state, input_socket) = match pending.early_replies {
+            Some(replies) => (
+                replies.power_state.map(|reply| self.set_power_state(&reply)),
+                match replies.input_socket {
+                    Ok(reply) => self.open_input_socket_from(&reply).await,
+                    Err(e) => Err(e),
+                },
+            ),
+            None => (
+                self.get_power_state().await,
+                self.connect_input_socket().await,
+            ),
+        };
+        match power_state {
+            Ok(state) => log::debug!("Power state after connect: {:?}", state),
+            Err(e) => log::debug!("getPowerState failed: {}", e),
+        }
+        if let Err(e) = input_socket {
             log::warn!("Could not connect input socket: {}", e);
         }
 
-        let mut result = CommandResult::ok_with_message("Connected");
+        if let Some(key) = new_key.as_ref().or(pending.client_key.as_ref()) {
+            self.session_ack = Some(SessionAck {
+                ip: self.ip.clone(),
+                client_key: key.clone(),
+                at: std::time::Instant::now(),
+            });
+        }
+
+        let mut result = if self.power_state == PowerState::Standby {
+            CommandResult::ok_with_message("Connected (TV in standby)")
+        } else {
+            CommandResult::ok_with_message("Connected")
+        };
         result.client_key = new_key;
         Ok(result)
     }
 
-    async fn connect_input_socket(&mut self) -> Result<(), String> {
-        let response = self.send_command(
-            "ssap://com.webos.service.networkinput/getPointerInputSocket",
-            None,
-        ).await?;
+    /// From the handshake on, a session reader task owns the read half and routes replies
+    /// and subscription pushes by id.
+    fn start_session(&mut self, ws: WsStream, router: Arc<std::sync::Mutex<Router>>) {
+        let (sink, stream) = ws.split();
+        let sink = Arc::new(Mutex::new(sink));
+        self.tasks.spawn(read_loop(
+            stream,
+            sink.clone(),
+            router.clone(),
+            self.trace.clone(),
+            self.timeouts,
+        ));
+        self.router = router;
+        self.ws = Some(sink);
+        self.connected = true;
+    }
 
-        let socket_path = response["payload"]["socketPath"]
-            .as_str()
-            .ok_or("No socket path in response")?;
+    /// Why the session's reader task stopped, if it has; the SSAP socket is unusable then.
+    pub fn link_error(&self) -> Option<AppError> {
+        let router = self.router.lock().unwrap();
+        if !router.closed {
+            None
+        } else if router.pong_timed_out {
+            Some(AppError::catalog("pong_timeout", &[]))
+        } else {
+            Some(AppError::catalog("connection_closed", &[]))
+        }
+    }
 
-        let ws = Self::connect_ws(socket_path, self.use_ssl).await?;
-        self.input_ws = Some(Arc::new(Mutex::new(ws)));
-        Ok(())
+    /// Notified when the session's reader task stops.
+    pub fn link_down(&self) -> Arc<Notify> {
+        self.router.lock().unwrap().down.clone()
     }
 
-    /// Refresh the input socket (used for d-pad, enter, back, etc.). The TV can close
-    /// this socket while the main SSAP socket stays open; we don't ping it, so
-    /// reconnect it periodically so button commands keep working.
-    pub async fn refresh_input_socket(&mut self) -> Result<(), String> {
-        if let Some(old) = self.input_ws.take() {
-            let _ = old.lock().await.close(None).await;
-        }
-        self.connect_input_socket().await
+    /// Connect the input socket for button commands.
+    async fn connect_input_socket(&mut self) -> Result<(), AppError> {
+        let response = self.send_command(INPUT_SOCKET_URI, None).await?;
+        self.open_input_socket_from(&response).await
+    }
+
+    /// Connect the input socket a `getPointerInputSocket` reply points to.
+    async fn open_input_socket_from(&mut self, response: &Value) -> Result<(), AppError> {
+        let socket_path = self.input_socket_path(response)?;
+        let ws =
+            Self::connect_ws(&socket_path, self.use_ssl, self.cert_fingerprint.as_deref()).await?;
+        self.input_ws = Some(Arc::new(Mutex::new(ws)));
+        Ok(())
+    }
+
+    /// The socket path from a `getPointerInputSocket` reply.
+    pub fn input_socket_path(&mut self, response: &Value) -> Result<String, AppError> {
+        // Some TVs/firmwares deny this to clients without the right permissions; remember it
+        // so buttons degrade to SSAP instead of failing (and dropping the connection).
+        if response["type"] == "error" || response["payload"]["returnValue"] == false {
+            let reason = ssap_error::from_response(&response, "refused");
+            self.input_refused = Some(reason.clone());
+            return Err(AppError::Tv(format!("TV refused the input socket: {}", reason).into()));
+        }
+        self.input_refused = None;
+
+        let socket_path = response["payload"]["socketPath"]
+            .as_str()
+            .ok_or_else(|| AppError::Tv("No socket path in response".into()))?;
+        Ok(socket_path.to_string())
+    }
+
+    /// Open the input socket at `socket_path`; needs no access to the connection.
+    pub async fn open_input_socket(
+        socket_path: &str,
+        use_ssl: bool,
+        pinned: Option<String>,
+    ) -> Result<InputSocket, AppError> {
+        Self::connect_ws(socket_path, use_ssl, pinned.as_deref()).await.map(InputSocket)
+    }
+
+    /// Replace the input socket with one opened by `open_input_socket`. Dropped instead if
+    /// the session ended while it was being opened.
+    pub async fn install_input_socket(&mut self, socket: InputSocket) {
+        let mut ws = socket.0;
+        if !self.connected {
+            let _ = ws.close(None).await;
+            return;
+        }
+        if let Some(old) = self.input_ws.replace(Arc::new(Mutex::new(ws))) {
+            let _ = old.lock().await.close(None).await;
+        }
     }
 
     pub async fn disconnect(&mut self) {
+        self.tasks.cancel_all();
         self.connected = false;
+        self.registering = false;
+        self.power_state = PowerState::Unknown;
         if let Some(ws) = self.input_ws.take() {
             let _ = ws.lock().await.close(None).await;
         }
+        self.router.lock().unwrap().close();
         if let Some(ws) = self.ws.take() {
-            let _ = ws.lock().await.close(None).await;
+            let _ = ws.lock().await.close().await;
         }
     }
 
-    pub async fn send_command(&mut self, uri: &str, payload: Option<Value>) -> Result<Value, String> {
-        let ws = self.ws.as_ref().ok_or("Not connected")?;
+    /// After the session ended unexpectedly, tell whether another remote app took it over:
+    /// the TV itself closed our socket while it was on and it still answers on its SSAP port,
+    /// at the address the session connected to (a hostname may resolve elsewhere by now).
+    /// Sets `session_replaced` if so.
+    pub async fn detect_session_replaced(&mut self) -> bool {
+        if !self.session_replaced {
+            let closed_by_tv = self.router.lock().unwrap().closed_by_tv;
+            let ip = self.resolved_ip.map_or_else(|| self.ip.clone(), |ip| ip.to_string());
+            self.session_replaced = closed_by_tv
+                && self.power_state == PowerState::On
+                && probe_port(&ip, self.ssap_port()).await;
+        }
+        if self.session_replaced {
+            self.connected = false;
+        }
+        self.session_replaced
+    }
+
+    /// The SSAP socket, once the TV has accepted the handshake.
+    fn session_sink(&self) -> Result<Arc<Mutex<WsSink>>, AppError> {
+        match &self.ws {
+            Some(ws) if !self.registering => Ok(ws.clone()),
+            _ => Err(AppError::catalog("not_connected", &[])),
+        }
+    }
+
+    /// Err unless connected with the panel on. In Quick Start+ standby only power commands work.
+    pub fn ensure_active(&self) -> Result<(), AppError> {
+        if !self.connected || self.registering {
+            return Err(AppError::catalog("not_connected", &[]));
+        }
+        if self.power_state == PowerState::Standby {
+            return Err(AppError::catalog("tv_standby", &[]));
+        }
+        Ok(())
+    }
+
+    pub async fn send_command(&mut self, uri: &str, payload: Option<Value>) -> Result<Value, AppError> {
+        let result = self.start_request(uri, payload).await?.wait().await;
+        if let Err(e) = &result {
+            self.request_failed(e);
+        }
+        result
+    }
 
+    /// Send an SSAP request and return its reply handle without waiting for it, so the wait
+    /// can happen outside the connection queue. Pass a failed reply to `request_failed`.
+    pub async fn start_request(
+        &mut self,
+        uri: &str,
+        payload: Option<Value>,
+    ) -> Result<PendingReply, AppError> {
+        let ws = self.session_sink()?;
+        self.request_on(&ws, uri, payload).await
+    }
+
+    /// `start_request` on `ws`, which may still be registering.
+    async fn request_on(
+        &mut self,
+        ws: &Arc<Mutex<WsSink>>,
+        uri: &str,
+        payload: Option<Value>,
+    ) -> Result<PendingReply, AppError> {
         self.msg_id += 1;
+        let id = format!("cmd_{}", self.msg_id);
         let msg = json!({
             "type": "request",
-            "id": format!("cmd_{}", self.msg_id),
+            "id": id,
             "uri": uri,
             "payload": payload.unwrap_or(json!({}))
         });
 
-        let mut ws = ws.lock().await;
-        if let Err(e) = ws.send(Message::Text(msg.to_string().into())).await {
+        let reply = match self.router.lock().unwrap().add_request(&id) {
+            Ok(rx) => rx,
+            Err(e) => {
+                self.connected = false;
+                return Err(e);
+            }
+        };
+        let text = msg.to_string();
+        self.trace.record(Direction::Sent, "ssap", &text);
+        if let Err(e) = ws.lock().await.send(Message::Text(text.into())).await {
+            self.router.lock().unwrap().pending.remove(&id);
             self.connected = false;
-            return Err(format!("Send failed (disconnected): {}", e));
+            return Err(AppError::catalog("send_failed", &[("error", &e.to_string())]));
         }
 
-        // Wait for response
-        let response = tokio::time::timeout(std::time::Duration::from_secs(3), async {
-            while let Some(msg) = ws.next().await {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        if let Ok(data) = serde_json::from_str::<Value>(&text) {
-                            return Ok(data);
-                        }
-                    }
-                    Ok(_) => continue,
-                    Err(e) => return Err(format!("WebSocket error: {}", e)),
-                }
-            }
-            Err("Connection closed".to_string())
+        Ok(PendingReply {
+            id,
+            reply,
+            timeout: self.timeouts.command_timeout(),
+            router: self.router.clone(),
         })
-        .await;
+    }
 
-        match response {
-            Ok(Ok(data)) => Ok(data),
-            Ok(Err(e)) => {
-                self.connected = false;
-                Err(e)
-            }
-            Err(_) => {
-                // Timeout - connection may be dead
+    /// Update the connection state for a request that failed while its reply was awaited:
+    /// a timeout or closed socket means the session is dead, a "not registered" reply that
+    /// another app took it over.
+    pub fn request_failed(&mut self, error: &AppError) {
+        match error {
+            AppError::SessionReplaced(_) => {
+                self.session_replaced = true;
                 self.connected = false;
-                Err("Command timeout (disconnected)".to_string())
             }
+            AppError::ConnectionLost(_) | AppError::Timeout(_) => self.connected = false,
+            _ => {}
         }
     }
 
-    pub async fn send_button(&mut self, button: &str) -> Result<CommandResult, String> {
+    /// Subscribe to an SSAP URI. The TV replies with the current value and then pushes a
+    /// message with the same id on every change; all of them arrive on the returned channel,
+    /// which ends when the connection closes.
+    pub async fn subscribe(
+        &mut self,
+        uri: &str,
+        payload: Option<Value>,
+    ) -> Result<mpsc::UnboundedReceiver<Value>, AppError> {
+        self.subscribe_with_id(uri, payload).await.map(|(_, rx)| rx)
+    }
+
+    /// Like `subscribe`, also returning the subscription id for `unsubscribe`.
+    pub async fn subscribe_with_id(
+        &mut self,
+        uri: &str,
+        payload: Option<Value>,
+    ) -> Result<(String, mpsc::UnboundedReceiver<Value>), AppError> {
+        let ws = self.session_sink()?;
+
+        self.msg_id += 1;
+        let id = format!("sub_{}", self.msg_id);
+        let msg = json!({
+            "type": "subscribe",
+            "id": id,
+            "uri": uri,
+            "payload": payload.unwrap_or(json!({}))
+        });
+
+        let rx = self.router.lock().unwrap().add_subscription(&id)?;
+        let text = msg.to_string();
+        self.trace.record(Direction::Sent, "ssap", &text);
+        if let Err(e) = ws.lock().await.send(Message::Text(text.into())).await {
+            self.router.lock().unwrap().subscriptions.remove(&id);
+            self.connected = false;
+            return Err(AppError::ConnectionLost(format!(
+                "Subscribe failed (disconnected): {}",
+                e
+            ).into()));
+        }
+        Ok((id, rx))
+    }
+
+    /// Frames from the TV that no request or subscription is waiting for, for the rest of
+    /// this session. Replaces an earlier receiver.
+    pub fn unsolicited_messages(&mut self) -> mpsc::UnboundedReceiver<Value> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.router.lock().unwrap().unsolicited = Some(tx);
+        rx
+    }
+
+    /// Stop a subscription: the TV stops pushing and the subscriber's channel ends.
+    pub async fn unsubscribe(&mut self, id: &str) -> Result<(), AppError> {
+        self.router.lock().unwrap().subscriptions.remove(id);
+        let ws = self.session_sink()?;
+        let msg = json!({ "type": "unsubscribe", "id": id }).to_string();
+        self.trace.record(Direction::Sent, "ssap", &msg);
+        ws.lock()
+            .await
+            .send(Message::Text(msg.into()))
+            .await
+            .map_err(|e| AppError::ConnectionLost(format!("Unsubscribe failed: {}", e).into()))
+    }
+
+    /// Send a raw message on the pointer input socket, reconnecting it first if needed.
+    async fn send_input(&mut self, cmd: String) -> Result<(), AppError> {
         // Reconnect input socket if needed
         if self.input_ws.is_none() {
             if let Err(e) = self.connect_input_socket().await {
+                if self.input_refused.is_some() {
+                    return Err(e);
+                }
                 self.connected = false;
-                return Err(format!("Failed to connect input socket: {}", e));
+                return Err(AppError::ConnectionLost(format!(
+                    "Failed to connect input socket: {}",
+                    e
+                ).into()));
             }
         }
 
-        let input_ws = self.input_ws.as_ref().ok_or("Input socket not available")?;
-        let cmd = format!("type:button\nname:{}\n\n", button.to_uppercase());
+        let input_ws = self
+            .input_ws
+            .as_ref()
+            .ok_or_else(|| AppError::ConnectionLost("Input socket not available".into()))?;
 
+        self.trace.record(Direction::Sent, "input", &cmd);
         let mut ws = input_ws.lock().await;
         if let Err(e) = ws.send(Message::Text(cmd.into())).await {
             // Input socket died, clear it so we reconnect next time
             drop(ws);
             self.input_ws = None;
             self.connected = false;
-            return Err(format!("Button send failed (disconnected): {}", e));
+            return Err(AppError::catalog("input_send_failed", &[("error", &e.to_string())]));
         }
 
+        Ok(())
+    }
+
+    /// Send an SSAP request and treat `type: "error"` / `returnValue: false` responses as errors.
+    pub async fn request(&mut self, uri: &str, payload: Option<Value>) -> Result<Value, AppError> {
+        let response = self.send_command(uri, payload).await?;
+        if response["type"] == "error" || response["payload"]["returnValue"] == false {
+            return Err(AppError::Tv(ssap_error::from_response(&response, "Request failed").into()));
+        }
+        Ok(response)
+    }
+
+    pub async fn send_button(&mut self, button: &str) -> Result<CommandResult, AppError> {
+        if let Some(reason) = self.input_refused.clone() {
+            let uri = buttons::ssap_fallback(button).ok_or_else(|| {
+                format!(
+                    "{} is unavailable: the TV refused remote button input ({})",
+                    button.to_uppercase(),
+                    reason
+                )
+            })?;
+            self.request(uri, None).await?;
+            return Ok(CommandResult::ok());
+        }
+        let cmd = format!("type:button\nname:{}\n\n", button.to_uppercase());
+        self.send_input(cmd).await?;
         Ok(CommandResult::ok())
     }
 
-    pub async fn volume_up(&mut self) -> Result<CommandResult, String> {
+    /// Whether buttons go through the pointer input socket, and which ones are unavailable
+    /// when the TV refused it.
+    pub fn input_capabilities(&self) -> InputCapabilities {
+        InputCapabilities {
+            input_socket: self.input_refused.is_none(),
+            reason: self.input_refused.clone(),
+            unavailable_buttons: if self.input_refused.is_some() {
+                buttons::unavailable_without_input_socket()
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    /// Press a button without releasing it. Pair with `button_up`; webOS treats the time in
+    /// between as a press-and-hold (e.g. holding OK opens context menus).
+    pub async fn button_down(&mut self, button: &str) -> Result<CommandResult, AppError> {
+        let cmd = format!("type:button\nname:{}\nstate:down\n\n", button.to_uppercase());
+        self.send_input(cmd).await?;
+        Ok(CommandResult::ok())
+    }
+
+    pub async fn button_up(&mut self, button: &str) -> Result<CommandResult, AppError> {
+        let cmd = format!("type:button\nname:{}\nstate:up\n\n", button.to_uppercase());
+        self.send_input(cmd).await?;
+        Ok(CommandResult::ok())
+    }
+
+    /// Bring up the TV's on-screen number pad / screen remote, like the Magic Remote's 123
+    /// (MORE) button, for apps and set-top contexts that expect the virtual keypad.
+    pub async fn show_keypad(&mut self) -> Result<CommandResult, AppError> {
+        self.send_button("SCREEN_REMOTE").await
+    }
+
+    /// Scroll by `dy` steps via the pointer input socket (mouse wheel). Positive scrolls down.
+    pub async fn send_scroll(&mut self, dy: i32) -> Result<CommandResult, AppError> {
+        let cmd = format!("type:scroll\ndx:0\ndy:{}\n\n", dy);
+        self.send_input(cmd).await?;
+        Ok(CommandResult::ok())
+    }
+
+    pub async fn volume_up(&mut self) -> Result<CommandResult, AppError> {
         self.send_command("ssap://audio/volumeUp", None).await?;
         Ok(CommandResult::ok())
     }
 
-    pub async fn volume_down(&mut self) -> Result<CommandResult, String> {
+    pub async fn volume_down(&mut self) -> Result<CommandResult, AppError> {
         self.send_command("ssap://audio/volumeDown", None).await?;
         Ok(CommandResult::ok())
     }
 
-    pub async fn set_mute(&mut self, mute: bool) -> Result<CommandResult, String> {
+    /// Move the volume by `steps` (negative is down) with one setVolume instead of one
+    /// volumeUp/volumeDown per step.
+    pub async fn change_volume(&mut self, steps: i64) -> Result<CommandResult, AppError> {
+        match steps {
+            0 => return Ok(CommandResult::ok()),
+            1 => return self.volume_up().await,
+            -1 => return self.volume_down().await,
+            _ => {}
+        }
+        let response = self.request("ssap://audio/getVolume", None).await?;
+        let current = VolumeStatus::from_payload(&response["payload"])
+            .and_then(|status| status.volume)
+            .ok_or_else(|| AppError::Tv("TV did not report its volume".into()))?;
+        let volume = (current as i64 + steps).clamp(0, 100);
+        self.send_command("ssap://audio/setVolume", Some(json!({ "volume": volume })))
+            .await?;
+        Ok(CommandResult::ok())
+    }
+
+    pub async fn set_mute(&mut self, mute: bool) -> Result<CommandResult, AppError> {
         self.send_command("ssap://audio/setMute", Some(json!({ "mute": mute }))).await?;
         Ok(CommandResult::ok())
     }
 
-    pub async fn power_off(&mut self) -> Result<CommandResult, String> {
+    pub async fn power_off(&mut self) -> Result<CommandResult, AppError> {
         self.send_command("ssap://system/turnOff", None).await?;
         self.connected = false;
         Ok(CommandResult::ok_with_message("TV powered off"))
     }
 
-    /// Lightweight keepalive to prevent idle connection drops.
-    /// Sends a minimal SSAP request; if it fails, connection is marked disconnected.
-    pub async fn keepalive_ping(&mut self) -> Result<(), String> {
-        let uri = "ssap://com.webos.service.connectionmanager/getinfo";
-        match self.send_command(uri, None).await {
-            Ok(res) => {
-                log::debug!("Keepalive ping response: {:?}", res);
-                Ok(())
+    /// Query the power state and remember it. TVs without the tvpower service report Unknown.
+    pub async fn get_power_state(&mut self) -> Result<PowerState, AppError> {
+        let response = self
+            .send_command(POWER_STATE_URI, None)
+            .await?;
+        Ok(self.set_power_state(&response))
+    }
+
+    /// Remember the power state from a `getPowerState` reply.
+    fn set_power_state(&mut self, response: &Value) -> PowerState {
+        self.power_state = PowerState::from_response(response);
+        self.power_state
+    }
+
+    /// Turn the panel back on from Quick Start+ standby. Tries `preferred` (the method that
+    /// worked last time) first, then tvpower turnOn, turnOnScreen and finally the POWER button.
+    /// Returns the method that worked so the caller can remember it per TV.
+    pub async fn wake_from_standby(
+        &mut self,
+        preferred: Option<PowerOnMethod>,
+    ) -> Result<PowerOnMethod, AppError> {
+        let mut methods = vec![
+            PowerOnMethod::TurnOn,
+            PowerOnMethod::TurnOnScreen,
+            PowerOnMethod::PowerButton,
+        ];
+        if let Some(p) = preferred {
+            methods.retain(|m| *m != p);
+            methods.insert(0, p);
+        }
+
+        let mut last_err = AppError::Other("No standby power-on method available".into());
+        for method in methods {
+            let attempt = match method {
+                PowerOnMethod::TurnOn => self
+                    .request("ssap://com.webos.service.tvpower/power/turnOn", None)
+                    .await
+                    .map(|_| ()),
+                PowerOnMethod::TurnOnScreen => self
+                    .request("ssap://com.webos.service.tvpower/power/turnOnScreen", None)
+                    .await
+                    .map(|_| ()),
+                PowerOnMethod::PowerButton => self.send_button("POWER").await.map(|_| ()),
+            };
+            match attempt {
+                Ok(()) => {
+                    self.power_state = PowerState::On;
+                    return Ok(method);
+                }
+                Err(e) => {
+                    log::debug!("Standby power-on via {:?} failed: {}", method, e);
+                    last_err = e;
+                }
             }
-            Err(e) => {
-                log::debug!("Keepalive ping error: {}", e);
-                Err(e)
+            // A timeout or send failure means the TV went fully off; no point trying the rest
+            if !self.connected {
+                break;
             }
         }
+        Err(last_err)
     }
 
-    pub async fn get_network_info(&mut self) -> Result<Value, String> {
-        // Get MAC addresses from getinfo endpoint
-        self.send_command("ssap://com.webos.service.connectionmanager/getinfo", None).await
+    /// Blank the panel while audio keeps playing.
+    pub async fn screen_off(&mut self) -> Result<CommandResult, AppError> {
+        self.request("ssap://com.webos.service.tvpower/power/turnOffScreen", None)
+            .await?;
+        Ok(CommandResult::ok_with_message("Screen off"))
     }
 
-    /// Get connection status (which interface is connected).
-    /// Tries connectionmanager/getStatus first, then com.webos.service.wifi/getstatus as fallback.
-    fn check_status_response(response: &Value) -> Result<Value, String> {
-        if response.get("error").is_some() {
-            return Err(response["error"]
-                .as_str()
-                .unwrap_or("getStatus failed")
-                .to_string());
-        }
-        Ok(response.clone())
+    pub async fn screen_on(&mut self) -> Result<CommandResult, AppError> {
+        self.request("ssap://com.webos.service.tvpower/power/turnOnScreen", None)
+            .await?;
+        Ok(CommandResult::ok_with_message("Screen on"))
+    }
+
+    /// Start OLED Pixel Cleaning (OLED Care → Pixel Cleaning → Start). The screen goes dark for
+    /// roughly an hour and the TV turns itself off when done. Goes through the luna settings
+    /// path, so it only works on OLED models whose firmware exposes the service.
+    pub async fn start_pixel_cleaning(&mut self) -> Result<CommandResult, AppError> {
+        self.send_luna(
+            "luna://com.webos.service.oledepl/startPixelCleaning",
+            json!({ "mode": "manual" }),
+        )
+        .await?;
+        Ok(CommandResult::ok_with_message(
+            "Pixel cleaning started — the TV turns off when it finishes",
+        ))
+    }
+
+    /// Change system settings in `category`. Current firmware refuses
+    /// `ssap://settings/setSystemSettings` to apps like us, so go to the settings service
+    /// over luna.
+    async fn set_system_settings(
+        &mut self,
+        category: &str,
+        settings: Value,
+    ) -> Result<CommandResult, AppError> {
+        self.send_luna(
+            "luna://com.webos.settingsservice/setSystemSettings",
+            json!({ "category": category, "settings": settings }),
+        )
+        .await
+    }
+
+    pub async fn set_energy_saving(
+        &mut self,
+        level: EnergySavingLevel,
+    ) -> Result<CommandResult, AppError> {
+        self.set_system_settings("picture", json!({ "energySaving": level.as_webos() }))
+            .await?;
+        Ok(CommandResult::ok_with_message(&format!(
+            "Energy saving set to {}",
+            level.as_webos()
+        )))
     }
 
-    pub async fn get_network_status(&mut self) -> Result<Value, String> {
-        // Primary: com.webos.service.connectionmanager/getStatus (wifi + wired state)
-        log::debug!("Trying connectionmanager/getStatus...");
+    /// Current sound mode. `Err` carries the raw webOS value when it isn't one we know
+    /// (e.g. AI Sound on newer models).
+    pub async fn get_sound_mode(&mut self) -> Result<SoundMode, AppError> {
         let response = self
-            .send_command("ssap://com.webos.service.connectionmanager/getStatus", None)
+            .request(
+                "ssap://settings/getSystemSettings",
+                Some(json!({ "category": "sound", "keys": ["soundMode"] })),
+            )
             .await?;
-        if let Ok(status) = Self::check_status_response(&response) {
-            log::debug!("connectionmanager/getStatus succeeded");
-            return Ok(status);
+        let mode = response["payload"]["settings"]["soundMode"]
+            .as_str()
+            .ok_or_else(|| AppError::Tv("TV did not report a sound mode".into()))?;
+        SoundMode::from_webos(mode)
+            .ok_or_else(|| AppError::Tv(format!("Unsupported sound mode: {}", mode).into()))
+    }
+
+    pub async fn set_sound_mode(&mut self, mode: SoundMode) -> Result<CommandResult, AppError> {
+        self.set_system_settings("sound", json!({ "soundMode": mode.as_webos() }))
+            .await?;
+        Ok(CommandResult::ok_with_message(&format!(
+            "Sound mode set to {}",
+            mode.name()
+        )))
+    }
+
+    /// Apply a scene's settings in order: picture mode first, since switching mode resets
+    /// backlight to that mode's value. Stops at the first setting the TV rejects.
+    pub async fn apply_scene(
+        &mut self,
+        name: &str,
+        scene: &Scene,
+    ) -> Result<CommandResult, AppError> {
+        if let Some(mode) = &scene.picture_mode {
+            self.set_system_settings("picture", json!({ "pictureMode": mode }))
+                .await
+                .map_err(|e| e.context(&format!("Picture mode {}", mode)))?;
         }
-        log::debug!("connectionmanager/getStatus failed, trying com.webos.service.wifi/getstatus...");
-        // Fallback: com.webos.service.wifi/getstatus (lowercase per webOS OSE docs)
+        if let Some(backlight) = scene.backlight {
+            let backlight = backlight.min(100);
+            self.set_system_settings("picture", json!({ "backlight": backlight }))
+                .await
+                .map_err(|e| e.context("Backlight"))?;
+        }
+        if let Some(output) = &scene.sound_output {
+            self.request("ssap://audio/changeSoundOutput", Some(json!({ "output": output })))
+                .await
+                .map_err(|e| e.context(&format!("Sound output {}", output)))?;
+        }
+        if let Some(level) = scene.energy_saving {
+            self.set_energy_saving(level)
+                .await
+                .map_err(|e| e.context("Energy saving"))?;
+        }
+        Ok(CommandResult::ok_with_message(&format!("Applied scene {}", name)))
+    }
+
+    /// Launch an app, optionally with launch params for deep links (e.g. Netflix
+    /// `{"contentTarget": "https://www.netflix.com/watch/<id>"}`, YouTube
+    /// `{"contentTarget": "v=<videoId>"}`). Params are passed through as-is.
+    pub async fn launch_app(
+        &mut self,
+        app_id: &str,
+        params: Option<Value>,
+    ) -> Result<CommandResult, AppError> {
+        let mut payload = json!({ "id": app_id });
+        if let Some(params) = params.filter(|p| !p.is_null()) {
+            payload["params"] = params;
+        }
+        self.request("ssap://system.launcher/launch", Some(payload))
+            .await?;
+        Ok(CommandResult::ok_with_message(&format!("Launched {}", app_id)))
+    }
+
+    /// Launch a web app (LAUNCH_WEBAPP permission). An id starts that hosted web app with
+    /// `params` as its URL parameters. A bare http(s) URL has no web app id, so it's opened
+    /// in the TV's web app runtime through the browser app, with `params` merged in.
+    pub async fn launch_webapp(
+        &mut self,
+        url_or_id: &str,
+        params: Option<Value>,
+    ) -> Result<CommandResult, AppError> {
+        let params = params.filter(|p| !p.is_null());
+        if url_or_id.starts_with("http://") || url_or_id.starts_with("https://") {
+            let mut launch_params = params.unwrap_or_else(|| json!({}));
+            if !launch_params.is_object() {
+                return Err(AppError::Other("Web app params must be a JSON object".into()));
+            }
+            launch_params["target"] = json!(url_or_id);
+            self.launch_app("com.webos.app.browser", Some(launch_params))
+                .await?;
+            return Ok(CommandResult::ok_with_message(&format!("Opened {}", url_or_id)));
+        }
+        let mut payload = json!({ "webAppId": url_or_id });
+        if let Some(params) = params {
+            payload["urlParams"] = params;
+        }
+        self.request("ssap://webapp/launchWebApp", Some(payload))
+            .await?;
+        Ok(CommandResult::ok_with_message(&format!("Launched web app {}", url_or_id)))
+    }
+
+    /// Apps installed on the TV, in launcher order.
+    pub async fn list_apps(&mut self) -> Result<Vec<TvApp>, AppError> {
+        let response = self
+            .request("ssap://com.webos.applicationManager/listLaunchPoints", None)
+            .await?;
+        Ok(TvApp::list_from_payload(&response["payload"]))
+    }
+
+    /// External inputs, labelled with `labels` (input id -> label) where set.
+    pub async fn list_inputs(
+        &mut self,
+        labels: &HashMap<String, String>,
+    ) -> Result<Vec<InputSource>, AppError> {
+        let response = self
+            .request("ssap://tv/getExternalInputList", None)
+            .await?;
+        Ok(InputSource::list_from_payload(&response["payload"], labels))
+    }
+
+    pub async fn switch_input(&mut self, input_id: &str) -> Result<CommandResult, AppError> {
+        self.request(
+            "ssap://tv/switchInput",
+            Some(json!({ "inputId": input_id })),
+        )
+        .await?;
+        Ok(CommandResult::ok())
+    }
+
+    /// Paired Bluetooth audio devices (headphones, soundbars) and which one is connected.
+    pub async fn list_bluetooth_devices(&mut self) -> Result<Vec<BluetoothDevice>, AppError> {
         let response = self
-            .send_command("ssap://com.webos.service.wifi/getstatus", None)
+            .request("ssap://com.webos.service.bluetooth2/device/getStatus", None)
             .await?;
-        if let Ok(status) = Self::check_status_response(&response) {
-            log::debug!("com.webos.service.wifi/getstatus succeeded");
-            return Ok(status);
+        Ok(BluetoothDevice::list_from_payload(&response["payload"]))
+    }
+
+    /// Connect (or disconnect) a paired Bluetooth audio device. Goes through luna, so the TV
+    /// doesn't report whether it worked; list the devices again to see the new state.
+    pub async fn set_bluetooth_connected(
+        &mut self,
+        address: &str,
+        connect: bool,
+    ) -> Result<CommandResult, AppError> {
+        let uri = if connect {
+            "luna://com.webos.service.bluetooth2/a2dp/connect"
+        } else {
+            "luna://com.webos.service.bluetooth2/a2dp/disconnect"
+        };
+        self.send_luna(uri, json!({ "address": address })).await?;
+        Ok(CommandResult::ok_with_message(if connect {
+            "Connecting Bluetooth device"
+        } else {
+            "Disconnecting Bluetooth device"
+        }))
+    }
+
+    /// Call a `luna://` service, which SSAP doesn't expose directly. Uses the well-known alert
+    /// workaround: create an invisible alert whose close handler is the luna call, then close it
+    /// right away so the TV runs the call with system privileges. Fire-and-forget: the luna
+    /// response is not returned.
+    pub async fn send_luna(&mut self, uri: &str, params: Value) -> Result<CommandResult, AppError> {
+        if !uri.starts_with("luna://") {
+            return Err(AppError::Other(format!("Not a luna:// URI: {}", uri).into()));
         }
-        log::debug!("com.webos.service.wifi/getstatus failed, trying com.palm.wifi/getStatus...");
-        // Some consumer TVs use the palm namespace
+        let handler = json!({ "uri": uri, "params": params });
         let response = self
-            .send_command("ssap://com.palm.wifi/getStatus", None)
+            .request(
+                "ssap://system.notifications/createAlert",
+                Some(json!({
+                    "message": " ",
+                    "buttons": [{ "label": "", "onClick": uri, "params": params }],
+                    "onclose": handler,
+                    "onfail": handler,
+                })),
+            )
             .await?;
-        if let Ok(status) = Self::check_status_response(&response) {
-            log::debug!("com.palm.wifi/getStatus succeeded");
-            return Ok(status);
+        let alert_id = response["payload"]["alertId"]
+            .as_str()
+            .ok_or_else(|| AppError::Tv("TV did not return an alert id".into()))?
+            .to_string();
+        self.request(
+            "ssap://system.notifications/closeAlert",
+            Some(json!({ "alertId": alert_id })),
+        )
+        .await?;
+        Ok(CommandResult::ok())
+    }
+
+    pub async fn get_system_info(&mut self) -> Result<SystemInfo, AppError> {
+        let response = self.request(SYSTEM_INFO_URI, None).await?;
+        let mut info = SystemInfo::from_payload(&response["payload"]);
+
+        // Not available on every firmware; model info alone is still useful
+        match self.request(SOFTWARE_INFO_URI, None).await {
+            Ok(response) => info.add_software_info(&response["payload"]),
+            Err(e) => log::debug!("getCurrentSWInformation failed: {}", e),
+        }
+        Ok(info)
+    }
+
+    /// Channel on the Live TV input. Fails when the TV is showing an app or another input.
+    pub async fn get_current_channel(&mut self) -> Result<CurrentChannel, AppError> {
+        let response = self.request("ssap://tv/getCurrentChannel", None).await?;
+        Ok(CurrentChannel::from_payload(&response["payload"]))
+    }
+
+    /// Installed firmware and whether LG's update server has a newer one.
+    pub async fn check_update(&mut self) -> Result<TvUpdateStatus, AppError> {
+        let mut status = TvUpdateStatus {
+            current_version: self.get_system_info().await?.firmware_version,
+            ..Default::default()
+        };
+        match self
+            .request("ssap://com.webos.service.update/checkUpdate", None)
+            .await
+        {
+            Ok(response) => status.apply_check_payload(&response["payload"]),
+            Err(e) => log::debug!("checkUpdate failed: {}", e),
+        }
+        Ok(status)
+    }
+
+    /// Reasons an automated power off should wait: a firmware update or recording that turning
+    /// the TV off would interrupt. SSAP exposes neither directly, so this goes by the app on
+    /// screen (Software Update, Recordings); a recording running in the background of Live TV
+    /// can't be seen. Empty when nothing was found or the TV didn't answer.
+    pub async fn power_off_blockers(&mut self) -> Vec<String> {
+        let app_id = match self
+            .request("ssap://com.webos.applicationManager/getForegroundAppInfo", None)
+            .await
+        {
+            Ok(response) => ForegroundApp::from_payload(&response["payload"]).map(|fg| fg.app_id),
+            Err(e) => {
+                log::debug!("Power off pre-flight: getForegroundAppInfo failed: {}", e);
+                None
+            }
+        };
+        let mut blockers = Vec::new();
+        match app_id.as_deref() {
+            Some("com.webos.app.softwareupdate") => {
+                blockers.push("a firmware update may be in progress".to_string())
+            }
+            Some("com.webos.app.dvr") | Some("com.webos.app.recordings") => {
+                blockers.push("a recording may be in progress".to_string())
+            }
+            _ => {}
+        }
+        blockers
+    }
+
+    /// Pending notifications, where the TV lets us read them. webOS keeps its notification
+    /// center (and app notifications) private to the TV; over SSAP only the firmware update
+    /// check is readable, so that's what this reports.
+    pub async fn get_notifications(&mut self) -> Result<Vec<TvNotification>, AppError> {
+        let mut notifications = Vec::new();
+        let update = self.check_update().await?;
+        if update.update_available == Some(true) {
+            let version = update.available_version.unwrap_or_default();
+            notifications.push(TvNotification {
+                id: format!("software_update:{}", version),
+                kind: "software_update".to_string(),
+                message: if version.is_empty() {
+                    format!("{}: software update available", self.name)
+                } else {
+                    format!("{}: software update {} available", self.name, version)
+                },
+            });
+        }
+        Ok(notifications)
+    }
+
+    /// Show a short message in the corner of the TV screen.
+    pub async fn show_toast(&mut self, message: &str) -> Result<CommandResult, AppError> {
+        self.request(
+            "ssap://system.notifications/createToast",
+            Some(json!({ "message": message })),
+        )
+        .await?;
+        Ok(CommandResult::ok())
+    }
+
+    /// Open the TV's Software Update screen. Installing still has to be confirmed on the TV
+    /// (SSAP can't start a firmware install by itself).
+    pub async fn open_software_update(&mut self) -> Result<CommandResult, AppError> {
+        self.launch_app("com.webos.app.softwareupdate", None).await?;
+        Ok(CommandResult::ok_with_message(
+            "Software Update opened on the TV — confirm the update there",
+        ))
+    }
+
+    /// What `ConnectionManager::measure_latency` times, read from the connection so the
+    /// rounds can run outside the queue.
+    pub fn latency_probe(&self) -> Result<LatencyProbe, AppError> {
+        if !self.connected {
+            return Err(AppError::catalog("not_connected", &[]));
+        }
+        Ok(LatencyProbe {
+            host: self.resolved_ip.map_or_else(|| self.ip.clone(), |ip| ip.to_string()),
+            port: self.ssap_port(),
+            input_ws: self.input_ws.clone(),
+            timeouts: self.timeouts,
+        })
+    }
+
+    pub async fn get_network_info(&mut self) -> Result<Value, AppError> {
+        // Get MAC addresses from getinfo endpoint
+        self.send_command("ssap://com.webos.service.connectionmanager/getinfo", None).await
+    }
+
+    fn check_status_response(response: &Value) -> Result<Value, AppError> {
+        if response.get("error").is_some() {
+            return Err(AppError::Tv(ssap_error::from_response(response, "getStatus failed").into()));
+        }
+        Ok(response.clone())
+    }
+
+    /// Network status services, most common first.
+    const NETWORK_STATUS_ENDPOINTS: [(NetworkStatusEndpoint, &'static str); 3] = [
+        // connectionmanager/getStatus: wifi + wired state
+        (
+            NetworkStatusEndpoint::ConnectionManager,
+            "ssap://com.webos.service.connectionmanager/getStatus",
+        ),
+        // wifi/getstatus: lowercase per webOS OSE docs
+        (NetworkStatusEndpoint::Wifi, "ssap://com.webos.service.wifi/getstatus"),
+        // Some consumer TVs use the palm namespace
+        (NetworkStatusEndpoint::PalmWifi, "ssap://com.palm.wifi/getStatus"),
+    ];
+
+    /// Tries the endpoint that worked before (`capabilities.network_status`) and only probes
+    /// the others if it fails; a TV where none answered isn't asked again.
+    pub async fn get_network_status(&mut self) -> Result<Value, AppError> {
+        let cached = self.capabilities.network_status;
+        if cached == Some(NetworkStatusEndpoint::Unsupported) {
+            return Err(AppError::Tv("Network status isn't available on this TV".into()));
         }
-        let err = response["error"].as_str().unwrap_or("unknown");
-        Err(err.to_string())
+        let mut endpoints = Self::NETWORK_STATUS_ENDPOINTS.to_vec();
+        endpoints.sort_by_key(|(endpoint, _)| Some(*endpoint) != cached);
+        let mut last_response = Value::Null;
+        for (endpoint, uri) in endpoints {
+            log::debug!("Trying {}...", uri);
+            let response = self.send_command(uri, None).await?;
+            if let Ok(status) = Self::check_status_response(&response) {
+                log::debug!("{} succeeded", uri);
+                self.capabilities.network_status = Some(endpoint);
+                return Ok(status);
+            }
+            last_response = response;
+        }
+        self.capabilities.network_status = Some(NetworkStatusEndpoint::Unsupported);
+        Err(AppError::Tv(ssap_error::from_response(&last_response, "unknown").into()))
     }
 
     /// Get the MAC address of the connected network interface
-    pub async fn get_connected_mac(&mut self) -> Result<Option<String>, String> {
+    pub async fn get_connected_mac(&mut self) -> Result<Option<String>, AppError> {
         // First get MAC addresses
         let info = self.get_network_info().await?;
         log::debug!("Network info: {:?}", info);
@@ -491,9 +2215,88 @@ impl TvConnection {
     }
 }
 
+/// SHA-256 fingerprint of the TLS peer certificate, as colon-separated hex.
+fn cert_fingerprint(ws: &WsStream) -> Option<String> {
+    let MaybeTlsStream::NativeTls(tls) = ws.get_ref() else {
+        return None;
+    };
+    let der = tls.get_ref().peer_certificate().ok()??.to_der().ok()?;
+    Some(
+        Sha256::digest(&der)
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
+}
+
+/// TLS connector for the TV's sockets. TVs use self-signed certificates, so it accepts any
+/// certificate; callers that care compare the fingerprint against the pinned one. Built once
+/// and shared, since building one loads the system trust store and made every connect and
+/// input socket refresh slower.
+pub fn tls_connector() -> Result<TlsConnector, String> {
+    static CONNECTOR: OnceLock<TlsConnector> = OnceLock::new();
+    if let Some(connector) = CONNECTOR.get() {
+        return Ok(connector.clone());
+    }
+    let connector = TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(CONNECTOR.get_or_init(|| connector).clone())
+}
+
+/// `host:port` for a hostname or IP literal; IPv6 addresses get the brackets URIs and socket
+/// address strings need. Brackets the user already typed are kept.
+pub fn host_port(host: &str, port: u16) -> String {
+    let host = host.trim();
+    if host.parse::<std::net::Ipv6Addr>().is_ok() {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// First address `host` (hostname, IPv4 or IPv6 literal) resolves to.
+pub async fn resolve(host: &str, port: u16) -> Option<SocketAddr> {
+    tokio::net::lookup_host(host_port(host, port))
+        .await
+        .ok()?
+        .next()
+}
+
+/// Quick TCP probe of the TV's SSAP port. Open means the TV is on (or in Quick Start+
+/// standby); a timeout or refusal means it's off or unreachable.
+pub async fn probe(ip: &str, use_ssl: bool) -> bool {
+    probe_port(ip, if use_ssl { 3001 } else { 3000 }).await
+}
+
+/// `probe` of a given port.
+pub async fn probe_port(ip: &str, port: u16) -> bool {
+    matches!(
+        tokio::time::timeout(
+            std::time::Duration::from_millis(1500),
+            TcpStream::connect((ip, port)),
+        )
+        .await,
+        Ok(Ok(_))
+    )
+}
+
 /// Send Wake-on-LAN magic packet. If broadcast_ip is set (e.g. 10.0.0.255), also send to that
 /// subnet broadcast on ports 9 and 7 — required on some networks where 255.255.255.255 is blocked.
-pub fn wake_on_lan(mac: &str, broadcast_ip: Option<&str>) -> Result<CommandResult, String> {
+/// If unicast_ip is set, also send straight to that address. Packets go out from `source`, or
+/// when None from the local address that reaches the TV. Each destination gets `burst.count`
+/// packets `burst.interval_ms` apart. `payload` lists each destination with its own
+/// success/error; the call only fails if every destination failed.
+pub async fn wake_on_lan(
+    mac: &str,
+    broadcast_ip: Option<&str>,
+    unicast_ip: Option<&str>,
+    source: Option<IpAddr>,
+    burst: WolConfig,
+) -> Result<CommandResult, AppError> {
     let mac_clean = mac.replace([':', '-'], "");
     let mac_bytes: [u8; 6] = hex::decode(&mac_clean)
         .map_err(|_| "Invalid MAC address")?
@@ -501,38 +2304,105 @@ pub fn wake_on_lan(mac: &str, broadcast_ip: Option<&str>) -> Result<CommandResul
         .map_err(|_| "Invalid MAC address length")?;
 
     let magic_packet = wake_on_lan::MagicPacket::new(&mac_bytes);
-    magic_packet
-        .send()
-        .map_err(|e| format!("WoL send failed: {}", e))?;
 
+    // Send from the interface that reaches the TV (or the configured broadcast subnet) so a
+    // multi-homed machine doesn't broadcast on the wrong network
+    let unicast_ip = unicast_ip.map(str::trim).filter(|ip| !ip.is_empty());
+    let broadcast_ip = broadcast_ip.map(str::trim).filter(|ip| !ip.is_empty());
+    let source = source.or_else(|| unicast_ip.or(broadcast_ip).and_then(netif::local_addr_for));
+    let from = match source {
+        Some(ip) => SocketAddr::new(ip, 0).to_string(),
+        None => "0.0.0.0:0".to_string(),
+    };
+    if let Some(ip) = source {
+        log::debug!("WoL: sending from {}", ip);
+    }
+
+    let mut destinations = vec!["255.255.255.255:9".to_string()];
+    // The TV's /24 broadcast, when this machine is on the same /24
+    let subnet_broadcast = source
+        .zip(unicast_ip)
+        .and_then(|(local, ip)| netif::shared_subnet_broadcast(local, ip))
+        .map(|broadcast| broadcast.to_string())
+        .filter(|broadcast| Some(broadcast.as_str()) != broadcast_ip);
+    if let Some(ip) = &subnet_broadcast {
+        destinations.push(format!("{}:9", ip));
+    }
     if let Some(ip) = broadcast_ip {
-        let ip = ip.trim();
-        if !ip.is_empty() {
-            let from: &str = "0.0.0.0:0";
-            for port in [9u16, 7] {
-                let to_addr = format!("{}:{}", ip, port);
-                if let Err(e) = magic_packet.send_to(to_addr.as_str(), from) {
-                    log::warn!("WoL send_to {} failed: {}", to_addr, e);
-                }
+        for port in [9u16, 7] {
+            destinations.push(format!("{}:{}", ip, port));
+        }
+    }
+    // Unicast to the last known address: works across routers that drop broadcasts, as long
+    // as the TV's ARP entry is still cached
+    if let Some(ip) = unicast_ip {
+        destinations.push(host_port(ip, 9));
+    }
+
+    // Try every path and report each one, so the UI can show which worked. A destination
+    // counts as sent once any packet of the burst went out to it.
+    let burst = burst.clamped();
+    let mut results: Vec<(String, Result<(), String>)> = Vec::new();
+    for round in 0..burst.count {
+        if round > 0 {
+            tokio::time::sleep(burst.interval()).await;
+        }
+        for (i, to_addr) in destinations.iter().enumerate() {
+            let sent = magic_packet
+                .send_to(to_addr.as_str(), from.as_str())
+                .map_err(|e| e.to_string());
+            match results.get_mut(i) {
+                Some((_, previous)) if previous.is_err() => *previous = sent,
+                Some(_) => {}
+                None => results.push((to_addr.clone(), sent)),
             }
         }
     }
 
-    Ok(CommandResult::ok_with_message("Wake-on-LAN packet sent"))
+    for (to_addr, sent) in &results {
+        if let Err(e) = sent {
+            log::warn!("WoL send to {} failed: {}", to_addr, e);
+        }
+    }
+    let sent_count = results.iter().filter(|(_, r)| r.is_ok()).count();
+    if sent_count == 0 {
+        let error = results[0].1.as_ref().unwrap_err();
+        return Err(AppError::Other(format!("WoL send failed: {}", error).into()));
+    }
+
+    let payload = results
+        .iter()
+        .map(|(to_addr, sent)| {
+            json!({
+                "destination": to_addr,
+                "source": source.map(|ip| ip.to_string()),
+                "success": sent.is_ok(),
+                "error": sent.as_ref().err(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut result = CommandResult::ok_with_message(&format!(
+        "Wake-on-LAN packet sent ({}/{} destinations)",
+        sent_count,
+        results.len()
+    ));
+    result.payload = Some(Value::Array(payload));
+    Ok(result)
 }
 
 /// Wake a Roku device via ECP (External Control Protocol). Sends keypress/PowerOn to port 8060.
-pub async fn wake_roku(ip: &str) -> Result<CommandResult, String> {
+pub async fn wake_roku(ip: &str) -> Result<CommandResult, AppError> {
     use tokio::io::{AsyncWriteExt, BufWriter};
 
-    let mut stream = TcpStream::connect(format!("{}:8060", ip))
+    let target = host_port(ip, 8060);
+    let mut stream = TcpStream::connect(&target)
         .await
-        .map_err(|e| format!("Could not reach Roku at {}:8060: {}", ip, e))?;
+        .map_err(|e| format!("Could not reach Roku at {}: {}", target, e))?;
 
     // Roku ECP: POST /keypress/PowerOn with Host header set to IP (required by Roku).
     let req = format!(
         "POST /keypress/PowerOn HTTP/1.1\r\nHost: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
-        ip
+        target
     );
     let mut writer = BufWriter::new(&mut stream);
     writer
@@ -546,10 +2416,10 @@ pub async fn wake_roku(ip: &str) -> Result<CommandResult, String> {
 
 /// Wake an Android TV / NVIDIA Shield via ADB. Requires Network debugging enabled on the device.
 /// Uses system `adb` from PATH.
-pub async fn wake_adb(ip: &str, port: u16) -> Result<CommandResult, String> {
+pub async fn wake_adb(ip: &str, port: u16) -> Result<CommandResult, AppError> {
     use tokio::process::Command;
 
-    let target = format!("{}:{}", ip, port);
+    let target = host_port(ip, port);
     let output = Command::new("adb")
         .args(["connect", &target])
         .output()
@@ -557,7 +2427,7 @@ pub async fn wake_adb(ip: &str, port: u16) -> Result<CommandResult, String> {
         .map_err(|e| format!("adb not found or failed: {}. Install Android platform tools (e.g. brew install android-platform-tools).", e))?;
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("adb connect failed: {}", stderr.trim()));
+        return Err(AppError::Other(format!("adb connect failed: {}", stderr.trim()).into()));
     }
 
     let output = Command::new("adb")
@@ -567,7 +2437,7 @@ pub async fn wake_adb(ip: &str, port: u16) -> Result<CommandResult, String> {
         .map_err(|e| format!("adb shell failed: {}", e))?;
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("adb wake failed: {}", stderr.trim()));
+        return Err(AppError::Other(format!("adb wake failed: {}", stderr.trim()).into()));
     }
 
     Ok(CommandResult::ok_with_message("ADB wake sent"))
@@ -0,0 +1,400 @@
+//! Rolling 7-day history of TV power state and volume per TV, recorded from subscriptions,
+//! for "TV was on 6h yesterday" style stats and rough energy estimates. Kept in usage.json next
+//! to config.json, written at most once per `PERSIST_INTERVAL` like the state cache, since
+//! events come from subscription tasks and arrive in bursts.
+
+use crate::config::{Config, EnergyConfig};
+use crate::tv::PowerState;
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+const RETENTION_SECS: u64 = 7 * 24 * 60 * 60;
+/// Volume changes closer together than this are one change (holding volume up).
+const VOLUME_COALESCE_SECS: u64 = 5;
+const PERSIST_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageChange {
+    /// `Unknown` marks a gap: the app stopped seeing the TV (disconnect, exit, switching to
+    /// another TV), so the time until the next event counts as neither on nor standby.
+    Power(PowerState),
+    Volume(u64),
+    /// Screen turned off (or back on) while the TV stays on.
+    ScreenOff(bool),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageEvent {
+    /// Unix seconds.
+    pub at: u64,
+    pub change: UsageChange,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DayUsage {
+    /// Local date, e.g. "2026-10-15".
+    pub date: String,
+    /// Includes `screen_off_secs`.
+    pub on_secs: u64,
+    pub screen_off_secs: u64,
+    pub standby_secs: u64,
+    /// Time the app wasn't watching the TV (not connected, or not running).
+    pub unknown_secs: u64,
+    pub volume_changes: u32,
+    /// Time-weighted average volume while on.
+    pub average_volume: Option<f64>,
+    /// Length of the day covered so far (less than 24h for today).
+    #[serde(skip)]
+    span_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageStats {
+    /// Oldest first, ending with today.
+    pub days: Vec<DayUsage>,
+    pub total_on_secs: u64,
+}
+
+pub struct UsageHistory {
+    logs: Mutex<UsageLogs>,
+    /// Signalled on every recorded change; the writer saves once per interval.
+    dirty: Notify,
+}
+
+/// What's saved to usage.json.
+#[derive(Default, Serialize, Deserialize)]
+struct UsageLogs {
+    /// By TV name.
+    #[serde(default)]
+    tvs: HashMap<String, UsageLog>,
+    /// Single history from before it was kept per TV; moved to the active TV on load.
+    #[serde(default, skip_serializing)]
+    events: Vec<UsageEvent>,
+}
+
+/// Recorded events of one TV.
+#[derive(Default, Serialize, Deserialize)]
+struct UsageLog {
+    events: Vec<UsageEvent>,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl UsageHistory {
+    fn path() -> PathBuf {
+        Config::config_path().with_file_name("usage.json")
+    }
+
+    /// Load usage.json; a history from before it was kept per TV goes to `active_tv`.
+    pub fn load(active_tv: Option<&str>) -> Self {
+        let mut logs: UsageLogs = fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        let legacy = std::mem::take(&mut logs.events);
+        if let Some(tv) = active_tv
+            && !legacy.is_empty()
+        {
+            logs.tvs.entry(tv.to_string()).or_default().events = legacy;
+        }
+        Self {
+            logs: Mutex::new(logs),
+            dirty: Notify::new(),
+        }
+    }
+
+    pub fn record(&self, tv: &str, change: UsageChange) {
+        let changed = self
+            .logs
+            .lock()
+            .unwrap()
+            .tvs
+            .entry(tv.to_string())
+            .or_default()
+            .record(change);
+        if changed {
+            self.dirty.notify_one();
+        }
+    }
+
+    /// Mark a gap in every TV's history, e.g. when the app exits.
+    pub fn record_gap_all(&self) {
+        let mut changed = false;
+        for log in self.logs.lock().unwrap().tvs.values_mut() {
+            changed |= log.record(UsageChange::Power(PowerState::Unknown));
+        }
+        if changed {
+            self.dirty.notify_one();
+        }
+    }
+
+    /// Per-day on/standby time and volume of `tv` for the last 7 local days.
+    pub fn stats(&self, tv: &str) -> UsageStats {
+        match self.logs.lock().unwrap().tvs.get(tv) {
+            Some(log) => log.stats(),
+            None => UsageLog::default().stats(),
+        }
+    }
+
+    /// Write the history now (on exit), instead of waiting for the writer.
+    pub fn flush(&self) {
+        let result = serde_json::to_string(&*self.logs.lock().unwrap())
+            .map_err(|e| e.to_string())
+            .and_then(|contents| fs::write(Self::path(), contents).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::warn!("Failed to save usage history: {}", e);
+        }
+    }
+
+    /// Write the history after changes, at most once per `PERSIST_INTERVAL`. Runs forever.
+    pub async fn run_writer(&self) {
+        loop {
+            self.dirty.notified().await;
+            self.flush();
+            tokio::time::sleep(PERSIST_INTERVAL).await;
+        }
+    }
+}
+
+impl UsageLog {
+    fn current_power(&self) -> Option<PowerState> {
+        self.events.iter().rev().find_map(|e| match e.change {
+            UsageChange::Power(p) => Some(p),
+            _ => None,
+        })
+    }
+
+    fn current_screen_off(&self) -> bool {
+        for event in self.events.iter().rev() {
+            match event.change {
+                UsageChange::ScreenOff(off) => return off,
+                // The screen comes back on with every power transition
+                UsageChange::Power(_) => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Add `change`; false if it changed nothing (same state as already recorded, or a gap
+    /// before anything was seen).
+    fn record(&mut self, change: UsageChange) -> bool {
+        let at = now();
+        match change {
+            UsageChange::Power(PowerState::Unknown) if self.current_power().is_none() => {
+                return false;
+            }
+            UsageChange::Power(power) if self.current_power() == Some(power) => return false,
+            UsageChange::ScreenOff(off) if self.current_screen_off() == off => return false,
+            UsageChange::Volume(_) => {
+                if let Some(last) = self.events.last_mut() {
+                    if matches!(last.change, UsageChange::Volume(_))
+                        && at.saturating_sub(last.at) < VOLUME_COALESCE_SECS
+                    {
+                        last.at = at;
+                        last.change = change;
+                        return true;
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.events.push(UsageEvent { at, change });
+        self.prune(at);
+        true
+    }
+
+    /// Drop events older than the retention window, keeping the last power state before it
+    /// so the first day still knows whether the TV was on.
+    fn prune(&mut self, now: u64) {
+        let cutoff = now.saturating_sub(RETENTION_SECS);
+        let first_kept = self
+            .events
+            .iter()
+            .position(|e| e.at >= cutoff)
+            .unwrap_or(self.events.len());
+        if first_kept == 0 {
+            return;
+        }
+        let carried = self.events[..first_kept]
+            .iter()
+            .rev()
+            .find(|e| matches!(e.change, UsageChange::Power(_)))
+            .cloned();
+        self.events.drain(..first_kept);
+        if let Some(mut carried) = carried {
+            carried.at = cutoff;
+            self.events.insert(0, carried);
+        }
+    }
+
+    fn stats(&self) -> UsageStats {
+        let now = now();
+        let today = Local::now().date_naive();
+        let mut days = Vec::new();
+        for offset in (0..7).rev() {
+            let date = today - chrono::Days::new(offset);
+            let start = day_start(date);
+            let end = day_start(date + chrono::Days::new(1)).min(now);
+            days.push(self.day_usage(date, start, end));
+        }
+        let total_on_secs = days.iter().map(|d| d.on_secs).sum();
+        UsageStats {
+            days,
+            total_on_secs,
+        }
+    }
+
+    fn day_usage(&self, date: NaiveDate, start: u64, end: u64) -> DayUsage {
+        let mut usage = DayUsage {
+            date: date.to_string(),
+            on_secs: 0,
+            screen_off_secs: 0,
+            standby_secs: 0,
+            unknown_secs: 0,
+            volume_changes: 0,
+            average_volume: None,
+            span_secs: end.saturating_sub(start),
+        };
+        // Nothing recorded before the first event
+        let mut power = PowerState::Unknown;
+        let mut screen_off = false;
+        let mut volume: Option<u64> = None;
+        let mut t = start;
+        let mut volume_weighted = 0u64;
+        let mut volume_secs = 0u64;
+        // Closes the last interval at `end`
+        let sentinel = UsageEvent {
+            at: end,
+            change: UsageChange::Power(PowerState::Unknown),
+        };
+        for event in self.events.iter().chain(std::iter::once(&sentinel)) {
+            let at = event.at.clamp(start, end);
+            if at > t {
+                let secs = at - t;
+                match power {
+                    PowerState::On => {
+                        usage.on_secs += secs;
+                        if screen_off {
+                            usage.screen_off_secs += secs;
+                        }
+                        if let Some(v) = volume {
+                            volume_weighted += v * secs;
+                            volume_secs += secs;
+                        }
+                    }
+                    PowerState::Standby => usage.standby_secs += secs,
+                    PowerState::Unknown => usage.unknown_secs += secs,
+                    PowerState::Off => {}
+                }
+                t = at;
+            }
+            if event.at > end {
+                break;
+            }
+            match event.change {
+                UsageChange::Power(p) => {
+                    power = p;
+                    screen_off = false;
+                }
+                UsageChange::ScreenOff(off) => screen_off = off,
+                UsageChange::Volume(v) => {
+                    volume = Some(v);
+                    if event.at >= start {
+                        usage.volume_changes += 1;
+                    }
+                }
+            }
+        }
+        if volume_secs > 0 {
+            usage.average_volume = Some(volume_weighted as f64 / volume_secs as f64);
+        }
+        usage
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnergyPeriod {
+    Today,
+    Yesterday,
+    /// Last 7 days including today.
+    Week,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnergyEstimate {
+    pub kwh: f64,
+    /// `kwh` times the configured price, if one is set.
+    pub cost: Option<f64>,
+    pub currency: Option<String>,
+    pub on_secs: u64,
+    pub screen_off_secs: u64,
+    /// Standby and off time (the TV draws standby power either way).
+    pub standby_secs: u64,
+    /// Time with no record of the TV's state, left out of `kwh`.
+    pub unknown_secs: u64,
+}
+
+impl UsageHistory {
+    /// Approximate energy use of `tv` from its power history and the configured wattage per
+    /// state.
+    pub fn energy_estimate(
+        &self,
+        tv: &str,
+        period: EnergyPeriod,
+        energy: &EnergyConfig,
+    ) -> EnergyEstimate {
+        let stats = self.stats(tv);
+        let days: &[DayUsage] = match period {
+            EnergyPeriod::Today => &stats.days[stats.days.len() - 1..],
+            EnergyPeriod::Yesterday => &stats.days[stats.days.len() - 2..stats.days.len() - 1],
+            EnergyPeriod::Week => &stats.days,
+        };
+        let mut estimate = EnergyEstimate {
+            kwh: 0.0,
+            cost: None,
+            currency: energy.currency.clone(),
+            on_secs: 0,
+            screen_off_secs: 0,
+            standby_secs: 0,
+            unknown_secs: 0,
+        };
+        for day in days {
+            estimate.on_secs += day.on_secs;
+            estimate.screen_off_secs += day.screen_off_secs;
+            estimate.unknown_secs += day.unknown_secs;
+            estimate.standby_secs += day
+                .span_secs
+                .saturating_sub(day.on_secs + day.unknown_secs);
+        }
+        let hours = |secs: u64| secs as f64 / 3600.0;
+        let watt_hours = hours(estimate.on_secs - estimate.screen_off_secs) * energy.on_watts
+            + hours(estimate.screen_off_secs) * energy.screen_off_watts
+            + hours(estimate.standby_secs) * energy.standby_watts;
+        estimate.kwh = watt_hours / 1000.0;
+        estimate.cost = energy.price_per_kwh.map(|price| estimate.kwh * price);
+        estimate
+    }
+}
+
+fn day_start(date: NaiveDate) -> u64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|dt| dt.timestamp().max(0) as u64)
+        .unwrap_or(0)
+}
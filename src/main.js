@@ -39,6 +39,29 @@ const ACTIONS = [
   { id: 'power_on', label: 'Power On', defaultShortcut: 'F7' },
   { id: 'power_off', label: 'Power Off', defaultShortcut: 'F8' },
   { id: 'home', label: 'Home', defaultShortcut: 'Home' },
+  { id: 'red', label: 'Red', defaultShortcut: '' },
+  { id: 'green', label: 'Green', defaultShortcut: '' },
+  { id: 'yellow', label: 'Yellow', defaultShortcut: '' },
+  { id: 'blue', label: 'Blue', defaultShortcut: '' },
+  { id: 'info', label: 'Info', defaultShortcut: '' },
+  { id: 'exit', label: 'Exit', defaultShortcut: '' },
+  { id: 'menu', label: 'Menu (Settings)', defaultShortcut: '' },
+  { id: 'qmenu', label: 'Quick Menu', defaultShortcut: '' },
+  { id: 'guide', label: 'Program Guide', defaultShortcut: '' },
+  { id: 'list', label: 'Channel List', defaultShortcut: '' },
+  { id: 'record', label: 'Record', defaultShortcut: '' },
+  { id: 'screen_off', label: 'Screen Off', defaultShortcut: '' },
+  { id: 'screen_on', label: 'Screen On', defaultShortcut: '' },
+  { id: 'pixel_cleaning', label: 'OLED Pixel Cleaning', defaultShortcut: '' },
+  { id: 'show_tv_keypad', label: 'TV Number Pad (123)', defaultShortcut: '' },
+  { id: 'energy_saving_max', label: 'Energy Saving: Max', defaultShortcut: '' },
+  { id: 'energy_saving_off', label: 'Energy Saving: Off', defaultShortcut: '' },
+  { id: 'sound_mode_standard', label: 'Sound: Standard', defaultShortcut: '' },
+  { id: 'sound_mode_cinema', label: 'Sound: Cinema', defaultShortcut: '' },
+  { id: 'sound_mode_clear_voice', label: 'Sound: Clear Voice', defaultShortcut: '' },
+  { id: 'sound_mode_sports', label: 'Sound: Sports', defaultShortcut: '' },
+  { id: 'sound_mode_music', label: 'Sound: Music', defaultShortcut: '' },
+  { id: 'show_settings', label: 'Show Settings', defaultShortcut: '' },
   {
     id: 'wake_streaming_device',
     label: 'Wake streaming device',
@@ -54,6 +77,10 @@ function hasConnectionInfo() {
   );
 }
 
+// While not connected: whether the TV answers on the network ('on' | 'off' | null)
+let tvPresence = null;
+const PRESENCE_LABELS = { on: 'TV is on', off: 'TV is off' };
+
 function setStatus(connected, text) {
   isConnected = connected;
   const dot = document.getElementById('status-dot');
@@ -62,12 +89,17 @@ function setStatus(connected, text) {
 
   dot.className = 'dot ' + (connected ? 'connected' : 'disconnected');
   if (connected) {
+    loadBluetoothDevices();
+    loadInputs();
+    loadInputCapabilities();
     statusText.textContent = text || 'Connected';
     statusText.style.display = '';
     connectBtn.style.display = 'none';
   } else {
     if (hasConnectionInfo()) {
-      statusText.style.display = 'none';
+      const presence = PRESENCE_LABELS[tvPresence];
+      statusText.textContent = presence || '';
+      statusText.style.display = presence ? '' : 'none';
       connectBtn.style.display = '';
     } else {
       statusText.textContent = text || 'Not Connected';
@@ -88,6 +120,20 @@ function setConnecting() {
   connectBtn.style.display = 'none';
 }
 
+// Backend errors arrive as {code, message, key, params}: key and params name the catalog
+// message the text was rendered from (null key otherwise). Anything else is a plain string
+function parseBackendError(error) {
+  if (error && typeof error === 'object' && 'code' in error) {
+    return {
+      code: error.code,
+      message: String(error.message),
+      key: error.key ?? null,
+      params: error.params || {},
+    };
+  }
+  return { code: null, message: String(error), key: null, params: {} };
+}
+
 function showToast(message, type = 'info') {
   // Remove existing toast
   const existing = document.querySelector('.toast');
@@ -95,7 +141,7 @@ function showToast(message, type = 'info') {
 
   const toast = document.createElement('div');
   toast.className = `toast ${type}`;
-  toast.textContent = message;
+  toast.textContent = parseBackendError(message).message;
   document.body.appendChild(toast);
 
   // Show
@@ -111,6 +157,33 @@ function showToast(message, type = 'info') {
 function toggleSettings() {
   const panel = document.getElementById('settings-panel');
   panel.classList.toggle('collapsed');
+  if (!panel.classList.contains('collapsed')) loadUsageSummary();
+}
+
+function formatDuration(secs) {
+  const h = Math.floor(secs / 3600);
+  const m = Math.round((secs % 3600) / 60);
+  return h ? `${h}h ${m}m` : `${m}m`;
+}
+
+async function loadUsageSummary() {
+  try {
+    const stats = await invoke('get_usage_stats');
+    const days = stats.days;
+    const today = days[days.length - 1];
+    const yesterday = days[days.length - 2];
+    const energy = await invoke('get_energy_estimate', { period: 'week' });
+    const cost =
+      energy.cost != null
+        ? ` (${energy.cost.toFixed(2)}${energy.currency ? ' ' + energy.currency : ''})`
+        : '';
+    document.getElementById('usage-summary').textContent =
+      `On today ${formatDuration(today.on_secs)}, yesterday ` +
+      `${formatDuration(yesterday.on_secs)}, last 7 days ` +
+      `${formatDuration(stats.total_on_secs)} · ≈${energy.kwh.toFixed(1)} kWh${cost}`;
+  } catch (e) {
+    console.error('Failed to load usage stats:', e);
+  }
 }
 
 function toggleShortcuts() {
@@ -126,8 +199,17 @@ function buttonFeedback(element) {
 // ============ TV Commands ============
 
 // Check if error indicates we are no longer connected (single source of truth for UI)
+const DISCONNECT_ERROR_CODES = new Set([
+  'not_connected',
+  'connection_lost',
+  'session_replaced',
+  'timeout',
+]);
+
 function isDisconnectError(error) {
-  const msg = String(error).toLowerCase();
+  const { code, message } = parseBackendError(error);
+  if (code) return DISCONNECT_ERROR_CODES.has(code);
+  const msg = message.toLowerCase();
   return (
     msg.includes('disconnected') ||
     msg.includes('not connected') ||
@@ -141,7 +223,9 @@ function isDisconnectError(error) {
 // Handle command errors - update status if disconnected
 function handleCommandError(e) {
   showToast(e, 'error');
-  if (isDisconnectError(e)) {
+  if (parseBackendError(e).code === 'session_replaced') {
+    setStatus(false, 'Taken over by another app');
+  } else if (isDisconnectError(e)) {
     setStatus(false, 'Disconnected');
   }
 }
@@ -159,6 +243,182 @@ async function sendButton(button) {
   }
 }
 
+// Right-click on OK is a press-and-hold (opens context menus on the TV)
+function setupLongPress() {
+  const ok = document.querySelector('.ok-btn');
+  if (!ok) return;
+  ok.addEventListener('contextmenu', async (e) => {
+    e.preventDefault();
+    if (!isConnected) return;
+    try {
+      await invoke('long_press_button', { button: 'ENTER', durationMs: 1000 });
+    } catch (err) {
+      handleCommandError(err);
+    }
+  });
+}
+
+// Mouse wheel over the d-pad scrolls the TV (lists, web pages) via the input socket
+function setupScrollWheel() {
+  const dpad = document.querySelector('.dpad');
+  if (!dpad) return;
+  dpad.addEventListener(
+    'wheel',
+    async (e) => {
+      e.preventDefault();
+      if (!isConnected || e.deltaY === 0) return;
+      try {
+        await invoke('send_scroll', { dy: Math.sign(e.deltaY) * 10 });
+      } catch (err) {
+        handleCommandError(err);
+      }
+    },
+    { passive: false },
+  );
+}
+
+async function setScreen(on) {
+  if (!isConnected) {
+    showToast('Not connected', 'error');
+    return;
+  }
+
+  try {
+    await invoke(on ? 'screen_on' : 'screen_off');
+  } catch (e) {
+    handleCommandError(e);
+  }
+}
+
+// Some TVs refuse the pointer input socket; buttons without an SSAP fallback are greyed out
+async function loadInputCapabilities() {
+  let caps;
+  try {
+    caps = await invoke('get_input_capabilities');
+  } catch (e) {
+    console.debug('Input capabilities unavailable:', e);
+    return;
+  }
+  const unavailable = new Set(caps.unavailable_buttons);
+  for (const btn of document.querySelectorAll('[onclick^="sendButton("]')) {
+    const name = btn.getAttribute('onclick').match(/sendButton\('([^']+)'/)?.[1];
+    const off = unavailable.has(name);
+    btn.disabled = off;
+    btn.classList.toggle('unavailable', off);
+  }
+  if (!caps.input_socket) {
+    showToast(`Remote buttons limited: TV refused input (${caps.reason})`, 'error');
+  }
+}
+
+// External inputs, shown with the labels set here (e.g. "PS5" for HDMI_2)
+async function loadInputs() {
+  const section = document.getElementById('inputs-section');
+  const select = document.getElementById('input-select');
+  let inputs = [];
+  try {
+    inputs = await invoke('list_inputs');
+  } catch (e) {
+    console.debug('Inputs unavailable:', e);
+  }
+  const previous = select.value;
+  select.innerHTML = '';
+  for (const input of inputs) {
+    const opt = document.createElement('option');
+    opt.value = input.id;
+    opt.textContent = input.label;
+    opt.title = input.tv_label;
+    opt.selected = input.id === previous;
+    select.appendChild(opt);
+  }
+  section.style.display = inputs.length ? '' : 'none';
+}
+
+async function switchInput() {
+  const inputId = document.getElementById('input-select').value;
+  if (!inputId) return;
+  try {
+    await invoke('switch_input', { inputId });
+  } catch (e) {
+    handleCommandError(e);
+  }
+}
+
+function editInputLabel() {
+  const select = document.getElementById('input-select');
+  const field = document.getElementById('input-label');
+  if (field.style.display !== 'none') return saveInputLabel();
+  const opt = select.selectedOptions[0];
+  if (!opt) return;
+  field.value = opt.textContent === opt.title ? '' : opt.textContent;
+  select.style.display = 'none';
+  field.style.display = '';
+  field.focus();
+}
+
+async function saveInputLabel() {
+  const select = document.getElementById('input-select');
+  const field = document.getElementById('input-label');
+  try {
+    await invoke('set_input_label', {
+      inputId: select.value,
+      label: field.value.trim() || null,
+    });
+    await loadInputs();
+  } catch (e) {
+    showToast(e, 'error');
+  }
+  field.style.display = 'none';
+  select.style.display = '';
+}
+
+// Paired Bluetooth audio devices on the TV; the section stays hidden when there are none
+async function loadBluetoothDevices() {
+  const section = document.getElementById('bluetooth-section');
+  const select = document.getElementById('bt-device-select');
+  let devices = [];
+  try {
+    devices = await invoke('list_bluetooth_devices');
+  } catch (e) {
+    console.debug('Bluetooth devices unavailable:', e);
+  }
+  select.innerHTML = '';
+  for (const d of devices) {
+    const opt = document.createElement('option');
+    opt.value = d.address;
+    opt.textContent = d.connected ? `${d.name} (connected)` : d.name;
+    opt.selected = d.connected;
+    select.appendChild(opt);
+  }
+  section.style.display = devices.length ? '' : 'none';
+}
+
+async function switchBluetoothDevice() {
+  const address = document.getElementById('bt-device-select').value;
+  if (!address) return;
+  try {
+    const result = await invoke('connect_bluetooth_device', { address });
+    showToast(result.message || 'Connecting Bluetooth device');
+    // The TV reports the new connection a moment later
+    setTimeout(loadBluetoothDevices, 3000);
+  } catch (e) {
+    handleCommandError(e);
+  }
+}
+
+async function setEnergySaving(level) {
+  if (!isConnected) {
+    showToast('Not connected', 'error');
+    return;
+  }
+
+  try {
+    await invoke('set_energy_saving', { level });
+  } catch (e) {
+    handleCommandError(e);
+  }
+}
+
 async function volumeUp() {
   if (!isConnected) {
     showToast('Not connected', 'error');
@@ -199,38 +459,40 @@ async function setMute(mute) {
   }
 }
 
-const POWER_ON_RECONNECT_INTERVAL_MS = 1000;
-const POWER_ON_RECONNECT_MAX_TRIES = 10;
-
 async function powerOn() {
   try {
-    const result = await invoke('power_on');
+    // The backend waits for the TV and connects; progress arrives as power-on-progress
+    const result = await invoke('power_on', { connect: true });
     showToast(result.message || 'Wake-on-LAN sent. Connecting...', 'success');
-    // Try to connect every 1s, up to 10 times
-    let tries = 0;
-    const tryConnect = async () => {
-      tries += 1;
-      setConnecting();
-      try {
-        await invoke('connect');
-        setStatus(true, 'Connected');
-        showToast('Connected to TV', 'success');
-        return;
-      } catch (e) {
-        if (tries < POWER_ON_RECONNECT_MAX_TRIES) {
-          setTimeout(tryConnect, POWER_ON_RECONNECT_INTERVAL_MS);
-        } else {
-          setStatus(false);
-          showToast('TV did not respond after 10 tries', 'error');
-        }
+    // Wake-on-LAN reports each destination it tried
+    if (Array.isArray(result.payload)) {
+      for (const d of result.payload) {
+        if (!d.success) console.warn(`WoL to ${d.destination} failed: ${d.error}`);
       }
-    };
-    setTimeout(tryConnect, POWER_ON_RECONNECT_INTERVAL_MS);
+    }
   } catch (e) {
     showToast(e, 'error');
   }
 }
 
+// Power On waiting for the TV to come up, then connecting
+function listenPowerOnProgress() {
+  if (window.__TAURI__ && window.__TAURI__.event) {
+    window.__TAURI__.event.listen('power-on-progress', (e) => {
+      const { stage, message } = e.payload;
+      if (stage === 'waiting' || stage === 'connecting') {
+        setConnecting();
+      } else if (stage === 'connected') {
+        setStatus(true, 'Connected');
+        showToast('Connected to TV', 'success');
+      } else if (stage === 'failed') {
+        setStatus(false);
+        showToast(message || 'TV did not respond', 'error');
+      }
+    });
+  }
+}
+
 async function powerOff() {
   if (!isConnected) {
     showToast('Not connected', 'error');
@@ -347,13 +609,22 @@ async function saveStreamingDevice() {
     });
     config = await invoke('get_config');
     document.getElementById('wake-streaming-btn').style.display =
-      config.streaming_device ? '' : 'none';
+      activeStreamingDevice(config) ? '' : 'none';
     showToast('Streaming device saved', 'success');
   } catch (e) {
     showToast(e, 'error');
   }
 }
 
+// Streaming device in effect for the active TV: its profile's choice, else the active one
+function activeStreamingDevice(config) {
+  const { active, devices } = config.streaming_devices || {};
+  const profile = config.tvs?.[config.active_tv]?.profile;
+  const name =
+    profile && 'streaming_device' in profile ? profile.streaming_device : active;
+  return (name && devices?.[name]) || null;
+}
+
 async function wakeStreamingDevice() {
   try {
     const result = await invoke('wake_streaming_device');
@@ -380,6 +651,28 @@ async function saveMac() {
   }
 }
 
+async function resetCertPin() {
+  try {
+    await invoke('reset_cert_pin');
+    showToast('Certificate pin cleared — connect to trust the TV again', 'success');
+  } catch (e) {
+    showToast(e, 'error');
+  }
+}
+
+async function forgetPairing() {
+  const name = document.getElementById('tv-name').value.trim();
+  if (!name) return;
+  try {
+    await invoke('forget_pairing', { tvName: name });
+    setStatus(false, 'Not paired');
+    showToast('Pairing forgotten — click Authenticate to pair again', 'success');
+    await loadConfig();
+  } catch (e) {
+    showToast(e, 'error');
+  }
+}
+
 async function quitApp() {
   await invoke('quit_app');
 }
@@ -402,10 +695,47 @@ async function connectTv() {
   }
 }
 
+// SSDP search; fills the name and IP fields from the picked TV
+async function discoverTvs() {
+  const select = document.getElementById('discovered-tvs');
+  showToast('Searching for TVs...', 'info');
+  try {
+    const tvs = await invoke('discover_tvs');
+    if (tvs.length === 0) {
+      select.style.display = 'none';
+      showToast('No TVs found — is the TV on and on this network?', 'error');
+      return;
+    }
+    select.innerHTML = '<option value="">Pick a TV...</option>';
+    tvs.forEach((tv, i) => {
+      const opt = document.createElement('option');
+      opt.value = String(i);
+      opt.textContent = tv.model ? `${tv.name} (${tv.ip}, ${tv.model})` : `${tv.name} (${tv.ip})`;
+      opt.dataset.name = tv.name;
+      opt.dataset.ip = tv.ip;
+      select.appendChild(opt);
+    });
+    select.style.display = '';
+  } catch (e) {
+    showToast(e, 'error');
+  }
+}
+
+function pickDiscoveredTv() {
+  const opt = document.getElementById('discovered-tvs').selectedOptions[0];
+  if (!opt || !opt.dataset.ip) return;
+  document.getElementById('tv-ip').value = opt.dataset.ip;
+  const nameField = document.getElementById('tv-name');
+  if (!nameField.value.trim()) {
+    nameField.value = opt.dataset.name.replace(/[^A-Za-z0-9]+/g, '');
+  }
+}
+
 async function authenticate() {
   const name = document.getElementById('tv-name').value.trim();
   const ip = document.getElementById('tv-ip').value.trim();
   const useSsl = document.getElementById('use-ssl').checked;
+  const pinPairing = document.getElementById('pin-pairing').checked;
 
   if (!name || !ip) {
     showToast('Please enter TV name and IP', 'error');
@@ -413,10 +743,12 @@ async function authenticate() {
   }
 
   setConnecting();
-  document.getElementById('status-text').textContent = 'Check TV for prompt...';
+  document.getElementById('status-text').textContent = pinPairing
+    ? 'Enter the PIN shown on the TV...'
+    : 'Check TV for prompt...';
 
   try {
-    const result = await invoke('authenticate', { name, ip, useSsl });
+    const result = await invoke('authenticate', { name, ip, useSsl, pinPairing });
     setStatus(true, 'Connected');
     showToast('Authenticated! Key saved.', 'success');
 
@@ -428,11 +760,110 @@ async function authenticate() {
   } catch (e) {
     setStatus(false);
     showToast(e, 'error');
+  } finally {
+    document.getElementById('pin-field').style.display = 'none';
+  }
+}
+
+// PIN pairing: the backend asks for the PIN once the TV is showing it
+async function submitPin() {
+  const field = document.getElementById('pin-input');
+  try {
+    await invoke('submit_pin', { pin: field.value });
+    document.getElementById('pin-field').style.display = 'none';
+  } catch (e) {
+    showToast(e, 'error');
+    field.select();
+  }
+}
+
+function listenPinRequired() {
+  if (window.__TAURI__ && window.__TAURI__.event) {
+    window.__TAURI__.event.listen('pin-required', () => {
+      const field = document.getElementById('pin-input');
+      field.value = '';
+      document.getElementById('pin-field').style.display = '';
+      field.focus();
+    });
   }
 }
 
 // ============ Config ============
 
+async function toggleAutoReconnect() {
+  const enabled = document.getElementById('auto-reconnect').checked;
+  try {
+    await invoke('set_auto_reconnect', { enabled });
+  } catch (e) {
+    showToast(e, 'error');
+  }
+}
+
+async function setLocale() {
+  const locale = document.getElementById('locale').value || null;
+  try {
+    await invoke('set_locale', { locale });
+  } catch (e) {
+    showToast(e, 'error');
+  }
+}
+
+async function setTrayLeftClick() {
+  const action = document.getElementById('tray-left-click').value;
+  try {
+    await invoke('set_tray_left_click', { action });
+  } catch (e) {
+    showToast(e, 'error');
+  }
+}
+
+const TRAY_MENU_KINDS = ['action', 'input', 'app', 'macro'];
+
+function trayMenuToText(items) {
+  return (items || [])
+    .map((item) => {
+      if (item.kind === 'separator') return '-';
+      const id = item.kind === 'macro' ? item.name : item.id;
+      return item.label
+        ? `${item.kind}:${id} = ${item.label}`
+        : `${item.kind}:${id}`;
+    })
+    .join('\n');
+}
+
+function parseTrayMenu(text) {
+  const items = [];
+  for (const raw of text.split('\n')) {
+    const line = raw.trim();
+    if (!line) continue;
+    if (line === '-') {
+      items.push({ kind: 'separator' });
+      continue;
+    }
+    const [entry, ...labelParts] = line.split('=');
+    const colon = entry.indexOf(':');
+    const kind = colon > 0 ? entry.slice(0, colon).trim().toLowerCase() : '';
+    if (!TRAY_MENU_KINDS.includes(kind)) {
+      throw new Error(`Unknown tray menu entry "${line}"`);
+    }
+    const id = entry.slice(colon + 1).trim();
+    const label = labelParts.join('=').trim() || null;
+    items.push(
+      kind === 'macro' ? { kind, name: id, label } : { kind, id, label },
+    );
+  }
+  return items;
+}
+
+async function setTrayMenu() {
+  try {
+    const items = parseTrayMenu(document.getElementById('tray-menu').value);
+    await invoke('set_tray_menu', { items });
+  } catch (e) {
+    showToast(e.message || e, 'error');
+  }
+}
+
 async function loadConfig() {
   try {
     config = await invoke('get_config');
@@ -459,8 +890,16 @@ async function loadConfig() {
       }
     }
 
+    document.getElementById('auto-reconnect').checked = !!config.auto_reconnect;
+    document.getElementById('tray-left-click').value =
+      config.tray_left_click || 'open_remote';
+    document.getElementById('tray-menu').value = trayMenuToText(
+      config.tray_menu,
+    );
+    document.getElementById('locale').value = config.locale || '';
+
     // Streaming device
-    const sd = config.streaming_device;
+    const sd = activeStreamingDevice(config);
     const typeSelect = document.getElementById('streaming-device-type');
     if (sd) {
       if (sd.type === 'wol') {
@@ -488,7 +927,9 @@ async function loadConfig() {
     toggleStreamingDeviceFields();
     const wakeStreamingBtn = document.getElementById('wake-streaming-btn');
     if (wakeStreamingBtn)
-      wakeStreamingBtn.style.display = config.streaming_device ? '' : 'none';
+      wakeStreamingBtn.style.display = activeStreamingDevice(config)
+        ? ''
+        : 'none';
 
     // Load shortcut settings
     await loadShortcutSettings();
@@ -806,7 +1247,67 @@ function buildShortcutToActionMap() {
   }
 }
 
+// Recorded macros run as macro_<name> actions, so they can get shortcuts like any action
+async function addMacroActions() {
+  try {
+    const macros = await invoke('get_macros');
+    for (const name of Object.keys(macros)) {
+      const id = `macro_${name}`;
+      if (!ACTIONS.find((a) => a.id === id)) {
+        ACTIONS.push({ id, label: `Macro: ${name}`, defaultShortcut: '' });
+      }
+    }
+  } catch (e) {
+    console.error('Failed to load macros:', e);
+  }
+}
+
+// Scenes (picture/sound presets from config) run as scene_<name> actions
+async function addSceneActions() {
+  try {
+    const scenes = await invoke('get_scenes');
+    for (const name of Object.keys(scenes)) {
+      const id = `scene_${name}`;
+      if (!ACTIONS.find((a) => a.id === id)) {
+        ACTIONS.push({ id, label: `Scene: ${name}`, defaultShortcut: '' });
+      }
+    }
+  } catch (e) {
+    console.error('Failed to load scenes:', e);
+  }
+}
+
+let isRecordingMacro = false;
+
+async function toggleMacroRecording() {
+  const btn = document.getElementById('macro-record-btn');
+  try {
+    if (!isRecordingMacro) {
+      await invoke('start_macro_recording');
+      isRecordingMacro = true;
+      btn.classList.add('recording');
+      btn.textContent = '■ Stop recording';
+      return;
+    }
+    const name = document.getElementById('macro-name').value.trim();
+    isRecordingMacro = false;
+    btn.classList.remove('recording');
+    btn.textContent = '● Record macro';
+    const steps = await invoke('stop_macro_recording', { name: name || null });
+    if (name) {
+      showToast(`Saved macro "${name}" (${steps.length} steps)`);
+      await loadActionShortcuts();
+    } else {
+      showToast('Recording discarded — enter a name to save it');
+    }
+  } catch (e) {
+    showToast(e, 'error');
+  }
+}
+
 async function loadActionShortcuts() {
+  await addMacroActions();
+  await addSceneActions();
   try {
     const loaded = await invoke('get_action_shortcuts');
     actionShortcuts = {};
@@ -1004,8 +1505,32 @@ async function runAction(actionId) {
       return wakeStreamingDevice();
     case 'home':
       return sendButton('HOME');
-    default:
+    case 'red':
+    case 'green':
+    case 'yellow':
+    case 'blue':
+    case 'info':
+    case 'exit':
+    case 'menu':
+    case 'qmenu':
+    case 'guide':
+    case 'list':
+    case 'record':
+      return sendButton(actionId.toUpperCase());
+    case 'screen_off':
+      return setScreen(false);
+    case 'screen_on':
+      return setScreen(true);
+    case 'energy_saving_max':
+      return setEnergySaving('max');
+    case 'energy_saving_off':
+      return setEnergySaving('off');
+    case 'show_settings':
+      document.getElementById('settings-panel').classList.remove('collapsed');
       return Promise.resolve();
+    default:
+      // Actions without a dedicated helper (e.g. energy_saving_min) run in the backend
+      return invoke('run_action', { actionId }).catch(handleCommandError);
   }
 }
 
@@ -1053,6 +1578,10 @@ async function checkStatus() {
     } else if (!isConnected && connected) {
       // Backend says connected
       setStatus(true, 'Connected');
+    } else if (!connected) {
+      const presence = await invoke('get_tv_presence');
+      tvPresence = presence === 'connected' ? null : presence;
+      setStatus(false);
     }
   } catch (e) {
     console.error('Status check failed:', e);
@@ -1064,9 +1593,20 @@ async function checkStatus() {
 document.addEventListener('DOMContentLoaded', () => {
   loadConfig();
   setupShortcutRecorder();
+  setupScrollWheel();
+  setupLongPress();
   listenRunCommand();
+  listenActionExecuted();
   listenConnectionLost();
+  listenActiveTvChanged();
+  listenNavigate();
   listenUpdateCheckResult();
+  listenForegroundApp();
+  listenConfigChanged();
+  listenVolumeChanged();
+  listenPinRequired();
+  listenPowerOffDeferred();
+  listenPowerOnProgress();
 });
 
 function listenRunCommand() {
@@ -1080,12 +1620,159 @@ function listenRunCommand() {
   }
 }
 
+// Actions triggered outside the window (global hotkeys) report failures here, since there's
+// no button that could show the error
+function listenActionExecuted() {
+  if (window.__TAURI__ && window.__TAURI__.event) {
+    window.__TAURI__.event.listen('action-executed', (e) => {
+      const { id, source, success, error } = e.payload;
+      if (!success && source !== 'ui') {
+        showToast(`${id}: ${error}`, 'error');
+      }
+    });
+  }
+}
+
+// An automation (parental schedule, macro) left the TV on because it looked busy
+function listenPowerOffDeferred() {
+  if (window.__TAURI__ && window.__TAURI__.event) {
+    window.__TAURI__.event.listen('power-off-deferred', (e) => {
+      showToast(`Power off postponed: ${e.payload}`, 'info');
+    });
+  }
+}
+
 // When keepalive detects connection dropped in background, sync UI
 function listenConnectionLost() {
   if (window.__TAURI__ && window.__TAURI__.event) {
     window.__TAURI__.event.listen('connection-lost', () => {
       setStatus(false, 'Disconnected');
     });
+    // Another remote app registered with the TV; payload says how to get the session back
+    window.__TAURI__.event.listen('session-replaced', (e) => {
+      setStatus(false, 'Taken over by another app');
+      showToast(e.payload, 'error');
+    });
+    // A configured TV announced itself on the network again (mDNS)
+    window.__TAURI__.event.listen('tv-discovered', (e) => {
+      const { name, ip, address_changed } = e.payload;
+      if (address_changed) showToast(`${name} is now at ${ip}`, 'info');
+    });
+    // Presence monitor: is the TV on while we're not connected?
+    window.__TAURI__.event.listen('tv-presence-changed', (e) => {
+      tvPresence = e.payload === 'connected' ? null : e.payload;
+      if (!isConnected) setStatus(false);
+    });
+    // Background auto-reconnect succeeded
+    window.__TAURI__.event.listen('connection-restored', () => {
+      setStatus(true, 'Connected');
+    });
+  }
+}
+
+// Live volume/mute from the TV (including the physical remote)
+function showVolume(status) {
+  const el = document.getElementById('volume-level');
+  if (!status) {
+    el.textContent = '';
+    return;
+  }
+  const parts = [];
+  if (status.volume != null) parts.push(`Volume ${status.volume}`);
+  if (status.muted) parts.push('muted');
+  el.textContent = parts.join(' · ');
+}
+
+function listenVolumeChanged() {
+  if (window.__TAURI__ && window.__TAURI__.event) {
+    window.__TAURI__.event.listen('volume-changed', (e) => showVolume(e.payload));
+  }
+  invoke('get_volume_status').then(showVolume).catch(() => {});
+}
+
+// Settings changed outside this window's own edits (resize, hotkeys, config.json edited by
+// hand): refresh the snapshot so later saves don't start from stale values
+let configGeneration = 0;
+
+function listenConfigChanged() {
+  if (window.__TAURI__ && window.__TAURI__.event) {
+    window.__TAURI__.event.listen('config-changed', async (e) => {
+      if (e.payload <= configGeneration) return;
+      configGeneration = e.payload;
+      try {
+        config = await invoke('get_config');
+      } catch (err) {
+        console.error('Failed to reload config:', err);
+      }
+    });
+  }
+}
+
+// On Live TV, show the tuned channel next to the connection status. The channel
+// subscription only runs while Live TV is in the foreground.
+let onLiveTv = false;
+
+function showChannel(number, name) {
+  const label = [number, name].filter(Boolean).join(' ');
+  document.getElementById('status-text').textContent = label
+    ? `Ch ${label}`
+    : 'Connected';
+}
+
+function listenForegroundApp() {
+  if (window.__TAURI__ && window.__TAURI__.event) {
+    window.__TAURI__.event.listen('foreground-app-changed', async (e) => {
+      const liveTv = e.payload.app_id === 'com.webos.app.livetv';
+      if (liveTv === onLiveTv) return;
+      onLiveTv = liveTv;
+      if (!liveTv) {
+        if (isConnected) {
+          document.getElementById('status-text').textContent = 'Connected';
+        }
+        invoke('unsubscribe_event', { kind: 'channel' }).catch(() => {});
+        return;
+      }
+      try {
+        const channel = await invoke('get_current_channel');
+        showChannel(channel.number, channel.name);
+        await invoke('subscribe_event', { kind: 'channel' });
+      } catch (e) {
+        console.debug('get_current_channel failed:', e);
+      }
+    });
+    window.__TAURI__.event.listen('channel-changed', (e) => {
+      if (onLiveTv) showChannel(e.payload.channelNumber, e.payload.channelName);
+    });
+  }
+}
+
+// show_<panel> actions: open the requested view. Unknown panels just show the remote.
+const PANELS = {
+  settings: 'settings-panel',
+  shortcuts: 'shortcuts-panel',
+};
+
+function listenNavigate() {
+  if (window.__TAURI__ && window.__TAURI__.event) {
+    window.__TAURI__.event.listen('navigate', (e) => {
+      const id = PANELS[e.payload];
+      if (id) {
+        document.getElementById(id).classList.remove('collapsed');
+      }
+    });
+  }
+}
+
+// A per-TV shortcut switched the active TV: reload settings and connect to it
+function listenActiveTvChanged() {
+  if (window.__TAURI__ && window.__TAURI__.event) {
+    window.__TAURI__.event.listen('active-tv-changed', async () => {
+      setStatus(false, 'Disconnected');
+      await loadConfig();
+      if (hasConnectionInfo()) {
+        connectTv();
+      }
+    });
   }
 }
 